@@ -0,0 +1,352 @@
+//! Layered lookups over several `MultiDict` in the style of Python `ChainMap`.
+//!
+//! Layers are ordered from top to bottom: the first layer is searched first and
+//! receives all writes. A typical stack is CLI flags, environment, file config and
+//! defaults, pushed in reverse with [`ChainMultiDict::push_layer`].
+use std::fmt;
+
+use crate::metadata::ElementMeta;
+use crate::{MultiDict, MultiElement};
+
+/// How values of one key are collected from the layers
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ChainLookup {
+    /// Values of every layer, top layer first
+    #[default]
+    Merge,
+    /// Values of the topmost layer that has the key, lower layers are shadowed
+    TopLayer,
+}
+
+/// Stack of `MultiDict` layers, `layers[0]` is the top
+#[derive(Clone)]
+pub struct ChainMultiDict<M = ()> {
+    layers: Vec<MultiDict<M>>,
+}
+
+impl<M> Default for ChainMultiDict<M> {
+    fn default() -> Self {
+        ChainMultiDict {
+            layers: vec![MultiDict::default()],
+        }
+    }
+}
+
+impl<M: ElementMeta> fmt::Display for ChainMultiDict<M> {
+    /// `ChainMultiDict` instance formatter, layers are printed top first
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::chain::ChainMultiDict;
+    ///
+    /// let chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::from_query_string("a=2").unwrap(),
+    ///     MultiDict::from_query_string("a=1&b=1").unwrap(),
+    /// ]);
+    /// println!("{chain}");
+    /// // ChainMultiDict < MultiDict < "a":"2" >, MultiDict < "a":"1", "b":"1" > >
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ChainMultiDict < {} >",
+            self.layers
+                .iter()
+                .map(|layer| layer.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for ChainMultiDict<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainMultiDict")
+            .field("layers", &self.layers)
+            .finish()
+    }
+}
+
+impl ChainMultiDict {
+    /// Return chain with one empty layer
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::chain::ChainMultiDict;
+    ///
+    /// let chain = ChainMultiDict::new();
+    /// assert_eq!(chain.layers().len(), 1);
+    /// assert!(chain.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        ChainMultiDict::default()
+    }
+}
+
+impl<M> ChainMultiDict<M> {
+    /// Return chain of `layers` ordered from top to bottom, an empty list gives one empty layer
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::chain::ChainMultiDict;
+    ///
+    /// let chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::from_query_string("verbose=1").unwrap(),
+    ///     MultiDict::from_query_string("verbose=0&color=auto").unwrap(),
+    /// ]);
+    /// assert_eq!(chain.get("verbose").unwrap().value, "1");
+    /// assert_eq!(chain.get("color").unwrap().value, "auto");
+    /// ```
+    pub fn from_layers(layers: Vec<MultiDict<M>>) -> Self {
+        if layers.is_empty() {
+            return ChainMultiDict::default();
+        }
+        ChainMultiDict { layers }
+    }
+
+    /// Return layers ordered from top to bottom
+    pub fn layers(&self) -> &[MultiDict<M>] {
+        &self.layers
+    }
+
+    /// Return mutable layers ordered from top to bottom
+    pub fn layers_mut(&mut self) -> &mut [MultiDict<M>] {
+        &mut self.layers
+    }
+
+    /// Return top layer, which receives writes
+    pub fn top(&self) -> &MultiDict<M> {
+        &self.layers[0]
+    }
+
+    /// Return mutable top layer
+    pub fn top_mut(&mut self) -> &mut MultiDict<M> {
+        &mut self.layers[0]
+    }
+
+    /// Put `layer` on top of the stack
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::chain::ChainMultiDict;
+    ///
+    /// let mut chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::from_query_string("level=info").unwrap(), // defaults
+    /// ]);
+    /// chain.push_layer(MultiDict::from_query_string("level=warn").unwrap()); // config file
+    /// chain.push_layer(MultiDict::from_query_string("level=debug").unwrap()); // CLI flags
+    /// assert_eq!(chain.get("level").unwrap().value, "debug");
+    /// ```
+    pub fn push_layer(&mut self, layer: MultiDict<M>) {
+        self.layers.insert(0, layer);
+    }
+
+    /// Remove and return the top layer, the last layer is never removed
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::chain::ChainMultiDict;
+    ///
+    /// let mut chain = ChainMultiDict::new();
+    /// chain.push_layer(MultiDict::from_query_string("a=1").unwrap());
+    /// assert_eq!(chain.pop_layer().unwrap().to_string(), r#"MultiDict < "a":"1" >"#);
+    /// assert_eq!(chain.pop_layer().unwrap_err(), "Cannot remove the last layer");
+    /// ```
+    pub fn pop_layer(&mut self) -> Result<MultiDict<M>, &'static str> {
+        if self.layers.len() == 1 {
+            return Err("Cannot remove the last layer");
+        }
+        Ok(self.layers.remove(0))
+    }
+
+    /// Return the number of elements of all layers
+    pub fn len(&self) -> usize {
+        self.layers.iter().map(MultiDict::len).sum()
+    }
+
+    /// Return true if no layer has elements
+    pub fn is_empty(&self) -> bool {
+        self.layers.iter().all(MultiDict::is_empty)
+    }
+
+    /// Return the **first** element for key from the topmost layer that has it
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::chain::ChainMultiDict;
+    ///
+    /// let chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::from_query_string("b=top").unwrap(),
+    ///     MultiDict::from_query_string("a=1&a=2&b=bottom").unwrap(),
+    /// ]);
+    /// println!("{}", chain.get("a").unwrap());
+    /// // MultiElement < "a":"1" >
+    /// assert_eq!(chain.get("b").unwrap().value, "top");
+    /// assert_eq!(chain.get("c").unwrap_err(), "No matching key found");
+    /// ```
+    pub fn get(&self, key: &str) -> Result<&MultiElement<M>, &str> {
+        self.layers
+            .iter()
+            .find_map(|layer| layer.get(key).ok())
+            .ok_or("No matching key found")
+    }
+
+    /// Return True if any layer has a key, else False
+    pub fn contains(&self, key: &str) -> bool {
+        self.layers.iter().any(|layer| layer.contains(key))
+    }
+
+    /// Append element to the top layer
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::chain::ChainMultiDict;
+    ///
+    /// let mut chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::from_query_string("a=1").unwrap(),
+    /// ]);
+    /// chain.push_layer(MultiDict::new());
+    /// chain.add(MultiElement {
+    ///             key: "a".to_string(),
+    ///             value: "2".to_string(),
+    ///             meta: None,
+    ///         });
+    /// println!("{chain}");
+    /// // ChainMultiDict < MultiDict < "a":"2" >, MultiDict < "a":"1" > >
+    /// ```
+    pub fn add(&mut self, new_item: MultiElement<M>) {
+        self.top_mut().add(new_item);
+    }
+
+    /// Remove and return the **first** element for key from the top layer, lower layers
+    /// are never changed
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::chain::ChainMultiDict;
+    ///
+    /// let mut chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::from_query_string("a=2").unwrap(),
+    ///     MultiDict::from_query_string("a=1").unwrap(),
+    /// ]);
+    /// assert_eq!(chain.popone("a").unwrap().value, "2");
+    /// assert_eq!(chain.popone("a").unwrap_err(), "No matching key found");
+    /// assert_eq!(chain.get("a").unwrap().value, "1");
+    /// ```
+    pub fn popone(&mut self, key: &str) -> Result<MultiElement<M>, &str> {
+        self.layers[0].popone(key)
+    }
+}
+
+impl<M: Clone> ChainMultiDict<M> {
+    /// Set all values of key in the top layer, lower layers are shadowed by it
+    ///
+    /// Existing top layer elements are updated in place, when the top layer has no such
+    /// key the element is appended to it.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::chain::{ChainLookup, ChainMultiDict};
+    ///
+    /// let mut chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::new(),
+    ///     MultiDict::from_query_string("a=1&a=2").unwrap(),
+    /// ]);
+    /// chain.update(MultiElement {
+    ///             key: "a".to_string(),
+    ///             value: "3".to_string(),
+    ///             meta: None,
+    ///         });
+    /// println!("{}", chain.getall("a", ChainLookup::TopLayer).unwrap());
+    /// // MultiDict < "a":"3" >
+    /// ```
+    pub fn update(&mut self, new_item: MultiElement<M>) {
+        let top = self.top_mut();
+        if top.contains(&new_item.key) {
+            top.update(new_item);
+        } else {
+            top.add(new_item);
+        }
+    }
+
+    /// Return all elements for key collected according to `lookup`,
+    /// the result has the top layer redaction policy
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::chain::{ChainLookup, ChainMultiDict};
+    ///
+    /// let chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::from_query_string("b=1").unwrap(),
+    ///     MultiDict::from_query_string("tag=x&tag=y").unwrap(),
+    ///     MultiDict::from_query_string("tag=default").unwrap(),
+    /// ]);
+    /// println!("{}", chain.getall("tag", ChainLookup::Merge).unwrap());
+    /// // MultiDict < "tag":"x", "tag":"y", "tag":"default" >
+    /// println!("{}", chain.getall("tag", ChainLookup::TopLayer).unwrap());
+    /// // MultiDict < "tag":"x", "tag":"y" >
+    /// assert_eq!(chain.getall("c", ChainLookup::Merge).unwrap_err(), "No matching key found");
+    /// ```
+    pub fn getall(&self, key: &str, lookup: ChainLookup) -> Result<MultiDict<M>, &str> {
+        let mut results = MultiDict::default();
+        results.set_redaction(self.top().redaction().clone());
+        for layer in &self.layers {
+            let Ok(found) = layer.getall(key) else {
+                continue;
+            };
+            results.elements.extend(found.elements);
+            if lookup == ChainLookup::TopLayer {
+                break;
+            }
+        }
+        if results.is_empty() {
+            Err("No matching key found")
+        } else {
+            Ok(results)
+        }
+    }
+
+    /// Return single `MultiDict` with the same lookups as the chain
+    ///
+    /// Keys are ordered by first occurrence from the top layer down, values of each key
+    /// are collected as by [`ChainMultiDict::getall`] with the same `lookup`.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::chain::{ChainLookup, ChainMultiDict};
+    ///
+    /// let chain = ChainMultiDict::from_layers(vec![
+    ///     MultiDict::from_query_string("port=8080&tag=cli").unwrap(),
+    ///     MultiDict::from_query_string("host=localhost&port=80&tag=a&tag=b").unwrap(),
+    /// ]);
+    /// println!("{}", chain.flatten(ChainLookup::TopLayer));
+    /// // MultiDict < "port":"8080", "tag":"cli", "host":"localhost" >
+    /// println!("{}", chain.flatten(ChainLookup::Merge));
+    /// // MultiDict < "port":"8080", "port":"80", "tag":"cli", "tag":"a", "tag":"b", "host":"localhost" >
+    /// ```
+    pub fn flatten(&self, lookup: ChainLookup) -> MultiDict<M> {
+        let mut results = MultiDict::default();
+        results.set_redaction(self.top().redaction().clone());
+        let mut seen: Vec<&str> = Vec::new();
+        for item in self.layers.iter().flat_map(MultiDict::iter) {
+            if seen.contains(&item.key.as_str()) {
+                continue;
+            }
+            seen.push(&item.key);
+            if let Ok(found) = self.getall(&item.key, lookup) {
+                results.elements.extend(found.elements);
+            }
+        }
+        results
+    }
+}
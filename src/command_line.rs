@@ -0,0 +1,708 @@
+//! `curl` and HTTPie command lines built from, and parsed into, `MultiDict` request parts.
+//!
+//! Arguments are quoted for POSIX shells. Repeated headers, query parameters and
+//! form fields are written as separate arguments, so their order is kept.
+use std::fmt;
+
+use crate::metadata::{ElementMeta, Span};
+use crate::{digest, percent, MultiDict, MultiElement};
+
+/// Request described by a command line, `form` is an `application/x-www-form-urlencoded` body
+#[derive(Clone)]
+pub struct HttpCommand<M = ()> {
+    pub method: String,
+    pub url: String,
+    pub headers: MultiDict<M>,
+    pub query: Option<MultiDict<M>>,
+    pub form: Option<MultiDict<M>>,
+}
+
+impl<M> Default for HttpCommand<M> {
+    fn default() -> Self {
+        HttpCommand {
+            method: String::new(),
+            url: String::new(),
+            headers: MultiDict::default(),
+            query: None,
+            form: None,
+        }
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for HttpCommand<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpCommand")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("headers", &self.headers)
+            .field("query", &self.query)
+            .field("form", &self.form)
+            .finish()
+    }
+}
+
+/// Return `word` quoted for a POSIX shell, words of safe characters are kept bare
+fn shell_quote(word: &str) -> String {
+    let safe = |byte: u8| byte.is_ascii_alphanumeric() || b"_@%+=:,./-".contains(&byte);
+    if !word.is_empty() && word.bytes().all(safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Return `key=value` with both sides form encoded
+fn form_pair(key: &str, value: &str) -> String {
+    format!(
+        "{}={}",
+        percent::encode(key, percent::is_unreserved, true),
+        percent::encode(value, percent::is_unreserved, true)
+    )
+}
+
+/// Escape HTTPie item separators in `key` and at the start of `value`
+fn httpie_item(key: &str, separator: &str, value: &str) -> String {
+    let mut item = String::new();
+    for character in key.chars() {
+        if matches!(character, '\\' | ':' | '=' | '@' | ';') {
+            item.push('\\');
+        }
+        item.push(character);
+    }
+    if value.is_empty() && separator == ":" {
+        item.push(';');
+        return item;
+    }
+    item.push_str(separator);
+    if value.starts_with(['=', '@', ':']) {
+        item.push('\\');
+    }
+    item.push_str(value);
+    item
+}
+
+/// Split command line into words following POSIX shell quoting, including `$'...'`,
+/// each with the span of its text in `input`
+fn split_words(input: &str) -> Result<Vec<(String, Span)>, &'static str> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut start = 0;
+    let mut chars = input.chars();
+    loop {
+        let position = input.len() - chars.as_str().len();
+        let Some(character) = chars.next() else {
+            break;
+        };
+        if !in_word {
+            start = position;
+        }
+        match character {
+            ' ' | '\t' | '\n' | '\r' => {
+                if in_word {
+                    let span = Span {
+                        start,
+                        end: position,
+                    };
+                    words.push((std::mem::take(&mut word), span));
+                    in_word = false;
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') if chars.clone().next() == Some('\n') => {
+                    chars.next();
+                }
+                Some(escaped) => {
+                    word.push(escaped);
+                    in_word = true;
+                }
+                None => return Err("Trailing backslash in command line"),
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(quoted) => word.push(quoted),
+                        None => return Err("Unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '\\' | '$' | '`')) => word.push(escaped),
+                            Some('\n') => {}
+                            Some(other) => {
+                                word.push('\\');
+                                word.push(other);
+                            }
+                            None => return Err("Unterminated double quote"),
+                        },
+                        Some(quoted) => word.push(quoted),
+                        None => return Err("Unterminated double quote"),
+                    }
+                }
+            }
+            '$' if chars.clone().next() == Some('\'') => {
+                chars.next();
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => {
+                            let escaped = match chars.next() {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some('r') => '\r',
+                                Some('a') => '\u{7}',
+                                Some('b') => '\u{8}',
+                                Some('e' | 'E') => '\u{1b}',
+                                Some('f') => '\u{c}',
+                                Some('v') => '\u{b}',
+                                Some(digits @ ('x' | 'u' | 'U')) => {
+                                    let limit = match digits {
+                                        'x' => 2,
+                                        'u' => 4,
+                                        _ => 8,
+                                    };
+                                    let mut code = 0;
+                                    let mut count = 0;
+                                    while count < limit {
+                                        let Some(digit) =
+                                            chars.clone().next().and_then(|c| c.to_digit(16))
+                                        else {
+                                            break;
+                                        };
+                                        code = code * 16 + digit;
+                                        count += 1;
+                                        chars.next();
+                                    }
+                                    if count == 0 {
+                                        return Err("Invalid escape in $'...' string");
+                                    }
+                                    char::from_u32(code).ok_or("Invalid escape in $'...' string")?
+                                }
+                                Some(other @ ('\\' | '\'' | '"' | '?')) => other,
+                                Some(other) => {
+                                    word.push('\\');
+                                    other
+                                }
+                                None => return Err("Unterminated $'...' string"),
+                            };
+                            word.push(escaped);
+                        }
+                        Some(quoted) => word.push(quoted),
+                        None => return Err("Unterminated $'...' string"),
+                    }
+                }
+            }
+            _ => {
+                word.push(character);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        let span = Span {
+            start,
+            end: input.len(),
+        };
+        words.push((word, span));
+    }
+    Ok(words)
+}
+
+/// Return long name of a short curl option and whether the option takes an argument
+fn short_option(option: char) -> Option<(&'static str, bool)> {
+    let long = match option {
+        'X' => ("request", true),
+        'H' => ("header", true),
+        'd' => ("data", true),
+        'A' => ("user-agent", true),
+        'e' => ("referer", true),
+        'b' => ("cookie", true),
+        'u' => ("user", true),
+        'F' => ("form", true),
+        'o' => ("output", true),
+        'm' => ("max-time", true),
+        'x' => ("proxy", true),
+        'w' => ("write-out", true),
+        'G' => ("get", false),
+        'I' => ("head", false),
+        's' => ("silent", false),
+        'S' => ("show-error", false),
+        'k' => ("insecure", false),
+        'L' => ("location", false),
+        'v' => ("verbose", false),
+        'i' => ("include", false),
+        'f' => ("fail", false),
+        'N' => ("no-buffer", false),
+        'g' => ("globoff", false),
+        _ => return None,
+    };
+    Some(long)
+}
+
+/// Return whether long curl option takes an argument, `None` for unsupported options
+fn long_option(option: &str) -> Option<bool> {
+    match option {
+        "request" | "header" | "data" | "data-raw" | "data-ascii" | "data-binary"
+        | "data-urlencode" | "url" | "user-agent" | "referer" | "cookie" | "user" | "form"
+        | "form-string" | "output" | "max-time" | "connect-timeout" | "retry" | "proxy"
+        | "cacert" | "cert" | "key" | "resolve" | "write-out" | "max-redirs" => Some(true),
+        "get"
+        | "head"
+        | "compressed"
+        | "silent"
+        | "show-error"
+        | "insecure"
+        | "location"
+        | "verbose"
+        | "include"
+        | "fail"
+        | "no-buffer"
+        | "globoff"
+        | "http1.1"
+        | "http2"
+        | "http2-prior-knowledge"
+        | "raw" => Some(false),
+        _ => None,
+    }
+}
+
+/// Options collected from a curl command line, arguments with the span of their word
+struct CurlOptions<M> {
+    method: Option<String>,
+    url: Option<(String, Span)>,
+    headers: MultiDict<M>,
+    data: Vec<(String, Span)>,
+    get: bool,
+    head: bool,
+}
+
+impl<M: ElementMeta> CurlOptions<M> {
+    fn header(&mut self, key: &str, value: &str, span: Span) {
+        self.headers.add(MultiElement {
+            key: key.to_string(),
+            value: value.to_string(),
+            meta: M::from_span(span),
+        });
+    }
+
+    fn apply(&mut self, option: &str, argument: String, span: Span) -> Result<(), &'static str> {
+        match option {
+            "request" => self.method = Some(argument),
+            "header" => {
+                if let Some((key, value)) = argument.split_once(':') {
+                    // "Name:" removes a header in curl, there is nothing to remove here
+                    if !value.trim().is_empty() {
+                        self.header(key.trim(), value.trim(), span);
+                    }
+                } else if let Some(key) = argument.strip_suffix(';') {
+                    self.header(key.trim(), "", span);
+                } else {
+                    return Err("Invalid curl header argument");
+                }
+            }
+            "data" | "data-ascii" | "data-binary" if argument.starts_with('@') => {
+                return Err("Reading data from files is not supported");
+            }
+            "data" | "data-ascii" | "data-binary" | "data-raw" => self.data.push((argument, span)),
+            "data-urlencode" => {
+                let encode = |content: &str| percent::encode(content, percent::is_unreserved, true);
+                let (name, content) = match argument.split_once('=') {
+                    Some((name, content)) => (name, content),
+                    None if argument.contains('@') => {
+                        return Err("Reading data from files is not supported");
+                    }
+                    None => ("", argument.as_str()),
+                };
+                if name.contains('@') {
+                    return Err("Reading data from files is not supported");
+                }
+                let data = match name {
+                    "" => encode(content),
+                    _ => format!("{name}={}", encode(content)),
+                };
+                self.data.push((data, span));
+            }
+            "url" => self.set_url(argument, span)?,
+            "user-agent" => self.header("User-Agent", &argument, span),
+            "referer" => self.header("Referer", &argument, span),
+            "cookie" if !argument.contains('=') => {
+                return Err("Reading cookies from files is not supported");
+            }
+            "cookie" => self.header("Cookie", &argument, span),
+            "user" if !argument.contains(':') => {
+                return Err("Password prompt is not supported");
+            }
+            "user" => {
+                let credentials = digest::base64_encode(argument.as_bytes());
+                self.header("Authorization", &format!("Basic {credentials}"), span);
+            }
+            "form" | "form-string" => return Err("Multipart form fields are not supported"),
+            "get" => self.get = true,
+            "head" => self.head = true,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn set_url(&mut self, url: String, span: Span) -> Result<(), &'static str> {
+        if self.url.is_some() {
+            return Err("Multiple URLs are not supported");
+        }
+        self.url = Some((url, span));
+        Ok(())
+    }
+}
+
+impl HttpCommand {
+    /// Return command for `method` and `url` without query or form
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::command_line::HttpCommand;
+    ///
+    /// let command = HttpCommand::new("GET", "https://example.com/", MultiDict::new());
+    /// assert_eq!(command.to_curl(), "curl https://example.com/");
+    /// ```
+    pub fn new(method: &str, url: &str, headers: MultiDict) -> Self {
+        HttpCommand {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers,
+            query: None,
+            form: None,
+        }
+    }
+
+    /// Return URL with the query parameters appended
+    pub fn full_url(&self) -> String {
+        match &self.query {
+            Some(query) if !query.is_empty() => {
+                let separator = if self.url.contains('?') { '&' } else { '?' };
+                format!("{}{separator}{}", self.url, query.to_query_string())
+            }
+            _ => self.url.clone(),
+        }
+    }
+
+    /// Return copy with values redacted under the policy of each `MultiDict`
+    fn redacted(&self) -> HttpCommand {
+        HttpCommand {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            headers: self.headers.redacted(),
+            query: self.query.as_ref().map(MultiDict::redacted),
+            form: self.form.as_ref().map(MultiDict::redacted),
+        }
+    }
+
+    /// Return `curl` command line, values follow the redaction policy of each `MultiDict`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::command_line::HttpCommand;
+    ///
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: "Accept".to_string(),
+    ///             value: "*/*".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "X-Note".to_string(),
+    ///             value: "it's".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut form = MultiDict::new();
+    /// form.add(MultiElement {
+    ///             key: "tag".to_string(),
+    ///             value: "a b".to_string(),
+    ///             meta: None,
+    ///         });
+    /// form.add(MultiElement {
+    ///             key: "tag".to_string(),
+    ///             value: "c&d".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut command = HttpCommand::new("POST", "https://example.com/items", headers);
+    /// command.form = Some(form);
+    /// println!("{}", command.to_curl());
+    /// assert_eq!(command.to_curl(),
+    ///            "curl https://example.com/items -H 'Accept: */*' -H 'X-Note: it'\\''s' \
+    ///             --data-raw tag=a+b --data-raw tag=c%26d");
+    /// ```
+    pub fn to_curl(&self) -> String {
+        let command = self.redacted();
+        let mut words = vec!["curl".to_string()];
+        let implied = if command.form.is_some() {
+            "POST"
+        } else {
+            "GET"
+        };
+        if command.method.eq_ignore_ascii_case("HEAD") {
+            words.push("--head".to_string());
+        } else if !command.method.eq_ignore_ascii_case(implied) {
+            words.push("-X".to_string());
+            words.push(shell_quote(&command.method));
+        }
+        words.push(shell_quote(&command.full_url()));
+        for item in command.headers.iter() {
+            let header = if item.value.is_empty() {
+                format!("{};", item.key)
+            } else {
+                format!("{}: {}", item.key, item.value)
+            };
+            words.push("-H".to_string());
+            words.push(shell_quote(&header));
+        }
+        for item in command.form.iter().flat_map(MultiDict::iter) {
+            words.push("--data-raw".to_string());
+            words.push(shell_quote(&form_pair(&item.key, &item.value)));
+        }
+        words.join(" ")
+    }
+
+    /// Return HTTPie command line, query parameters are written as `name==value` items.
+    /// Values follow the redaction policy of each `MultiDict`.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::command_line::HttpCommand;
+    ///
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: "X-Empty".to_string(),
+    ///             value: "".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut query = MultiDict::new();
+    /// query.add(MultiElement {
+    ///             key: "page".to_string(),
+    ///             value: "1".to_string(),
+    ///             meta: None,
+    ///         });
+    /// query.add(MultiElement {
+    ///             key: "page".to_string(),
+    ///             value: "2".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut form = MultiDict::new();
+    /// form.add(MultiElement {
+    ///             key: "file".to_string(),
+    ///             value: "@not-a-file".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut command = HttpCommand::new("PUT", "https://example.com/", headers);
+    /// command.query = Some(query);
+    /// command.form = Some(form);
+    /// assert_eq!(command.to_httpie(),
+    ///            "http --form PUT https://example.com/ 'X-Empty;' page==1 page==2 'file=\\@not-a-file'");
+    /// ```
+    pub fn to_httpie(&self) -> String {
+        let command = self.redacted();
+        let mut words = vec!["http".to_string()];
+        if command.form.is_some() {
+            words.push("--form".to_string());
+        }
+        words.push(shell_quote(&command.method.to_uppercase()));
+        words.push(shell_quote(&command.url));
+        for item in command.headers.iter() {
+            words.push(shell_quote(&httpie_item(&item.key, ":", &item.value)));
+        }
+        for item in command.query.iter().flat_map(MultiDict::iter) {
+            words.push(shell_quote(&httpie_item(&item.key, "==", &item.value)));
+        }
+        for item in command.form.iter().flat_map(MultiDict::iter) {
+            words.push(shell_quote(&httpie_item(&item.key, "=", &item.value)));
+        }
+        words.join(" ")
+    }
+
+    /// Parse `curl` command line, the URL query is split into `query`.
+    ///
+    /// Request bodies must be form encoded; options reading files, multipart forms
+    /// and options not affecting the request are rejected or ignored.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::command_line::HttpCommand;
+    ///
+    /// let command = HttpCommand::from_curl(
+    ///     "curl 'https://example.com/search?q=rust&q=http' \\\n  -H 'Accept: text/html' \
+    ///      -H $'X-Quote: it\\'s' --compressed -sS --data-urlencode 'note=a&b' -d tag=x",
+    /// ).unwrap();
+    /// assert_eq!(command.method, "POST");
+    /// assert_eq!(command.url, "https://example.com/search");
+    /// println!("{}", command.query.as_ref().unwrap());
+    /// // MultiDict < "q":"rust", "q":"http" >
+    /// assert_eq!(command.headers.get("X-Quote").unwrap().value, "it's");
+    /// let form = command.form.unwrap();
+    /// assert_eq!(form.get("note").unwrap().value, "a&b");
+    /// assert_eq!(form.keys(), vec!["note", "tag"]);
+    ///
+    /// assert_eq!(HttpCommand::from_curl("curl -F a=1 https://example.com").unwrap_err(),
+    ///            "Multipart form fields are not supported");
+    /// ```
+    pub fn from_curl(command: &str) -> Result<HttpCommand, &'static str> {
+        parse_curl(command)
+    }
+}
+
+impl<M: ElementMeta> HttpCommand<M> {
+    /// Parse `curl` command line like [`HttpCommand::from_curl`], every element gets
+    /// metadata for the byte range of the word it comes from: the header option argument,
+    /// the URL for query parameters or the data option argument for form fields
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::command_line::HttpCommand;
+    /// use multidict::metadata::Span;
+    ///
+    /// let line = "curl -H 'Max-Forwards: ten' 'https://example.com/?page=2' -d a=1";
+    /// let command: HttpCommand<Span> = HttpCommand::from_curl_spanned(line).unwrap();
+    /// let header = command.headers.get("Max-Forwards").unwrap();
+    /// assert_eq!(header.meta.unwrap().slice(line), "'Max-Forwards: ten'");
+    /// let page = command.query.unwrap().get("page").unwrap().meta.unwrap();
+    /// assert_eq!(page.slice(line), "'https://example.com/?page=2'");
+    /// ```
+    pub fn from_curl_spanned(command: &str) -> Result<HttpCommand<M>, &'static str> {
+        parse_curl(command)
+    }
+}
+
+/// Parse curl command line, metadata is built from the span of the word of each element
+fn parse_curl<M: ElementMeta>(command: &str) -> Result<HttpCommand<M>, &'static str> {
+    let mut words = split_words(command)?.into_iter();
+    match words.next() {
+        Some((program, _)) if program == "curl" || program.ends_with("/curl") => {}
+        _ => return Err("Command line must start with curl"),
+    }
+    let mut options = CurlOptions {
+        method: None,
+        url: None,
+        headers: MultiDict::default(),
+        data: Vec::new(),
+        get: false,
+        head: false,
+    };
+    let mut positional_only = false;
+    while let Some((word, span)) = words.next() {
+        if positional_only || word == "-" || !word.starts_with('-') {
+            options.set_url(word, span)?;
+        } else if word == "--" {
+            positional_only = true;
+        } else if let Some(option) = word.strip_prefix("--") {
+            match long_option(option) {
+                Some(true) => {
+                    let (argument, span) = words.next().ok_or("Missing curl option argument")?;
+                    options.apply(option, argument, span)?;
+                }
+                Some(false) => options.apply(option, String::new(), span)?,
+                None => return Err("Unsupported curl option"),
+            }
+        } else {
+            let flags = &word[1..];
+            for (idx, flag) in flags.char_indices() {
+                let Some((option, takes_argument)) = short_option(flag) else {
+                    return Err("Unsupported curl option");
+                };
+                if !takes_argument {
+                    options.apply(option, String::new(), span)?;
+                    continue;
+                }
+                let rest = &flags[idx + flag.len_utf8()..];
+                let (argument, span) = if rest.is_empty() {
+                    words.next().ok_or("Missing curl option argument")?
+                } else {
+                    (rest.to_string(), span)
+                };
+                options.apply(option, argument, span)?;
+                break;
+            }
+        }
+    }
+
+    let (url, url_span) = options.url.ok_or("Missing URL in curl command")?;
+    let url = url.split('#').next().unwrap_or_default();
+    let (url, mut query) = match url.split_once('?') {
+        Some((base, query)) => (
+            base.to_string(),
+            Some(with_spans(&[(query.to_string(), url_span)])?),
+        ),
+        None => (url.to_string(), None),
+    };
+    let mut form = None;
+    if !options.data.is_empty() {
+        let data = with_spans(&options.data)?;
+        if options.get {
+            let query = query.get_or_insert_with(MultiDict::default);
+            for item in data.elements {
+                query.add(item);
+            }
+        } else {
+            let content_type = options
+                .headers
+                .iter()
+                .find(|item| item.key.eq_ignore_ascii_case("content-type"));
+            if content_type.is_some_and(|item| {
+                !item
+                    .value
+                    .to_ascii_lowercase()
+                    .starts_with("application/x-www-form-urlencoded")
+            }) {
+                return Err("Only form encoded bodies are supported");
+            }
+            form = Some(data);
+        }
+    }
+    let method = match options.method {
+        Some(method) => method,
+        None if options.head => "HEAD".to_string(),
+        None if form.is_some() => "POST".to_string(),
+        None => "GET".to_string(),
+    };
+    Ok(HttpCommand {
+        method,
+        url,
+        headers: options.headers,
+        query,
+        form,
+    })
+}
+
+/// Parse query string of `words` joined with `&`, every element gets the span of the
+/// word its pair starts in
+fn with_spans<M: ElementMeta>(words: &[(String, Span)]) -> Result<MultiDict<M>, &'static str> {
+    let query = words
+        .iter()
+        .map(|(word, _)| word.as_str())
+        .collect::<Vec<_>>()
+        .join("&");
+    let pairs: MultiDict<Span> = MultiDict::from_query_string_spanned(&query)?;
+    let mut results = MultiDict::default();
+    for item in pairs.elements {
+        let start = item.meta.map_or(0, |span| span.start);
+        let mut end = 0;
+        let span = words.iter().find_map(|(word, span)| {
+            end += word.len() + 1;
+            (start < end).then_some(*span)
+        });
+        results.add(MultiElement {
+            key: item.key,
+            value: item.value,
+            meta: span.and_then(M::from_span),
+        });
+    }
+    Ok(results)
+}
@@ -0,0 +1,161 @@
+//! Debian control files (deb822): stanzas of `Field: value` lines.
+//!
+//! Stanzas are separated by blank lines and each one maps onto a `MultiDict`. Lines
+//! starting with whitespace continue the previous field; a field value keeps one line
+//! per source line, with the leading space of continuation lines removed and ` .`
+//! standing for an empty line. Lines starting with `#` are comments and are skipped.
+use crate::metadata::{ElementMeta, Span};
+use crate::{MultiDict, MultiElement};
+
+/// Return true if `name` is a valid field name
+fn is_field_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(['#', '-'])
+        && name
+            .bytes()
+            .all(|byte| (0x21..0x7f).contains(&byte) && byte != b':')
+}
+
+/// Parse all stanzas of a control file
+///
+/// # Examples
+/// ```
+/// use multidict::deb822::parse_stanzas;
+///
+/// let control = "Source: hello\nMaintainer: Jane <jane@example.com>\n\n\
+///                Package: hello\nDepends: libc6 (>= 2.34),\n libgreeting1\n\
+///                Description: greet the world\n Prints a greeting.\n .\n Then exits.\n";
+/// let stanzas = parse_stanzas(control).unwrap();
+/// assert_eq!(stanzas.len(), 2);
+/// assert_eq!(stanzas[1].get("Depends").unwrap().value, "libc6 (>= 2.34),\nlibgreeting1");
+/// assert_eq!(stanzas[1].get("Description").unwrap().value,
+///            "greet the world\nPrints a greeting.\n\nThen exits.");
+/// ```
+pub fn parse_stanzas(input: &str) -> Result<Vec<MultiDict>, &'static str> {
+    parse_stanzas_spanned(input)
+}
+
+/// Parse all stanzas like [`parse_stanzas`], every field gets metadata for the byte range
+/// of its lines including continuation lines
+///
+/// # Examples
+/// ```
+/// use multidict::deb822::parse_stanzas_spanned;
+/// use multidict::metadata::Span;
+/// use multidict::MultiDict;
+///
+/// let control = "Package: hello\nPriority: urgent\n";
+/// let stanzas: Vec<MultiDict<Span>> = parse_stanzas_spanned(control).unwrap();
+/// let priority = stanzas[0].get("Priority").unwrap();
+/// assert_eq!(priority.meta.unwrap().slice(control), "Priority: urgent");
+/// ```
+pub fn parse_stanzas_spanned<M: ElementMeta>(
+    input: &str,
+) -> Result<Vec<MultiDict<M>>, &'static str> {
+    let mut stanzas = Vec::new();
+    // fields of the current stanza, metadata is built once their span is complete
+    let mut current: Vec<(String, String, Span)> = Vec::new();
+    let mut start = 0;
+    for line in input.split('\n') {
+        let line_start = start;
+        start += line.len() + 1;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                stanzas.push(stanza(std::mem::take(&mut current)));
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let end = line_start + line.len();
+        if line.starts_with([' ', '\t']) {
+            let (_, value, span) = current
+                .last_mut()
+                .ok_or("Control continuation line outside a field")?;
+            let line = line[1..].trim_end();
+            value.push('\n');
+            value.push_str(if line == "." { "" } else { line });
+            span.end = end;
+            continue;
+        }
+        let (name, value) = line.split_once(':').ok_or("Control field has no colon")?;
+        if !is_field_name(name) {
+            return Err("Invalid control field name");
+        }
+        current.push((
+            name.to_string(),
+            value.trim().to_string(),
+            Span {
+                start: line_start,
+                end,
+            },
+        ));
+    }
+    if !current.is_empty() {
+        stanzas.push(stanza(current));
+    }
+    Ok(stanzas)
+}
+
+/// Return stanza of parsed fields with metadata built from their spans
+fn stanza<M: ElementMeta>(fields: Vec<(String, String, Span)>) -> MultiDict<M> {
+    let mut results = MultiDict::default();
+    for (key, value, span) in fields {
+        results.add(MultiElement {
+            key,
+            value,
+            meta: M::from_span(span),
+        });
+    }
+    results
+}
+
+/// Return control file of `stanzas`, separated by blank lines
+pub fn write_stanzas(stanzas: &[MultiDict]) -> Result<String, &'static str> {
+    let mut out = Vec::with_capacity(stanzas.len());
+    for stanza in stanzas {
+        out.push(stanza.to_deb822()?);
+    }
+    Ok(out.join("\n"))
+}
+
+impl MultiDict {
+    /// Return elements as one control file stanza, multi-line values are written as
+    /// continuation lines
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::deb822::parse_stanzas;
+    ///
+    /// let stanza = &parse_stanzas("Files:\n 0123 12 a.dsc\n 4567 34 a.tar.xz\n").unwrap()[0];
+    /// assert_eq!(stanza.get("Files").unwrap().value, "\n0123 12 a.dsc\n4567 34 a.tar.xz");
+    /// assert_eq!(stanza.to_deb822().unwrap(), "Files:\n 0123 12 a.dsc\n 4567 34 a.tar.xz\n");
+    /// ```
+    pub fn to_deb822(&self) -> Result<String, &'static str> {
+        let mut out = String::new();
+        for item in self.iter() {
+            if !is_field_name(&item.key) {
+                return Err("Invalid control field name");
+            }
+            let mut lines = item.value.split('\n');
+            out.push_str(&item.key);
+            out.push(':');
+            match lines.next() {
+                Some("") | None => {}
+                Some(first) => {
+                    out.push(' ');
+                    out.push_str(first);
+                }
+            }
+            out.push('\n');
+            for line in lines {
+                out.push_str(if line.is_empty() { " ." } else { " " });
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        Ok(out)
+    }
+}
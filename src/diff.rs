@@ -0,0 +1,482 @@
+//! Element-level diff and patch between two `MultiDict`.
+//!
+//! [`diff`] aligns both maps on their longest common subsequence of `(key, value)`
+//! pairs. Changes are ordered and their indexes refer to the map as changed by all
+//! previous changes, like the operations of an RFC 6902 JSON Patch. A removal followed
+//! by an insertion of the same key is reported as a modification.
+use crate::json::Json;
+use crate::metadata::ElementMeta;
+use crate::redaction::REDACTED;
+use crate::{MultiDict, MultiElement};
+
+/// One change of a [`Patch`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Change {
+    /// Element inserted at `index`
+    Insert {
+        index: usize,
+        key: String,
+        value: String,
+    },
+    /// Element removed from `index`
+    Remove {
+        index: usize,
+        key: String,
+        value: String,
+    },
+    /// Value of the element at `index` changed, its key is kept
+    Modify {
+        index: usize,
+        key: String,
+        old_value: String,
+        new_value: String,
+    },
+}
+
+/// Ordered list of changes turning one `MultiDict` into another
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Patch {
+    pub changes: Vec<Change>,
+    /// Indexes of changes with an element redacted in the diffed maps
+    redacted: Vec<usize>,
+}
+
+/// Alignment step of two element lists
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Step {
+    Keep(usize, usize),
+    Remove(usize),
+    Insert(usize),
+}
+
+/// Return alignment of `a` and `b` on their longest common subsequence,
+/// removals come before insertions between kept elements
+fn align<M>(a: &MultiDict<M>, b: &MultiDict<M>) -> Vec<Step> {
+    let same = |i: usize, j: usize| {
+        a.elements[i].key == b.elements[j].key && a.elements[i].value == b.elements[j].value
+    };
+    let (n, m) = (a.len(), b.len());
+    // lengths[i][j] is the LCS length of a[i..] and b[j..]
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if same(i, j) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut steps = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && same(i, j) {
+            steps.push(Step::Keep(i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            steps.push(Step::Remove(i));
+            i += 1;
+        } else {
+            steps.push(Step::Insert(j));
+            j += 1;
+        }
+    }
+    steps
+}
+
+/// Return changes turning `a` into `b`, metadata is not compared.
+/// Changes of elements redacted by the policy of their map are hidden in JSON output.
+///
+/// # Examples
+/// ```
+/// use multidict::MultiDict;
+/// use multidict::diff::{diff, Change};
+///
+/// let incoming = MultiDict::from_query_string("host=a&accept=json&x-debug=1").unwrap();
+/// let outgoing = MultiDict::from_query_string("host=b&accept=json&via=proxy").unwrap();
+/// let patch = diff(&incoming, &outgoing);
+/// assert_eq!(patch.changes, vec![
+///     Change::Modify { index: 0, key: "host".to_string(),
+///                      old_value: "a".to_string(), new_value: "b".to_string() },
+///     Change::Remove { index: 2, key: "x-debug".to_string(), value: "1".to_string() },
+///     Change::Insert { index: 2, key: "via".to_string(), value: "proxy".to_string() },
+/// ]);
+/// ```
+pub fn diff<M: ElementMeta>(a: &MultiDict<M>, b: &MultiDict<M>) -> Patch {
+    let steps = align(a, b);
+    let mut changes = Vec::new();
+    let mut redacted = Vec::new();
+    let mut index = 0;
+    let mut start = 0;
+    while start < steps.len() {
+        if let Step::Keep(..) = steps[start] {
+            index += 1;
+            start += 1;
+            continue;
+        }
+        let end = steps[start..]
+            .iter()
+            .position(|step| matches!(step, Step::Keep(..)))
+            .map_or(steps.len(), |offset| start + offset);
+        let removed: Vec<&MultiElement<M>> = steps[start..end]
+            .iter()
+            .filter_map(|step| match step {
+                Step::Remove(i) => Some(&a.elements[*i]),
+                _ => None,
+            })
+            .collect();
+        let inserted: Vec<&MultiElement<M>> = steps[start..end]
+            .iter()
+            .filter_map(|step| match step {
+                Step::Insert(j) => Some(&b.elements[*j]),
+                _ => None,
+            })
+            .collect();
+        let mut next = 0;
+        for old in removed {
+            match inserted.get(next) {
+                Some(new) if new.key == old.key => {
+                    if a.is_redacted(old) || b.is_redacted(new) {
+                        redacted.push(changes.len());
+                    }
+                    changes.push(Change::Modify {
+                        index,
+                        key: old.key.clone(),
+                        old_value: old.value.clone(),
+                        new_value: new.value.clone(),
+                    });
+                    index += 1;
+                    next += 1;
+                }
+                _ => {
+                    if a.is_redacted(old) {
+                        redacted.push(changes.len());
+                    }
+                    changes.push(Change::Remove {
+                        index,
+                        key: old.key.clone(),
+                        value: old.value.clone(),
+                    });
+                }
+            }
+        }
+        for new in &inserted[next..] {
+            if b.is_redacted(new) {
+                redacted.push(changes.len());
+            }
+            changes.push(Change::Insert {
+                index,
+                key: new.key.clone(),
+                value: new.value.clone(),
+            });
+            index += 1;
+        }
+        start = end;
+    }
+    Patch { changes, redacted }
+}
+
+/// Return unified diff of `a` and `b` with `context` unchanged elements around changes,
+/// every element is one `key: value` line and values follow each map redaction policy,
+/// unchanged lines that of `a`
+///
+/// # Examples
+/// ```
+/// use multidict::MultiDict;
+/// use multidict::diff::unified_diff;
+///
+/// let incoming = MultiDict::from_query_string("host=a&accept=json&x-debug=1").unwrap();
+/// let outgoing = MultiDict::from_query_string("host=b&accept=json&via=proxy").unwrap();
+/// print!("{}", unified_diff(&incoming, &outgoing, 1));
+/// // --- a
+/// // +++ b
+/// // @@ -1,3 +1,3 @@
+/// // -host: a
+/// // +host: b
+/// //  accept: json
+/// // -x-debug: 1
+/// // +via: proxy
+/// assert_eq!(unified_diff(&incoming, &incoming, 3), "");
+/// ```
+pub fn unified_diff<M: ElementMeta>(a: &MultiDict<M>, b: &MultiDict<M>, context: usize) -> String {
+    let steps = align(a, b);
+    let changed: Vec<usize> = steps
+        .iter()
+        .enumerate()
+        .filter(|(_, step)| !matches!(step, Step::Keep(..)))
+        .map(|(position, _)| position)
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+    // group changed steps closer than 2 * context into hunks
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for position in changed {
+        let start = position.saturating_sub(context);
+        let end = (position + context + 1).min(steps.len());
+        match hunks.last_mut() {
+            Some(hunk) if start <= hunk.1 => hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let line = |map: &MultiDict<M>, idx: usize| {
+        let item = &map.elements[idx];
+        format!("{}: {}", item.key, map.shown_value(item))
+    };
+    // elements of `a` and `b` before every step
+    let mut before = Vec::with_capacity(steps.len());
+    let (mut i, mut j) = (0, 0);
+    for step in &steps {
+        before.push((i, j));
+        match step {
+            Step::Keep(..) => {
+                i += 1;
+                j += 1;
+            }
+            Step::Remove(_) => i += 1,
+            Step::Insert(_) => j += 1,
+        }
+    }
+    let mut output = String::from("--- a\n+++ b\n");
+    for (start, end) in hunks {
+        let lines = &steps[start..end];
+        let old_count = lines
+            .iter()
+            .filter(|step| !matches!(step, Step::Insert(_)))
+            .count();
+        let new_count = lines
+            .iter()
+            .filter(|step| !matches!(step, Step::Remove(_)))
+            .count();
+        let (old_start, new_start) = before[start];
+        // empty ranges start at the line before them
+        let range = |first: usize, count: usize| {
+            let first = if count == 0 { first } else { first + 1 };
+            format!("{first},{count}")
+        };
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_count),
+            range(new_start, new_count)
+        ));
+        for step in lines {
+            let text = match step {
+                Step::Keep(i, _) => format!(" {}", line(a, *i)),
+                Step::Remove(i) => format!("-{}", line(a, *i)),
+                Step::Insert(j) => format!("+{}", line(b, *j)),
+            };
+            output.push_str(&text);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+impl Patch {
+    /// Return JSON Patch document of the changes
+    ///
+    /// Elements are addressed by index, `add` carries a `{key, value}` object, and
+    /// `remove` and `replace` carry the previous element in an extra `old` member,
+    /// which RFC 6902 processors ignore. Values of elements redacted in the diffed maps
+    /// are written as [`REDACTED`] like in [`unified_diff`], so they can not be restored
+    /// from the document.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::diff::{diff, Patch};
+    ///
+    /// let a = MultiDict::from_query_string("a=1&b=2").unwrap();
+    /// let b = MultiDict::from_query_string("a=3&c=4").unwrap();
+    /// let patch = diff(&a, &b);
+    /// let json = patch.to_json();
+    /// println!("{json}");
+    /// // [{"op":"replace","path":"/0/value","value":"3","old":{"key":"a","value":"1"}},
+    /// //  {"op":"remove","path":"/1","old":{"key":"b","value":"2"}},
+    /// //  {"op":"add","path":"/1","value":{"key":"c","value":"4"}}]
+    /// assert_eq!(Patch::from_json(&json).unwrap(), patch);
+    /// ```
+    pub fn to_json(&self) -> String {
+        let string = |value: &str| Json::String(value.to_string());
+        let element = |key: &str, value: &str| {
+            Json::Object(vec![
+                ("key".to_string(), string(key)),
+                ("value".to_string(), string(value)),
+            ])
+        };
+        let operation = |op: &str, path: String, members: Vec<(&str, Json)>| {
+            let mut object = vec![
+                ("op".to_string(), string(op)),
+                ("path".to_string(), Json::String(path)),
+            ];
+            object.extend(
+                members
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value)),
+            );
+            Json::Object(object)
+        };
+        Json::Array(
+            self.changes
+                .iter()
+                .enumerate()
+                .map(|(position, change)| {
+                    let hidden = self.redacted.contains(&position);
+                    let shown = |value| if hidden { REDACTED } else { value };
+                    match change {
+                        Change::Insert { index, key, value } => operation(
+                            "add",
+                            format!("/{index}"),
+                            vec![("value", element(key, shown(value)))],
+                        ),
+                        Change::Remove { index, key, value } => operation(
+                            "remove",
+                            format!("/{index}"),
+                            vec![("old", element(key, shown(value)))],
+                        ),
+                        Change::Modify {
+                            index,
+                            key,
+                            old_value,
+                            new_value,
+                        } => operation(
+                            "replace",
+                            format!("/{index}/value"),
+                            vec![
+                                ("value", string(shown(new_value))),
+                                ("old", element(key, shown(old_value))),
+                            ],
+                        ),
+                    }
+                })
+                .collect(),
+        )
+        .to_string()
+    }
+
+    /// Parse JSON Patch document written by [`Patch::to_json`]
+    pub fn from_json(input: &str) -> Result<Patch, &'static str> {
+        let document = Json::parse(input)?;
+        let operations = document.as_array().ok_or("JSON Patch must be an array")?;
+        let mut changes = Vec::with_capacity(operations.len());
+        for operation in operations {
+            let text = |value: Option<&Json>| {
+                value
+                    .and_then(Json::as_str)
+                    .map(str::to_string)
+                    .ok_or("Invalid patch operation")
+            };
+            let element = |value: Option<&Json>| -> Result<(String, String), &'static str> {
+                let value = value.ok_or("Invalid patch operation")?;
+                Ok((text(value.get("key"))?, text(value.get("value"))?))
+            };
+            let path = text(operation.get("path"))?;
+            let (index, field) = match path.strip_prefix('/').map(|path| path.split_once('/')) {
+                Some(Some((index, field))) => (index, Some(field)),
+                Some(None) => (&path[1..], None),
+                None => return Err("Invalid patch path"),
+            };
+            if index.starts_with('+') || (index.len() > 1 && index.starts_with('0')) {
+                return Err("Invalid patch path");
+            }
+            let index: usize = index.parse().map_err(|_| "Invalid patch path")?;
+            let change = match (text(operation.get("op"))?.as_str(), field) {
+                ("add", None) => {
+                    let (key, value) = element(operation.get("value"))?;
+                    Change::Insert { index, key, value }
+                }
+                ("remove", None) => {
+                    let (key, value) = element(operation.get("old"))?;
+                    Change::Remove { index, key, value }
+                }
+                ("replace", Some("value")) => {
+                    let (key, old_value) = element(operation.get("old"))?;
+                    Change::Modify {
+                        index,
+                        key,
+                        old_value,
+                        new_value: text(operation.get("value"))?,
+                    }
+                }
+                _ => return Err("Unsupported patch operation"),
+            };
+            changes.push(change);
+        }
+        Ok(Patch {
+            changes,
+            ..Default::default()
+        })
+    }
+}
+
+impl<M> MultiDict<M> {
+    /// Apply `patch`, every removed or modified element must match the recorded one.
+    /// On error the MultiDict is left unchanged.
+    ///
+    /// Inserted elements have no metadata, modified elements keep theirs.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::diff::{diff, Patch};
+    ///
+    /// let a = MultiDict::from_query_string("via=1&via=2&host=a").unwrap();
+    /// let b = MultiDict::from_query_string("via=2&host=b&via=3").unwrap();
+    /// let patch = Patch::from_json(&diff(&a, &b).to_json()).unwrap();
+    ///
+    /// let mut map = a.clone();
+    /// map.apply(&patch).unwrap();
+    /// assert_eq!(map.to_string(), b.to_string());
+    /// assert_eq!(map.apply(&patch).unwrap_err(), "Patch does not match MultiDict");
+    /// ```
+    pub fn apply(&mut self, patch: &Patch) -> Result<(), &'static str> {
+        let mut elements: Vec<(String, String, Option<usize>)> = self
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| (item.key.clone(), item.value.clone(), Some(idx)))
+            .collect();
+        for change in &patch.changes {
+            match change {
+                Change::Insert { index, key, value } => {
+                    if *index > elements.len() {
+                        return Err("Patch index out of range");
+                    }
+                    elements.insert(*index, (key.clone(), value.clone(), None));
+                }
+                Change::Remove { index, key, value } => match elements.get(*index) {
+                    Some((old_key, old_value, _)) if old_key == key && old_value == value => {
+                        elements.remove(*index);
+                    }
+                    Some(_) => return Err("Patch does not match MultiDict"),
+                    None => return Err("Patch index out of range"),
+                },
+                Change::Modify {
+                    index,
+                    key,
+                    old_value,
+                    new_value,
+                } => match elements.get_mut(*index) {
+                    Some((old_key, value, _)) if old_key == key && value == old_value => {
+                        *value = new_value.clone();
+                    }
+                    Some(_) => return Err("Patch does not match MultiDict"),
+                    None => return Err("Patch index out of range"),
+                },
+            }
+        }
+        let mut previous: Vec<Option<MultiElement<M>>> =
+            self.elements.drain(..).map(Some).collect();
+        self.elements = elements
+            .into_iter()
+            .map(|(key, value, origin)| MultiElement {
+                key,
+                value,
+                meta: origin
+                    .and_then(|idx| previous[idx].take())
+                    .and_then(|item| item.meta),
+            })
+            .collect();
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+//! gRPC custom metadata on top of `MultiDict`.
+//!
+//! Keys are case-insensitive and stored lowercase. Keys ending in `-bin` carry binary
+//! values, which travel base64 encoded in HTTP/2 headers; other values are printable
+//! ASCII. Pseudo-headers, `grpc-*` keys and the protocol headers of
+//! [`RESERVED_HEADERS`] belong to the transport and cannot be set.
+use std::fmt;
+
+use crate::digest::{base64_decode, base64_encode};
+use crate::value::ElementValue;
+use crate::{MultiDict, MultiElement};
+
+/// Protocol headers which are not metadata, besides pseudo-headers and `grpc-*`
+pub const RESERVED_HEADERS: [&str; 2] = ["content-type", "te"];
+
+/// Value of one metadata entry
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MetadataValue {
+    /// Printable ASCII value of a regular key
+    Ascii(String),
+    /// Raw value of a `-bin` key
+    Binary(Vec<u8>),
+}
+
+impl ElementValue for MetadataValue {
+    fn fmt_value(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataValue::Ascii(value) => write!(f, r#""{value}""#),
+            MetadataValue::Binary(value) => value.fmt_value(f),
+        }
+    }
+}
+
+/// Return true if `key` belongs to the transport
+///
+/// # Examples
+/// ```
+/// use multidict::grpc::is_reserved;
+///
+/// assert!(is_reserved(":authority"));
+/// assert!(is_reserved("grpc-timeout"));
+/// assert!(is_reserved("Content-Type"));
+/// assert!(!is_reserved("x-request-id"));
+/// ```
+pub fn is_reserved(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with(':') || key.starts_with("grpc-") || RESERVED_HEADERS.contains(&key.as_str())
+}
+
+/// Return true if values of `key` are binary
+pub fn is_binary_key(key: &str) -> bool {
+    key.to_ascii_lowercase().ends_with("-bin")
+}
+
+/// Return lowercase `key` if it is a valid, unreserved metadata key
+fn metadata_key(key: &str) -> Result<String, &'static str> {
+    let key = key.to_ascii_lowercase();
+    if is_reserved(&key) {
+        return Err("Reserved gRPC metadata key");
+    }
+    let valid = |byte: u8| matches!(byte, b'0'..=b'9' | b'a'..=b'z' | b'_' | b'-' | b'.');
+    if key.is_empty() || !key.bytes().all(valid) {
+        return Err("Invalid gRPC metadata key");
+    }
+    Ok(key)
+}
+
+/// Custom metadata of a gRPC call
+#[derive(Clone, Default)]
+pub struct GrpcMetadata {
+    entries: MultiDict<(), MetadataValue>,
+}
+
+impl fmt::Display for GrpcMetadata {
+    /// Entries in `MultiDict` format, binary values as byte strings
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.entries)
+    }
+}
+
+impl fmt::Debug for GrpcMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrpcMetadata")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl GrpcMetadata {
+    /// Return empty metadata
+    pub fn new() -> Self {
+        GrpcMetadata::default()
+    }
+
+    /// Return the number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return entries with lowercase keys in insertion order
+    pub fn entries(&self) -> &MultiDict<(), MetadataValue> {
+        &self.entries
+    }
+
+    /// Append entry, the key must not be reserved and its value kind must match the
+    /// `-bin` suffix of the key
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::grpc::{GrpcMetadata, MetadataValue};
+    ///
+    /// let mut metadata = GrpcMetadata::new();
+    /// metadata.add("X-Request-Id", MetadataValue::Ascii("42".to_string())).unwrap();
+    /// metadata.add("trace-bin", MetadataValue::Binary(vec![0, 1, 2])).unwrap();
+    /// println!("{metadata}");
+    /// // MultiDict < "x-request-id":"42", "trace-bin":b"\x00\x01\x02" >
+    ///
+    /// assert_eq!(metadata.add("grpc-timeout", MetadataValue::Ascii("1S".to_string())).unwrap_err(),
+    ///            "Reserved gRPC metadata key");
+    /// assert_eq!(metadata.add("trace-bin", MetadataValue::Ascii("x".to_string())).unwrap_err(),
+    ///            "Binary gRPC metadata key needs a binary value");
+    /// ```
+    pub fn add(&mut self, key: &str, value: MetadataValue) -> Result<(), &'static str> {
+        let key = metadata_key(key)?;
+        match (&value, is_binary_key(&key)) {
+            (MetadataValue::Binary(_), false) => {
+                return Err("Binary gRPC metadata value needs a -bin key")
+            }
+            (MetadataValue::Ascii(_), true) => {
+                return Err("Binary gRPC metadata key needs a binary value")
+            }
+            (MetadataValue::Ascii(text), false)
+                if !text.bytes().all(|byte| (0x20..0x7f).contains(&byte)) =>
+            {
+                return Err("gRPC metadata value is not printable ASCII")
+            }
+            _ => {}
+        }
+        self.entries.add(MultiElement {
+            key,
+            value,
+            meta: None,
+        });
+        Ok(())
+    }
+
+    /// Append ASCII entry
+    pub fn add_ascii(&mut self, key: &str, value: &str) -> Result<(), &'static str> {
+        self.add(key, MetadataValue::Ascii(value.to_string()))
+    }
+
+    /// Append binary entry of a `-bin` key
+    pub fn add_binary(&mut self, key: &str, value: &[u8]) -> Result<(), &'static str> {
+        self.add(key, MetadataValue::Binary(value.to_vec()))
+    }
+
+    /// Return the **first** value of key, ignoring ASCII case
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::grpc::{GrpcMetadata, MetadataValue};
+    ///
+    /// let mut metadata = GrpcMetadata::new();
+    /// metadata.add_ascii("tenant", "a").unwrap();
+    /// metadata.add_ascii("Tenant", "b").unwrap();
+    /// assert_eq!(metadata.get("TENANT"), Some(&MetadataValue::Ascii("a".to_string())));
+    /// assert_eq!(metadata.getall("tenant").len(), 2);
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&MetadataValue> {
+        self.entries
+            .get(&key.to_ascii_lowercase())
+            .ok()
+            .map(|item| &item.value)
+    }
+
+    /// Return all values of key in order, ignoring ASCII case
+    pub fn getall(&self, key: &str) -> Vec<&MetadataValue> {
+        let key = key.to_ascii_lowercase();
+        self.entries
+            .iter()
+            .filter(|item| item.key == key)
+            .map(|item| &item.value)
+            .collect()
+    }
+
+    /// Remove all values of key, return true if any was present
+    pub fn remove(&mut self, key: &str) -> bool {
+        let key = key.to_ascii_lowercase();
+        let count = self.entries.len();
+        self.entries.elements.retain(|item| item.key != key);
+        self.entries.len() != count
+    }
+
+    /// Return HTTP/2 header list of the metadata, binary values are unpadded base64
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::grpc::GrpcMetadata;
+    ///
+    /// let mut metadata = GrpcMetadata::new();
+    /// metadata.add_ascii("authorization", "Bearer t").unwrap();
+    /// metadata.add_binary("trace-bin", b"\x01\x02\x03\x04").unwrap();
+    /// println!("{}", metadata.to_headers());
+    /// // MultiDict < "authorization":"Bearer t", "trace-bin":"AQIDBA" >
+    /// ```
+    pub fn to_headers(&self) -> MultiDict {
+        let mut results = MultiDict::new_capacity(&self.len());
+        for item in self.entries.iter() {
+            let value = match &item.value {
+                MetadataValue::Ascii(value) => value.clone(),
+                MetadataValue::Binary(value) => {
+                    base64_encode(value).trim_end_matches('=').to_string()
+                }
+            };
+            results.add(MultiElement {
+                key: item.key.clone(),
+                value,
+                meta: None,
+            });
+        }
+        results
+    }
+
+    /// Read metadata from an HTTP/2 header list, transport headers are skipped
+    ///
+    /// Binary values may be padded or not, and a comma separated list of base64 values
+    /// gives one entry per item.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::grpc::{GrpcMetadata, MetadataValue};
+    ///
+    /// let mut headers = MultiDict::new();
+    /// for (key, value) in [(":path", "/pkg.Service/Method"), ("content-type", "application/grpc"),
+    ///                      ("grpc-timeout", "1S"), ("x-user", "alice"), ("id-bin", "AAE=,AgM")] {
+    ///     headers.add(multidict::MultiElement {
+    ///                 key: key.to_string(),
+    ///                 value: value.to_string(),
+    ///                 meta: None,
+    ///             });
+    /// }
+    /// let metadata = GrpcMetadata::from_headers(&headers).unwrap();
+    /// println!("{metadata}");
+    /// // MultiDict < "x-user":"alice", "id-bin":b"\x00\x01", "id-bin":b"\x02\x03" >
+    /// assert_eq!(metadata.getall("id-bin"), vec![&MetadataValue::Binary(vec![0, 1]),
+    ///                                            &MetadataValue::Binary(vec![2, 3])]);
+    /// ```
+    pub fn from_headers<M>(headers: &MultiDict<M>) -> Result<GrpcMetadata, &'static str> {
+        let mut results = GrpcMetadata::new();
+        for item in headers.iter() {
+            if is_reserved(&item.key) {
+                continue;
+            }
+            if !is_binary_key(&item.key) {
+                results.add_ascii(&item.key, &item.value)?;
+                continue;
+            }
+            for part in item.value.split(',') {
+                let value = base64_decode(part.trim())
+                    .map_err(|_| "Invalid base64 in binary gRPC metadata")?;
+                results.add(&item.key, MetadataValue::Binary(value))?;
+            }
+        }
+        Ok(results)
+    }
+}
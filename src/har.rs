@@ -0,0 +1,571 @@
+//! HAR 1.2 (HTTP Archive) import and export.
+//!
+//! Each `{name, value}` array of an entry (`headers`, `queryString`, `cookies`
+//! and `postData.params`) maps to a `MultiDict` keeping order and repeated names.
+//! Other fields of the format, such as cookie attributes or timings, are not kept:
+//! exported archives carry the values HAR 1.2 requires. The `_spanned` parsers give
+//! every pair the byte range of its `{name, value}` object.
+use std::fmt;
+
+use crate::json::Json;
+use crate::metadata::{ElementMeta, Span};
+use crate::{MultiDict, MultiElement};
+
+/// HTTP archive, the `log` object of a HAR file
+#[derive(Clone)]
+pub struct Har<M = ()> {
+    pub creator_name: String,
+    pub creator_version: String,
+    pub entries: Vec<HarEntry<M>>,
+}
+
+impl<M> Default for Har<M> {
+    fn default() -> Self {
+        Har {
+            creator_name: "multidict".to_string(),
+            creator_version: env!("CARGO_PKG_VERSION").to_string(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// Exchanged request and response
+#[derive(Clone)]
+pub struct HarEntry<M = ()> {
+    pub started_date_time: String,
+    pub time: f64,
+    pub request: HarRequest<M>,
+    pub response: HarResponse<M>,
+}
+
+impl<M> Default for HarEntry<M> {
+    fn default() -> Self {
+        HarEntry {
+            started_date_time: String::new(),
+            time: 0.0,
+            request: HarRequest::default(),
+            response: HarResponse::default(),
+        }
+    }
+}
+
+/// Request, `query_string` holds decoded query parameters
+#[derive(Clone)]
+pub struct HarRequest<M = ()> {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub headers: MultiDict<M>,
+    pub query_string: MultiDict<M>,
+    pub cookies: MultiDict<M>,
+    pub post_data: Option<HarPostData<M>>,
+}
+
+impl<M> Default for HarRequest<M> {
+    fn default() -> Self {
+        HarRequest {
+            method: "GET".to_string(),
+            url: String::new(),
+            http_version: "HTTP/1.1".to_string(),
+            headers: MultiDict::default(),
+            query_string: MultiDict::default(),
+            cookies: MultiDict::default(),
+            post_data: None,
+        }
+    }
+}
+
+/// Request body, `params` holds decoded form fields and `text` the raw body
+#[derive(Clone)]
+pub struct HarPostData<M = ()> {
+    pub mime_type: String,
+    pub params: MultiDict<M>,
+    pub text: String,
+}
+
+impl<M> Default for HarPostData<M> {
+    fn default() -> Self {
+        HarPostData {
+            mime_type: String::new(),
+            params: MultiDict::default(),
+            text: String::new(),
+        }
+    }
+}
+
+/// Response, `mime_type` and `text` describe the response content
+#[derive(Clone)]
+pub struct HarResponse<M = ()> {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub headers: MultiDict<M>,
+    pub cookies: MultiDict<M>,
+    pub mime_type: String,
+    pub text: String,
+    pub redirect_url: String,
+}
+
+impl<M> Default for HarResponse<M> {
+    fn default() -> Self {
+        HarResponse {
+            status: 0,
+            status_text: String::new(),
+            http_version: "HTTP/1.1".to_string(),
+            headers: MultiDict::default(),
+            cookies: MultiDict::default(),
+            mime_type: String::new(),
+            text: String::new(),
+            redirect_url: String::new(),
+        }
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for Har<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Har")
+            .field("creator_name", &self.creator_name)
+            .field("creator_version", &self.creator_version)
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for HarEntry<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HarEntry")
+            .field("started_date_time", &self.started_date_time)
+            .field("time", &self.time)
+            .field("request", &self.request)
+            .field("response", &self.response)
+            .finish()
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for HarRequest<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HarRequest")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("http_version", &self.http_version)
+            .field("headers", &self.headers)
+            .field("query_string", &self.query_string)
+            .field("cookies", &self.cookies)
+            .field("post_data", &self.post_data)
+            .finish()
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for HarPostData<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HarPostData")
+            .field("mime_type", &self.mime_type)
+            .field("params", &self.params)
+            .field("text", &self.text)
+            .finish()
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for HarResponse<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HarResponse")
+            .field("status", &self.status)
+            .field("status_text", &self.status_text)
+            .field("http_version", &self.http_version)
+            .field("headers", &self.headers)
+            .field("cookies", &self.cookies)
+            .field("mime_type", &self.mime_type)
+            .field("text", &self.text)
+            .field("redirect_url", &self.redirect_url)
+            .finish()
+    }
+}
+
+/// Return string member `key`, missing member is an empty string
+fn string_member(object: &Json, key: &str) -> Result<String, &'static str> {
+    match object.get(key) {
+        None | Some(Json::Null) => Ok(String::new()),
+        Some(value) => value
+            .as_str()
+            .map(str::to_string)
+            .ok_or("HAR field must be a string"),
+    }
+}
+
+/// Return `{name, value}` array member `key` of the object at document order `index`,
+/// missing member is an empty `MultiDict`
+fn pairs_member<M: ElementMeta>(
+    object: &Json,
+    index: usize,
+    key: &str,
+    spans: &[Span],
+) -> Result<MultiDict<M>, &'static str> {
+    match object.get_indexed(index, key) {
+        None => Ok(MultiDict::default()),
+        Some((value, index)) => pairs(value, index, spans),
+    }
+}
+
+/// Return `{name, value}` array at document order `index`, metadata is built from the
+/// span of each pair object
+fn pairs<M: ElementMeta>(
+    value: &Json,
+    index: usize,
+    spans: &[Span],
+) -> Result<MultiDict<M>, &'static str> {
+    let Some(items) = value.items_indexed(index) else {
+        return Err("HAR name/value list must be an array");
+    };
+    let mut results = MultiDict::default();
+    for (item, index) in items {
+        match (
+            item.get("name").and_then(Json::as_str),
+            item.get("value").and_then(Json::as_str),
+        ) {
+            (Some(name), Some(value)) => results.add(MultiElement {
+                key: name.to_string(),
+                value: value.to_string(),
+                meta: M::from_span(spans[index]),
+            }),
+            _ => return Err("HAR name/value pair requires string name and value"),
+        }
+    }
+    Ok(results)
+}
+
+fn string(value: &str) -> Json {
+    Json::String(value.to_string())
+}
+
+fn member(name: &str, value: Json) -> (String, Json) {
+    (name.to_string(), value)
+}
+
+impl MultiDict {
+    /// Parse HAR `{name, value}` array such as `headers` or `queryString`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let input = r#"[{"name": "Accept", "value": "*/*"}, {"name": "Cookie", "value": "a=1"},
+    ///                 {"name": "Cookie", "value": "b=2", "comment": "ignored"}]"#;
+    /// let headers = MultiDict::from_har_pairs(input).unwrap();
+    /// println!("{headers}");
+    /// // MultiDict < "Accept":"*/*", "Cookie":"a=1", "Cookie":"b=2" >
+    /// assert_eq!(headers.len(), 3);
+    /// assert!(MultiDict::from_har_pairs(r#"[{"name": "Accept"}]"#).is_err());
+    /// ```
+    pub fn from_har_pairs(input: &str) -> Result<MultiDict, &'static str> {
+        MultiDict::from_har_pairs_spanned(input)
+    }
+}
+
+impl<M: ElementMeta> MultiDict<M> {
+    /// Parse HAR `{name, value}` array like [`MultiDict::from_har_pairs`], every element
+    /// gets metadata for the byte range of its pair object
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::metadata::Span;
+    ///
+    /// let input = r#"[{"name": "Accept", "value": "*/*"}, {"name": "DNT", "value": "yes"}]"#;
+    /// let headers: MultiDict<Span> = MultiDict::from_har_pairs_spanned(input).unwrap();
+    /// let dnt = headers.get("DNT").unwrap().meta.unwrap();
+    /// assert_eq!(dnt.slice(input), r#"{"name": "DNT", "value": "yes"}"#);
+    /// ```
+    pub fn from_har_pairs_spanned(input: &str) -> Result<MultiDict<M>, &'static str> {
+        let (document, spans) = Json::parse_spanned(input)?;
+        pairs(&document, 0, &spans)
+    }
+
+    fn to_har_json(&self) -> Json {
+        Json::Array(
+            self.iter()
+                .map(|item| {
+                    Json::Object(vec![
+                        member("name", string(&item.key)),
+                        member("value", string(self.shown_value(item))),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    /// Return HAR `{name, value}` array in compact JSON, values follow the redaction policy
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    ///
+    /// let mut map = MultiDict::new();
+    /// map.add(MultiElement {
+    ///             key: "q".to_string(),
+    ///             value: "\"rust\"".to_string(),
+    ///             meta: None,
+    ///         });
+    /// assert_eq!(map.to_har_pairs(), r#"[{"name":"q","value":"\"rust\""}]"#);
+    /// ```
+    pub fn to_har_pairs(&self) -> String {
+        self.to_har_json().to_string()
+    }
+}
+
+impl<M: ElementMeta> HarRequest<M> {
+    fn from_json(value: &Json, index: usize, spans: &[Span]) -> Result<Self, &'static str> {
+        let post_data = match value.get_indexed(index, "postData") {
+            None | Some((Json::Null, _)) => None,
+            Some((post_data, post_index)) => Some(HarPostData {
+                mime_type: string_member(post_data, "mimeType")?,
+                params: pairs_member(post_data, post_index, "params", spans)?,
+                text: string_member(post_data, "text")?,
+            }),
+        };
+        Ok(HarRequest {
+            method: string_member(value, "method")?,
+            url: string_member(value, "url")?,
+            http_version: string_member(value, "httpVersion")?,
+            headers: pairs_member(value, index, "headers", spans)?,
+            query_string: pairs_member(value, index, "queryString", spans)?,
+            cookies: pairs_member(value, index, "cookies", spans)?,
+            post_data,
+        })
+    }
+
+    fn to_json(&self) -> Json {
+        let mut members = vec![
+            member("method", string(&self.method)),
+            member("url", string(&self.url)),
+            member("httpVersion", string(&self.http_version)),
+            member("cookies", self.cookies.to_har_json()),
+            member("headers", self.headers.to_har_json()),
+            member("queryString", self.query_string.to_har_json()),
+        ];
+        let body_size = self
+            .post_data
+            .as_ref()
+            .map_or(0, |post_data| post_data.text.len());
+        if let Some(post_data) = &self.post_data {
+            members.push(member(
+                "postData",
+                Json::Object(vec![
+                    member("mimeType", string(&post_data.mime_type)),
+                    member("params", post_data.params.to_har_json()),
+                    member("text", string(&post_data.text)),
+                ]),
+            ));
+        }
+        members.push(member("headersSize", Json::Number(-1.0)));
+        members.push(member("bodySize", Json::Number(body_size as f64)));
+        Json::Object(members)
+    }
+}
+
+impl<M: ElementMeta> HarResponse<M> {
+    fn from_json(value: &Json, index: usize, spans: &[Span]) -> Result<Self, &'static str> {
+        let status = match value.get("status").and_then(Json::as_f64) {
+            Some(status) if (0.0..=999.0).contains(&status) && status.fract() == 0.0 => {
+                status as u16
+            }
+            _ => return Err("HAR response status must be an integer"),
+        };
+        let content = value.get("content").unwrap_or(&Json::Null);
+        Ok(HarResponse {
+            status,
+            status_text: string_member(value, "statusText")?,
+            http_version: string_member(value, "httpVersion")?,
+            headers: pairs_member(value, index, "headers", spans)?,
+            cookies: pairs_member(value, index, "cookies", spans)?,
+            mime_type: string_member(content, "mimeType")?,
+            text: string_member(content, "text")?,
+            redirect_url: string_member(value, "redirectURL")?,
+        })
+    }
+
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            member("status", Json::Number(self.status as f64)),
+            member("statusText", string(&self.status_text)),
+            member("httpVersion", string(&self.http_version)),
+            member("cookies", self.cookies.to_har_json()),
+            member("headers", self.headers.to_har_json()),
+            member(
+                "content",
+                Json::Object(vec![
+                    member("size", Json::Number(self.text.len() as f64)),
+                    member("mimeType", string(&self.mime_type)),
+                    member("text", string(&self.text)),
+                ]),
+            ),
+            member("redirectURL", string(&self.redirect_url)),
+            member("headersSize", Json::Number(-1.0)),
+            member("bodySize", Json::Number(self.text.len() as f64)),
+        ])
+    }
+}
+
+impl<M: ElementMeta> HarEntry<M> {
+    fn from_json(value: &Json, index: usize, spans: &[Span]) -> Result<Self, &'static str> {
+        let (Some((request, request_index)), Some((response, response_index))) = (
+            value.get_indexed(index, "request"),
+            value.get_indexed(index, "response"),
+        ) else {
+            return Err("HAR entry requires request and response");
+        };
+        Ok(HarEntry {
+            started_date_time: string_member(value, "startedDateTime")?,
+            time: value.get("time").and_then(Json::as_f64).unwrap_or(0.0),
+            request: HarRequest::from_json(request, request_index, spans)?,
+            response: HarResponse::from_json(response, response_index, spans)?,
+        })
+    }
+
+    fn to_json(&self) -> Json {
+        Json::Object(vec![
+            member("startedDateTime", string(&self.started_date_time)),
+            member("time", Json::Number(self.time)),
+            member("request", self.request.to_json()),
+            member("response", self.response.to_json()),
+            member("cache", Json::Object(Vec::new())),
+            member(
+                "timings",
+                Json::Object(vec![
+                    member("send", Json::Number(0.0)),
+                    member("wait", Json::Number(self.time)),
+                    member("receive", Json::Number(0.0)),
+                ]),
+            ),
+        ])
+    }
+}
+
+impl Har {
+    /// Parse HAR document
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::har::Har;
+    ///
+    /// let input = r#"{"log": {"version": "1.2", "creator": {"name": "Firefox", "version": "128"},
+    ///   "entries": [{
+    ///     "startedDateTime": "2024-05-01T10:00:00.000Z", "time": 42,
+    ///     "request": {"method": "POST", "url": "https://example.com/login?next=%2F&next=%2Fhome",
+    ///       "httpVersion": "HTTP/2", "headersSize": -1, "bodySize": 25,
+    ///       "headers": [{"name": "content-type", "value": "application/x-www-form-urlencoded"}],
+    ///       "queryString": [{"name": "next", "value": "/"}, {"name": "next", "value": "/home"}],
+    ///       "cookies": [{"name": "session", "value": "abc", "httpOnly": true}],
+    ///       "postData": {"mimeType": "application/x-www-form-urlencoded",
+    ///         "params": [{"name": "user", "value": "ann"}, {"name": "role", "value": "admin"}],
+    ///         "text": "user=ann&role=admin"}},
+    ///     "response": {"status": 302, "statusText": "Found", "httpVersion": "HTTP/2",
+    ///       "headers": [{"name": "location", "value": "/"}], "cookies": [],
+    ///       "content": {"size": 0, "mimeType": "text/html"}, "redirectURL": "/",
+    ///       "headersSize": -1, "bodySize": 0},
+    ///     "cache": {}, "timings": {"send": 0, "wait": 42, "receive": 0}}]}}"#;
+    /// let har = Har::from_json(input).unwrap();
+    /// let request = &har.entries[0].request;
+    /// println!("{}", request.query_string);
+    /// // MultiDict < "next":"/", "next":"/home" >
+    /// assert_eq!(request.query_string.getall("next").unwrap().len(), 2);
+    /// assert_eq!(request.cookies.get("session").unwrap().value, "abc");
+    /// let params = &request.post_data.as_ref().unwrap().params;
+    /// assert_eq!(params.keys(), vec!["user", "role"]);
+    /// assert_eq!(har.entries[0].response.status, 302);
+    ///
+    /// // export and import keep every name/value list
+    /// let output = har.to_json();
+    /// assert_eq!(Har::from_json(&output).unwrap().to_json(), output);
+    /// ```
+    pub fn from_json(input: &str) -> Result<Har, &'static str> {
+        Har::from_json_spanned(input)
+    }
+}
+
+impl<M: ElementMeta> Har<M> {
+    /// Parse HAR document like [`Har::from_json`], every element of the name/value lists
+    /// gets metadata for the byte range of its pair object
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::har::Har;
+    /// use multidict::metadata::Span;
+    ///
+    /// let input = r#"{"log": {"entries": [{
+    ///   "request": {"url": "https://example.com/?page=two",
+    ///     "queryString": [{"name": "page", "value": "two"}]},
+    ///   "response": {"status": 200}}]}}"#;
+    /// let har: Har<Span> = Har::from_json_spanned(input).unwrap();
+    /// let page = har.entries[0].request.query_string.get("page").unwrap();
+    /// assert_eq!(page.meta.unwrap().slice(input), r#"{"name": "page", "value": "two"}"#);
+    /// ```
+    pub fn from_json_spanned(input: &str) -> Result<Har<M>, &'static str> {
+        let (document, spans) = Json::parse_spanned(input)?;
+        let Some((log, log_index)) = document.get_indexed(0, "log") else {
+            return Err("HAR document requires log object");
+        };
+        let creator = log.get("creator").unwrap_or(&Json::Null);
+        let Some(entries) = log
+            .get_indexed(log_index, "entries")
+            .and_then(|(entries, index)| entries.items_indexed(index))
+        else {
+            return Err("HAR log requires entries array");
+        };
+        Ok(Har {
+            creator_name: string_member(creator, "name")?,
+            creator_version: string_member(creator, "version")?,
+            entries: entries
+                .into_iter()
+                .map(|(entry, index)| HarEntry::from_json(entry, index, &spans))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Return HAR 1.2 document, indented by two spaces
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::har::{Har, HarEntry, HarRequest};
+    ///
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: "accept".to_string(),
+    ///             value: "*/*".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut har = Har::default();
+    /// har.entries.push(HarEntry {
+    ///     started_date_time: "2024-05-01T10:00:00.000Z".to_string(),
+    ///     request: HarRequest {
+    ///         url: "https://example.com/".to_string(),
+    ///         headers,
+    ///         ..Default::default()
+    ///     },
+    ///     ..Default::default()
+    /// });
+    /// let output = har.to_json();
+    /// assert!(output.starts_with("{\n  \"log\": {\n    \"version\": \"1.2\""));
+    /// assert!(output.contains("\"name\": \"accept\""));
+    /// ```
+    pub fn to_json(&self) -> String {
+        Json::Object(vec![member(
+            "log",
+            Json::Object(vec![
+                member("version", string("1.2")),
+                member(
+                    "creator",
+                    Json::Object(vec![
+                        member("name", string(&self.creator_name)),
+                        member("version", string(&self.creator_version)),
+                    ]),
+                ),
+                member(
+                    "entries",
+                    Json::Array(self.entries.iter().map(HarEntry::to_json).collect()),
+                ),
+            ]),
+        )])
+        .pretty()
+    }
+}
@@ -0,0 +1,923 @@
+//! HPACK (RFC 7541) header compression for HTTP/2 with `MultiDict` header lists.
+//!
+//! [`Decoder`] turns a header block into a `MultiDict` keeping field order and
+//! repeated names, [`Encoder`] turns a `MultiDict` into a header block.
+//! Both sides keep their dynamic table between blocks of one connection.
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+use crate::{MultiDict, MultiElement};
+
+/// Static table of RFC 7541 Appendix A, index 1 is the first entry
+pub(crate) const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Huffman code `(code, bit length)` of RFC 7541 Appendix B, index 256 is EOS
+const HUFFMAN_CODES: [(u32, u8); 257] = [
+    (0x1ff8, 13),
+    (0x7fffd8, 23),
+    (0xfffffe2, 28),
+    (0xfffffe3, 28),
+    (0xfffffe4, 28),
+    (0xfffffe5, 28),
+    (0xfffffe6, 28),
+    (0xfffffe7, 28),
+    (0xfffffe8, 28),
+    (0xffffea, 24),
+    (0x3ffffffc, 30),
+    (0xfffffe9, 28),
+    (0xfffffea, 28),
+    (0x3ffffffd, 30),
+    (0xfffffeb, 28),
+    (0xfffffec, 28),
+    (0xfffffed, 28),
+    (0xfffffee, 28),
+    (0xfffffef, 28),
+    (0xffffff0, 28),
+    (0xffffff1, 28),
+    (0xffffff2, 28),
+    (0x3ffffffe, 30),
+    (0xffffff3, 28),
+    (0xffffff4, 28),
+    (0xffffff5, 28),
+    (0xffffff6, 28),
+    (0xffffff7, 28),
+    (0xffffff8, 28),
+    (0xffffff9, 28),
+    (0xffffffa, 28),
+    (0xffffffb, 28),
+    (0x14, 6),
+    (0x3f8, 10),
+    (0x3f9, 10),
+    (0xffa, 12),
+    (0x1ff9, 13),
+    (0x15, 6),
+    (0xf8, 8),
+    (0x7fa, 11),
+    (0x3fa, 10),
+    (0x3fb, 10),
+    (0xf9, 8),
+    (0x7fb, 11),
+    (0xfa, 8),
+    (0x16, 6),
+    (0x17, 6),
+    (0x18, 6),
+    (0x0, 5),
+    (0x1, 5),
+    (0x2, 5),
+    (0x19, 6),
+    (0x1a, 6),
+    (0x1b, 6),
+    (0x1c, 6),
+    (0x1d, 6),
+    (0x1e, 6),
+    (0x1f, 6),
+    (0x5c, 7),
+    (0xfb, 8),
+    (0x7ffc, 15),
+    (0x20, 6),
+    (0xffb, 12),
+    (0x3fc, 10),
+    (0x1ffa, 13),
+    (0x21, 6),
+    (0x5d, 7),
+    (0x5e, 7),
+    (0x5f, 7),
+    (0x60, 7),
+    (0x61, 7),
+    (0x62, 7),
+    (0x63, 7),
+    (0x64, 7),
+    (0x65, 7),
+    (0x66, 7),
+    (0x67, 7),
+    (0x68, 7),
+    (0x69, 7),
+    (0x6a, 7),
+    (0x6b, 7),
+    (0x6c, 7),
+    (0x6d, 7),
+    (0x6e, 7),
+    (0x6f, 7),
+    (0x70, 7),
+    (0x71, 7),
+    (0x72, 7),
+    (0xfc, 8),
+    (0x73, 7),
+    (0xfd, 8),
+    (0x1ffb, 13),
+    (0x7fff0, 19),
+    (0x1ffc, 13),
+    (0x3ffc, 14),
+    (0x22, 6),
+    (0x7ffd, 15),
+    (0x3, 5),
+    (0x23, 6),
+    (0x4, 5),
+    (0x24, 6),
+    (0x5, 5),
+    (0x25, 6),
+    (0x26, 6),
+    (0x27, 6),
+    (0x6, 5),
+    (0x74, 7),
+    (0x75, 7),
+    (0x28, 6),
+    (0x29, 6),
+    (0x2a, 6),
+    (0x7, 5),
+    (0x2b, 6),
+    (0x76, 7),
+    (0x2c, 6),
+    (0x8, 5),
+    (0x9, 5),
+    (0x2d, 6),
+    (0x77, 7),
+    (0x78, 7),
+    (0x79, 7),
+    (0x7a, 7),
+    (0x7b, 7),
+    (0x7ffe, 15),
+    (0x7fc, 11),
+    (0x3ffd, 14),
+    (0x1ffd, 13),
+    (0xffffffc, 28),
+    (0xfffe6, 20),
+    (0x3fffd2, 22),
+    (0xfffe7, 20),
+    (0xfffe8, 20),
+    (0x3fffd3, 22),
+    (0x3fffd4, 22),
+    (0x3fffd5, 22),
+    (0x7fffd9, 23),
+    (0x3fffd6, 22),
+    (0x7fffda, 23),
+    (0x7fffdb, 23),
+    (0x7fffdc, 23),
+    (0x7fffdd, 23),
+    (0x7fffde, 23),
+    (0xffffeb, 24),
+    (0x7fffdf, 23),
+    (0xffffec, 24),
+    (0xffffed, 24),
+    (0x3fffd7, 22),
+    (0x7fffe0, 23),
+    (0xffffee, 24),
+    (0x7fffe1, 23),
+    (0x7fffe2, 23),
+    (0x7fffe3, 23),
+    (0x7fffe4, 23),
+    (0x1fffdc, 21),
+    (0x3fffd8, 22),
+    (0x7fffe5, 23),
+    (0x3fffd9, 22),
+    (0x7fffe6, 23),
+    (0x7fffe7, 23),
+    (0xffffef, 24),
+    (0x3fffda, 22),
+    (0x1fffdd, 21),
+    (0xfffe9, 20),
+    (0x3fffdb, 22),
+    (0x3fffdc, 22),
+    (0x7fffe8, 23),
+    (0x7fffe9, 23),
+    (0x1fffde, 21),
+    (0x7fffea, 23),
+    (0x3fffdd, 22),
+    (0x3fffde, 22),
+    (0xfffff0, 24),
+    (0x1fffdf, 21),
+    (0x3fffdf, 22),
+    (0x7fffeb, 23),
+    (0x7fffec, 23),
+    (0x1fffe0, 21),
+    (0x1fffe1, 21),
+    (0x3fffe0, 22),
+    (0x1fffe2, 21),
+    (0x7fffed, 23),
+    (0x3fffe1, 22),
+    (0x7fffee, 23),
+    (0x7fffef, 23),
+    (0xfffea, 20),
+    (0x3fffe2, 22),
+    (0x3fffe3, 22),
+    (0x3fffe4, 22),
+    (0x7ffff0, 23),
+    (0x3fffe5, 22),
+    (0x3fffe6, 22),
+    (0x7ffff1, 23),
+    (0x3ffffe0, 26),
+    (0x3ffffe1, 26),
+    (0xfffeb, 20),
+    (0x7fff1, 19),
+    (0x3fffe7, 22),
+    (0x7ffff2, 23),
+    (0x3fffe8, 22),
+    (0x1ffffec, 25),
+    (0x3ffffe2, 26),
+    (0x3ffffe3, 26),
+    (0x3ffffe4, 26),
+    (0x7ffffde, 27),
+    (0x7ffffdf, 27),
+    (0x3ffffe5, 26),
+    (0xfffff1, 24),
+    (0x1ffffed, 25),
+    (0x7fff2, 19),
+    (0x1fffe3, 21),
+    (0x3ffffe6, 26),
+    (0x7ffffe0, 27),
+    (0x7ffffe1, 27),
+    (0x3ffffe7, 26),
+    (0x7ffffe2, 27),
+    (0xfffff2, 24),
+    (0x1fffe4, 21),
+    (0x1fffe5, 21),
+    (0x3ffffe8, 26),
+    (0x3ffffe9, 26),
+    (0xffffffd, 28),
+    (0x7ffffe3, 27),
+    (0x7ffffe4, 27),
+    (0x7ffffe5, 27),
+    (0xfffec, 20),
+    (0xfffff3, 24),
+    (0xfffed, 20),
+    (0x1fffe6, 21),
+    (0x3fffe9, 22),
+    (0x1fffe7, 21),
+    (0x1fffe8, 21),
+    (0x7ffff3, 23),
+    (0x3fffea, 22),
+    (0x3fffeb, 22),
+    (0x1ffffee, 25),
+    (0x1ffffef, 25),
+    (0xfffff4, 24),
+    (0xfffff5, 24),
+    (0x3ffffea, 26),
+    (0x7ffff4, 23),
+    (0x3ffffeb, 26),
+    (0x7ffffe6, 27),
+    (0x3ffffec, 26),
+    (0x3ffffed, 26),
+    (0x7ffffe7, 27),
+    (0x7ffffe8, 27),
+    (0x7ffffe9, 27),
+    (0x7ffffea, 27),
+    (0x7ffffeb, 27),
+    (0xffffffe, 28),
+    (0x7ffffec, 27),
+    (0x7ffffed, 27),
+    (0x7ffffee, 27),
+    (0x7ffffef, 27),
+    (0x7fffff0, 27),
+    (0x3ffffee, 26),
+    (0x3fffffff, 30),
+];
+
+/// Canonical Huffman decoding tables indexed by code length
+struct HuffmanDecoding {
+    first_code: [u32; 31],
+    count: [u32; 31],
+    offset: [usize; 31],
+    symbols: Vec<u16>,
+}
+
+fn huffman_decoding() -> &'static HuffmanDecoding {
+    static DECODING: OnceLock<HuffmanDecoding> = OnceLock::new();
+    DECODING.get_or_init(|| {
+        let mut symbols: Vec<u16> = (0..257).collect();
+        symbols.sort_by_key(|symbol| (HUFFMAN_CODES[*symbol as usize].1, *symbol));
+        let mut decoding = HuffmanDecoding {
+            first_code: [0; 31],
+            count: [0; 31],
+            offset: [0; 31],
+            symbols,
+        };
+        for (idx, symbol) in decoding.symbols.iter().enumerate() {
+            let (code, length) = HUFFMAN_CODES[*symbol as usize];
+            let length = length as usize;
+            if decoding.count[length] == 0 {
+                decoding.first_code[length] = code;
+                decoding.offset[length] = idx;
+            }
+            decoding.count[length] += 1;
+        }
+        decoding
+    })
+}
+
+/// Return Huffman encoded `data`, padded with the most significant bits of EOS
+pub(crate) fn huffman_encode(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(huffman_encoded_len(data));
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for byte in data {
+        let (code, length) = HUFFMAN_CODES[*byte as usize];
+        buffer = (buffer << length) | code as u64;
+        bits += length;
+        while bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+        }
+    }
+    if bits > 0 {
+        result.push(((buffer << (8 - bits)) as u8) | (0xff >> bits));
+    }
+    result
+}
+
+/// Return length of Huffman encoded `data` in bytes
+pub(crate) fn huffman_encoded_len(data: &[u8]) -> usize {
+    let bits: usize = data
+        .iter()
+        .map(|byte| HUFFMAN_CODES[*byte as usize].1 as usize)
+        .sum();
+    bits.div_ceil(8)
+}
+
+/// Decode Huffman encoded `data`, EOS and padding longer than 7 bits are errors
+pub(crate) fn huffman_decode(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let decoding = huffman_decoding();
+    let mut result = Vec::with_capacity(data.len() * 8 / 5);
+    let mut code: u32 = 0;
+    let mut length = 0;
+    for byte in data {
+        for shift in (0..8).rev() {
+            code = (code << 1) | ((byte >> shift) & 1) as u32;
+            length += 1;
+            if length > 30 {
+                return Err("Invalid Huffman code");
+            }
+            let first = decoding.first_code[length];
+            if decoding.count[length] > 0 && code >= first && code - first < decoding.count[length]
+            {
+                let symbol = decoding.symbols[decoding.offset[length] + (code - first) as usize];
+                if symbol == 256 {
+                    return Err("EOS symbol in Huffman string");
+                }
+                result.push(symbol as u8);
+                code = 0;
+                length = 0;
+            }
+        }
+    }
+    if length > 7 || code != (1 << length) - 1 {
+        return Err("Invalid Huffman padding");
+    }
+    Ok(result)
+}
+
+/// Append integer with `prefix_bits` bit prefix, `flags` fill the bits above the prefix
+pub(crate) fn encode_integer(value: usize, prefix_bits: u8, flags: u8, output: &mut Vec<u8>) {
+    let max_prefix = (1usize << prefix_bits) - 1;
+    if value < max_prefix {
+        output.push(flags | value as u8);
+        return;
+    }
+    output.push(flags | max_prefix as u8);
+    let mut value = value - max_prefix;
+    while value >= 128 {
+        output.push((value % 128) as u8 | 0x80);
+        value /= 128;
+    }
+    output.push(value as u8);
+}
+
+/// Decode integer with `prefix_bits` bit prefix starting at `position`
+pub(crate) fn decode_integer(
+    input: &[u8],
+    position: &mut usize,
+    prefix_bits: u8,
+) -> Result<usize, &'static str> {
+    let max_prefix = (1usize << prefix_bits) - 1;
+    let Some(first) = input.get(*position) else {
+        return Err("Truncated integer");
+    };
+    *position += 1;
+    let mut value = *first as usize & max_prefix;
+    if value < max_prefix {
+        return Ok(value);
+    }
+    let mut shift = 0;
+    loop {
+        let Some(byte) = input.get(*position) else {
+            return Err("Truncated integer");
+        };
+        *position += 1;
+        let increment = ((*byte & 0x7f) as usize)
+            .checked_shl(shift)
+            .filter(|increment| increment >> shift == (*byte & 0x7f) as usize)
+            .ok_or("Integer overflow")?;
+        value = value.checked_add(increment).ok_or("Integer overflow")?;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+        if shift > 56 {
+            return Err("Integer overflow");
+        }
+    }
+}
+
+/// Append string literal with `prefix_bits` bit length prefix, the Huffman flag is
+/// the bit right above the prefix. Huffman coding is used unless it is longer.
+pub(crate) fn encode_string(
+    value: &str,
+    prefix_bits: u8,
+    flags: u8,
+    huffman: bool,
+    output: &mut Vec<u8>,
+) {
+    let data = value.as_bytes();
+    if huffman && huffman_encoded_len(data) <= data.len() {
+        let encoded = huffman_encode(data);
+        encode_integer(
+            encoded.len(),
+            prefix_bits,
+            flags | (1 << prefix_bits),
+            output,
+        );
+        output.extend_from_slice(&encoded);
+    } else {
+        encode_integer(data.len(), prefix_bits, flags, output);
+        output.extend_from_slice(data);
+    }
+}
+
+/// Decode string literal with `prefix_bits` bit length prefix starting at `position`
+pub(crate) fn decode_string(
+    input: &[u8],
+    position: &mut usize,
+    prefix_bits: u8,
+) -> Result<String, &'static str> {
+    let huffman = input
+        .get(*position)
+        .is_some_and(|byte| byte & (1 << prefix_bits) != 0);
+    let length = decode_integer(input, position, prefix_bits)?;
+    let Some(data) = position
+        .checked_add(length)
+        .and_then(|end| input.get(*position..end))
+    else {
+        return Err("Truncated string literal");
+    };
+    *position += length;
+    let data = if huffman {
+        huffman_decode(data)?
+    } else {
+        data.to_vec()
+    };
+    String::from_utf8(data).map_err(|_| "Header field is not valid UTF-8")
+}
+
+/// Dynamic table, newest entry first
+#[derive(Debug, Clone, Default)]
+struct DynamicTable {
+    entries: VecDeque<(String, String)>,
+    size: usize,
+    max_size: usize,
+}
+
+impl DynamicTable {
+    fn new(max_size: usize) -> Self {
+        DynamicTable {
+            entries: VecDeque::new(),
+            size: 0,
+            max_size,
+        }
+    }
+
+    fn entry_size(name: &str, value: &str) -> usize {
+        name.len() + value.len() + 32
+    }
+
+    fn evict(&mut self, limit: usize) {
+        while self.size > limit {
+            let Some((name, value)) = self.entries.pop_back() else {
+                break;
+            };
+            self.size -= DynamicTable::entry_size(&name, &value);
+        }
+    }
+
+    fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.evict(max_size);
+    }
+
+    /// Insert entry, an entry larger than the table empties it (RFC 7541 section 4.4)
+    fn insert(&mut self, name: String, value: String) {
+        let size = DynamicTable::entry_size(&name, &value);
+        self.evict(self.max_size.saturating_sub(size));
+        if size <= self.max_size {
+            self.size += size;
+            self.entries.push_front((name, value));
+        }
+    }
+
+    /// Return entry of 1-based HPACK index space (static then dynamic)
+    fn get(&self, index: usize) -> Result<(&str, &str), &'static str> {
+        match index {
+            0 => Err("Invalid header table index 0"),
+            1..=61 => Ok(STATIC_TABLE[index - 1]),
+            _ => self
+                .entries
+                .get(index - 62)
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .ok_or("Header table index out of range"),
+        }
+    }
+
+    /// Return `(index of exact match, index of name match)`, static table first
+    fn find(&self, name: &str, value: &str) -> (Option<usize>, Option<usize>) {
+        let entries = STATIC_TABLE
+            .iter()
+            .copied()
+            .chain(self.entries.iter().map(|(n, v)| (n.as_str(), v.as_str())));
+        let mut name_index = None;
+        for (idx, (entry_name, entry_value)) in entries.enumerate() {
+            if entry_name.eq(name) {
+                if entry_value.eq(value) {
+                    return (Some(idx + 1), name_index.or(Some(idx + 1)));
+                }
+                name_index = name_index.or(Some(idx + 1));
+            }
+        }
+        (None, name_index)
+    }
+
+    fn to_multidict(&self) -> MultiDict {
+        let mut results = MultiDict::new_capacity(&self.entries.len());
+        for (name, value) in &self.entries {
+            results.add(MultiElement {
+                key: name.clone(),
+                value: value.clone(),
+                meta: None,
+            });
+        }
+        results
+    }
+}
+
+/// HPACK encoder of one HTTP/2 connection direction
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    table: DynamicTable,
+    huffman: bool,
+    size_update: Option<(usize, usize)>,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Encoder::new(4096)
+    }
+}
+
+impl Encoder {
+    /// Return new encoder with dynamic table limited to `max_table_size` bytes,
+    /// Huffman coding is enabled
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::hpack::Encoder;
+    ///
+    /// let mut encoder = Encoder::new(4096);
+    /// ```
+    pub fn new(max_table_size: usize) -> Self {
+        Encoder {
+            table: DynamicTable::new(max_table_size),
+            huffman: true,
+            size_update: None,
+        }
+    }
+
+    /// Enable or disable Huffman coding of string literals
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::hpack::Encoder;
+    ///
+    /// let headers = MultiDict::from_query_string(":authority=www.example.com").unwrap();
+    /// let mut encoder = Encoder::new(4096);
+    /// encoder.set_huffman(false);
+    /// assert_eq!(encoder.encode(&headers)[..2], [0x41, 0x0f]);
+    /// ```
+    pub fn set_huffman(&mut self, enabled: bool) {
+        self.huffman = enabled;
+    }
+
+    /// Change dynamic table size, the update is signalled at the start of the next block
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::hpack::Encoder;
+    ///
+    /// let mut encoder = Encoder::new(4096);
+    /// encoder.set_max_table_size(0);
+    /// encoder.set_max_table_size(256);
+    /// // both the minimum and the final size are signalled
+    /// assert_eq!(encoder.encode(&MultiDict::new()), vec![0x20, 0x3f, 0xe1, 0x01]);
+    /// ```
+    pub fn set_max_table_size(&mut self, max_table_size: usize) {
+        let minimum = match self.size_update {
+            Some((minimum, _)) => minimum.min(max_table_size),
+            None => max_table_size,
+        };
+        self.size_update = Some((minimum, max_table_size));
+        self.table.set_max_size(max_table_size);
+    }
+
+    /// Return current dynamic table size in bytes
+    pub fn table_size(&self) -> usize {
+        self.table.size
+    }
+
+    /// Encode header list, names are lowercased as HTTP/2 requires
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::hpack::{Decoder, Encoder};
+    ///
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: ":method".to_string(),
+    ///             value: "GET".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "Cookie".to_string(),
+    ///             value: "a=1".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "cookie".to_string(),
+    ///             value: "b=2".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut encoder = Encoder::new(4096);
+    /// let mut decoder = Decoder::new(4096);
+    /// let decoded = decoder.decode(&encoder.encode(&headers)).unwrap();
+    /// println!("{decoded}");
+    /// // MultiDict < ":method":"GET", "cookie":"a=1", "cookie":"b=2" >
+    /// assert_eq!(decoded.keys(), vec![":method", "cookie", "cookie"]);
+    /// // second block reuses dynamic table entries
+    /// assert_eq!(encoder.encode(&headers), vec![0x82, 0xbf, 0xbe]);
+    /// ```
+    pub fn encode(&mut self, headers: &MultiDict) -> Vec<u8> {
+        self.encode_fields(headers.iter().map(|item| (item, false)))
+    }
+
+    /// Encode header list, `never_indexed[idx]` marks element `idx` as sensitive:
+    /// it is sent as never-indexed literal and is not added to the dynamic table
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::hpack::{Decoder, Encoder};
+    ///
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: "authorization".to_string(),
+    ///             value: "secret".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut encoder = Encoder::new(4096);
+    /// let block = encoder.encode_flagged(&headers, &[true]).unwrap();
+    /// assert_eq!(block[0], 0x1f); // never indexed, static name index 23
+    /// assert_eq!(encoder.table_size(), 0);
+    ///
+    /// let (decoded, never_indexed) = Decoder::new(4096).decode_flagged(&block).unwrap();
+    /// assert_eq!(decoded.values(), vec!["secret"]);
+    /// assert_eq!(never_indexed, vec![true]);
+    ///
+    /// assert!(encoder.encode_flagged(&headers, &[]).is_err());
+    /// ```
+    pub fn encode_flagged(
+        &mut self,
+        headers: &MultiDict,
+        never_indexed: &[bool],
+    ) -> Result<Vec<u8>, &'static str> {
+        if never_indexed.len() != headers.len() {
+            return Err("Never indexed flags must match header count");
+        }
+        Ok(self.encode_fields(headers.iter().zip(never_indexed.iter().copied())))
+    }
+
+    fn encode_fields<'a>(
+        &mut self,
+        fields: impl Iterator<Item = (&'a MultiElement, bool)>,
+    ) -> Vec<u8> {
+        let mut output = Vec::new();
+        if let Some((minimum, last)) = self.size_update.take() {
+            if minimum < last {
+                encode_integer(minimum, 5, 0x20, &mut output);
+            }
+            encode_integer(last, 5, 0x20, &mut output);
+        }
+        for (item, never_indexed) in fields {
+            let name = item.key.to_lowercase();
+            let (exact, name_index) = self.table.find(&name, &item.value);
+            match (exact, never_indexed) {
+                (Some(index), false) => encode_integer(index, 7, 0x80, &mut output),
+                _ => {
+                    let (prefix_bits, flags) = if never_indexed { (4, 0x10) } else { (6, 0x40) };
+                    encode_integer(name_index.unwrap_or(0), prefix_bits, flags, &mut output);
+                    if name_index.is_none() {
+                        encode_string(&name, 7, 0, self.huffman, &mut output);
+                    }
+                    encode_string(&item.value, 7, 0, self.huffman, &mut output);
+                    if !never_indexed {
+                        self.table.insert(name, item.value.clone());
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+/// HPACK decoder of one HTTP/2 connection direction
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    table: DynamicTable,
+    max_table_size: usize,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new(4096)
+    }
+}
+
+impl Decoder {
+    /// Return new decoder, `max_table_size` is the `SETTINGS_HEADER_TABLE_SIZE`
+    /// limit the peer encoder must respect
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::hpack::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(4096);
+    /// ```
+    pub fn new(max_table_size: usize) -> Self {
+        Decoder {
+            table: DynamicTable::new(max_table_size),
+            max_table_size,
+        }
+    }
+
+    /// Return current dynamic table size in bytes
+    pub fn table_size(&self) -> usize {
+        self.table.size
+    }
+
+    /// Return dynamic table entries, newest first
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::hpack::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(4096);
+    /// decoder.decode(b"\x40\x0acustom-key\x0dcustom-header").unwrap();
+    /// assert_eq!(decoder.dynamic_table().keys(), vec!["custom-key"]);
+    /// assert_eq!(decoder.table_size(), 55);
+    /// ```
+    pub fn dynamic_table(&self) -> MultiDict {
+        self.table.to_multidict()
+    }
+
+    /// Decode header block into header list
+    ///
+    /// # Examples
+    ///
+    /// RFC 7541 Appendix C.4.1
+    /// ```
+    /// use multidict::hpack::Decoder;
+    ///
+    /// let block = [0x82, 0x86, 0x84, 0x41, 0x8c, 0xf1, 0xe3, 0xc2, 0xe5, 0xf2, 0x3a,
+    ///              0x6b, 0xa0, 0xab, 0x90, 0xf4, 0xff];
+    /// let headers = Decoder::new(4096).decode(&block).unwrap();
+    /// println!("{headers}");
+    /// // MultiDict < ":method":"GET", ":scheme":"http", ":path":"/", ":authority":"www.example.com" >
+    /// assert_eq!(headers.values(), vec!["GET", "http", "/", "www.example.com"]);
+    /// ```
+    ///
+    /// Malformed blocks are rejected
+    /// ```
+    /// use multidict::hpack::Decoder;
+    ///
+    /// assert_eq!(Decoder::new(4096).decode(&[0x80]).unwrap_err(), "Invalid header table index 0");
+    /// assert_eq!(Decoder::new(4096).decode(&[0xbe]).unwrap_err(), "Header table index out of range");
+    /// assert_eq!(Decoder::new(4096).decode(&[0x82, 0x3f, 0xe1, 0x1f]).unwrap_err(),
+    ///            "Dynamic table size update must be at the start of block");
+    /// ```
+    pub fn decode(&mut self, block: &[u8]) -> Result<MultiDict, &'static str> {
+        Ok(self.decode_flagged(block)?.0)
+    }
+
+    /// Decode header block into header list and never-indexed flag of every element
+    pub fn decode_flagged(&mut self, block: &[u8]) -> Result<(MultiDict, Vec<bool>), &'static str> {
+        let mut headers = MultiDict::new();
+        let mut never_indexed = Vec::new();
+        let mut position = 0;
+        while let Some(byte) = block.get(position).copied() {
+            if byte & 0xe0 == 0x20 {
+                if !headers.is_empty() {
+                    return Err("Dynamic table size update must be at the start of block");
+                }
+                let size = decode_integer(block, &mut position, 5)?;
+                if size > self.max_table_size {
+                    return Err("Dynamic table size update exceeds the limit");
+                }
+                self.table.set_max_size(size);
+                continue;
+            }
+            let (name, value, flag) = if byte & 0x80 != 0 {
+                let index = decode_integer(block, &mut position, 7)?;
+                let (name, value) = self.table.get(index)?;
+                (name.to_string(), value.to_string(), false)
+            } else {
+                let prefix_bits = if byte & 0xc0 == 0x40 { 6 } else { 4 };
+                let index = decode_integer(block, &mut position, prefix_bits)?;
+                let name = match index {
+                    0 => decode_string(block, &mut position, 7)?,
+                    _ => self.table.get(index)?.0.to_string(),
+                };
+                let value = decode_string(block, &mut position, 7)?;
+                if prefix_bits == 6 {
+                    self.table.insert(name.clone(), value.clone());
+                }
+                (name, value, byte & 0xf0 == 0x10)
+            };
+            headers.add(MultiElement {
+                key: name,
+                value,
+                meta: None,
+            });
+            never_indexed.push(flag);
+        }
+        Ok((headers, never_indexed))
+    }
+}
@@ -0,0 +1,368 @@
+//! INI family files: plain INI, Python `configparser` style and systemd unit files.
+//!
+//! [`IniFile`] keeps every assignment as written, with repeated keys, and the comment
+//! and blank lines before each element in its [`IniComments`] metadata, so files can
+//! be written back with their comments. The metadata also holds the byte range of the
+//! assignment. [`IniFile::section`] returns the values a program reading the file sees
+//! under the chosen [`IniDialect`].
+use std::fmt;
+
+use crate::metadata::{ElementMeta, Span};
+use crate::{MultiDict, MultiElement};
+
+/// Rules for reading and writing a file
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum IniDialect {
+    /// `key=value`, repeated keys all kept, keys allowed before the first section
+    #[default]
+    Plain,
+    /// `key = value` or `key: value`, keys are case-insensitive and the last value wins,
+    /// lines indented deeper than the key continue its value, blank lines included, and
+    /// `[DEFAULT]` values apply to every section
+    ConfigParser,
+    /// `Key=value`, lines ending with `\` continue on the next line and an empty
+    /// assignment clears earlier values of the key
+    Systemd,
+}
+
+/// Comment and blank lines before an element or section header, kept as written, and
+/// the span of a parsed element
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct IniComments {
+    pub lines: Vec<String>,
+    /// Byte range of the assignment in parsed input, including continuation lines
+    pub span: Option<Span>,
+}
+
+impl ElementMeta for IniComments {
+    fn from_span(span: Span) -> Option<Self> {
+        Some(IniComments {
+            lines: Vec::new(),
+            span: Some(span),
+        })
+    }
+}
+
+/// One section with its assignments in file order, `name` is empty for assignments
+/// before the first section header
+#[derive(Debug, Clone, Default)]
+pub struct IniSection {
+    pub name: String,
+    pub comments: Vec<String>,
+    pub entries: MultiDict<IniComments>,
+}
+
+/// Parsed INI file
+#[derive(Debug, Clone, Default)]
+pub struct IniFile {
+    pub dialect: IniDialect,
+    pub sections: Vec<IniSection>,
+    /// Comment and blank lines after the last assignment
+    pub trailing: Vec<String>,
+}
+
+/// Return true if `line` is a comment in every dialect
+fn is_comment(line: &str) -> bool {
+    line.starts_with(['#', ';'])
+}
+
+impl IniSection {
+    /// Return empty section `name`
+    pub fn new(name: &str) -> Self {
+        IniSection {
+            name: name.to_string(),
+            ..IniSection::default()
+        }
+    }
+
+    /// Append assignment without comments
+    pub fn add(&mut self, key: &str, value: &str) {
+        self.entries.add(MultiElement {
+            key: key.to_string(),
+            value: value.to_string(),
+            meta: None,
+        });
+    }
+}
+
+impl fmt::Display for IniFile {
+    /// File as written by [`IniFile::write`], invalid values are written unchanged
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let delimiter = match self.dialect {
+            IniDialect::ConfigParser => " = ",
+            _ => "=",
+        };
+        for section in &self.sections {
+            for line in &section.comments {
+                writeln!(f, "{line}")?;
+            }
+            if !section.name.is_empty() {
+                writeln!(f, "[{}]", section.name)?;
+            }
+            for item in section.entries.iter() {
+                for line in item.meta.iter().flat_map(|meta| &meta.lines) {
+                    writeln!(f, "{line}")?;
+                }
+                let mut lines = item.value.split('\n');
+                write!(
+                    f,
+                    "{}{delimiter}{}",
+                    item.key,
+                    lines.next().unwrap_or_default()
+                )?;
+                for line in lines {
+                    // blank lines of a value are written empty
+                    if line.is_empty() {
+                        writeln!(f)?;
+                    } else {
+                        write!(f, "\n    {line}")?;
+                    }
+                }
+                writeln!(f)?;
+            }
+        }
+        for line in &self.trailing {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl IniFile {
+    /// Return empty file of `dialect`
+    pub fn new(dialect: IniDialect) -> Self {
+        IniFile {
+            dialect,
+            ..IniFile::default()
+        }
+    }
+
+    /// Parse `input` under `dialect`, every element gets [`IniComments`] with its span
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::ini::{IniDialect, IniFile};
+    ///
+    /// let unit = "[Service]\n# prepare\nExecStartPre=/bin/mkdir -p /run/app\n\
+    ///             ExecStartPre=/bin/chown app /run/app\nExecStart=/usr/bin/app \\\n    --verbose\n";
+    /// let file = IniFile::parse(unit, IniDialect::Systemd).unwrap();
+    /// let service = file.section("Service").unwrap();
+    /// assert_eq!(service.getall("ExecStartPre").unwrap().len(), 2);
+    /// assert_eq!(service.get("ExecStart").unwrap().value, "/usr/bin/app      --verbose");
+    /// let meta = file.sections[0].entries.elements[0].meta.as_ref().unwrap();
+    /// assert_eq!(meta.lines, ["# prepare"]);
+    /// assert_eq!(meta.span.unwrap().slice(unit), "ExecStartPre=/bin/mkdir -p /run/app");
+    /// ```
+    pub fn parse(input: &str, dialect: IniDialect) -> Result<IniFile, &'static str> {
+        let mut file = IniFile::new(dialect);
+        let mut pending: Vec<String> = Vec::new();
+        // indentation of the last assignment of the current section
+        let mut indent: Option<usize> = None;
+        let mut offset = 0;
+        let mut lines = input.split_inclusive('\n').map(|line| {
+            let start = offset;
+            offset += line.len();
+            let line = match line.strip_suffix('\n') {
+                Some(line) => line.strip_suffix('\r').unwrap_or(line),
+                None => line,
+            };
+            (
+                Span {
+                    start,
+                    end: start + line.len(),
+                },
+                line,
+            )
+        });
+        while let Some((mut span, line)) = lines.next() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || is_comment(trimmed) {
+                pending.push(line.trim_end().to_string());
+                continue;
+            }
+            let line_indent = line.len() - line.trim_start().len();
+            if dialect == IniDialect::ConfigParser
+                && indent.is_some_and(|indent| line_indent > indent)
+            {
+                if let Some(item) = file
+                    .sections
+                    .last_mut()
+                    .and_then(|section| section.entries.elements.last_mut())
+                {
+                    // like `empty_lines_in_values`, blank lines in between belong to
+                    // the value and comment lines are dropped
+                    let blanks = pending.drain(..).filter(|line| line.is_empty()).count();
+                    item.value.push_str(&"\n".repeat(blanks + 1));
+                    item.value.push_str(trimmed);
+                    if let Some(known) = item.meta.as_mut().and_then(|meta| meta.span.as_mut()) {
+                        known.end = span.end;
+                    }
+                    continue;
+                }
+            }
+            if let Some(name) = trimmed.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or("INI section header is not closed")?;
+                if name.is_empty() {
+                    return Err("INI section name is empty");
+                }
+                let mut section = IniSection::new(name);
+                section.comments = std::mem::take(&mut pending);
+                file.sections.push(section);
+                indent = None;
+                continue;
+            }
+            let mut logical = trimmed.to_string();
+            if dialect == IniDialect::Systemd {
+                while let Some(start) = logical.strip_suffix('\\') {
+                    logical = start.to_string() + " ";
+                    match lines.by_ref().find(|(_, next)| !is_comment(next.trim_start())) {
+                        Some((next_span, next)) => {
+                            logical.push_str(next.trim_end());
+                            span.end = next_span.end;
+                        }
+                        None => break,
+                    }
+                }
+            }
+            let split = match dialect {
+                IniDialect::ConfigParser => logical.find(['=', ':']),
+                _ => logical.find('='),
+            };
+            let (key, value) = match split {
+                Some(index) => (logical[..index].trim(), logical[index + 1..].trim()),
+                None => return Err("INI line has no assignment"),
+            };
+            if key.is_empty() {
+                return Err("INI key is empty");
+            }
+            if file.sections.is_empty() {
+                if dialect != IniDialect::Plain {
+                    return Err("INI key outside a section");
+                }
+                file.sections.push(IniSection::default());
+            }
+            let meta = IniComments {
+                lines: std::mem::take(&mut pending),
+                span: Some(span),
+            };
+            indent = Some(line_indent);
+            let section = file.sections.last_mut().expect("a section was pushed above");
+            section.entries.add(MultiElement {
+                key: key.to_string(),
+                value: value.to_string(),
+                meta: Some(meta),
+            });
+        }
+        file.trailing = pending;
+        Ok(file)
+    }
+
+    /// Return the file text, multi-line values need the `ConfigParser` dialect
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::ini::{IniDialect, IniFile};
+    ///
+    /// let input = "; database\n[db]\nhost = localhost\n\n# replicas\nhosts = a\n    b\n";
+    /// let file = IniFile::parse(input, IniDialect::ConfigParser).unwrap();
+    /// assert_eq!(file.write().unwrap(), input);
+    /// ```
+    pub fn write(&self) -> Result<String, &'static str> {
+        for section in &self.sections {
+            if section.name.contains(['[', ']', '\n']) {
+                return Err("Invalid INI section name");
+            }
+            for item in section.entries.iter() {
+                if item.key.is_empty() || item.key.contains(['=', '\n']) {
+                    return Err("Invalid INI key");
+                }
+                if item.value.contains('\n') && self.dialect != IniDialect::ConfigParser {
+                    return Err("Multi-line INI value needs the ConfigParser dialect");
+                }
+            }
+        }
+        Ok(self.to_string())
+    }
+
+    /// Return distinct section names in order of first appearance
+    pub fn section_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for section in &self.sections {
+            if !names.contains(&section.name.as_str()) {
+                names.push(&section.name);
+            }
+        }
+        names
+    }
+
+    /// Return values of section `name` as the dialect reads them, sections with the
+    /// same name are merged
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::ini::{IniDialect, IniFile};
+    ///
+    /// let unit = "[Service]\nEnvironment=A=1\nEnvironment=B=2\nEnvironment=\nEnvironment=C=3\n";
+    /// let file = IniFile::parse(unit, IniDialect::Systemd).unwrap();
+    /// println!("{}", file.section("Service").unwrap());
+    /// // MultiDict < "Environment":"C=3" >
+    ///
+    /// let config = "[DEFAULT]\nuser = root\n[server]\nPort = 80\nport = 8080\n";
+    /// let file = IniFile::parse(config, IniDialect::ConfigParser).unwrap();
+    /// println!("{}", file.section("server").unwrap());
+    /// // MultiDict < "port":"8080", "user":"root" >
+    /// ```
+    pub fn section(&self, name: &str) -> Option<MultiDict> {
+        let mut sections = self
+            .sections
+            .iter()
+            .filter(|section| section.name == name)
+            .peekable();
+        sections.peek()?;
+        let mut results = MultiDict::new();
+        for item in sections.flat_map(|section| section.entries.iter()) {
+            let mut key = item.key.clone();
+            match self.dialect {
+                IniDialect::Plain => {}
+                IniDialect::ConfigParser => {
+                    key = key.to_lowercase();
+                    if let Some(known) = results.elements.iter_mut().find(|known| known.key == key)
+                    {
+                        known.value = item.value.clone();
+                        continue;
+                    }
+                }
+                IniDialect::Systemd if item.value.is_empty() => {
+                    results.elements.retain(|known| known.key != key);
+                    continue;
+                }
+                IniDialect::Systemd => {}
+            }
+            results.add(MultiElement {
+                key,
+                value: item.value.clone(),
+                meta: None,
+            });
+        }
+        if self.dialect == IniDialect::ConfigParser && name != "DEFAULT" {
+            if let Some(defaults) = self.section("DEFAULT") {
+                for item in defaults.elements {
+                    if !results.contains(&item.key) {
+                        results.add(item);
+                    }
+                }
+            }
+        }
+        Some(results)
+    }
+
+    /// Return every section as the dialect reads it, in order of first appearance
+    pub fn to_multidicts(&self) -> Vec<(String, MultiDict)> {
+        self.section_names()
+            .into_iter()
+            .filter_map(|name| Some((name.to_string(), self.section(name)?)))
+            .collect()
+    }
+}
@@ -0,0 +1,411 @@
+//! Minimal RFC 8259 JSON document model used by the archive and patch formats.
+use std::fmt;
+
+use crate::metadata::Span;
+
+/// JSON value, object members keep their document order
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+const MAX_DEPTH: usize = 128;
+
+impl Json {
+    /// Parse JSON text, surrounding whitespace is allowed
+    pub(crate) fn parse(input: &str) -> Result<Json, &'static str> {
+        Json::parse_spanned(input).map(|(value, _)| value)
+    }
+
+    /// Parse JSON text like [`Json::parse`], also return the span of every value in
+    /// document order, the document itself first
+    pub(crate) fn parse_spanned(input: &str) -> Result<(Json, Vec<Span>), &'static str> {
+        let mut parser = Parser {
+            input: input.as_bytes(),
+            position: 0,
+            spans: Vec::new(),
+        };
+        let value = parser.value(0)?;
+        parser.whitespace();
+        if parser.position != parser.input.len() {
+            return Err("Trailing data after JSON value");
+        }
+        Ok((value, parser.spans))
+    }
+
+    /// Return the number of values in the document order of this value, itself included
+    fn size(&self) -> usize {
+        match self {
+            Json::Array(items) => 1 + items.iter().map(Json::size).sum::<usize>(),
+            Json::Object(members) => {
+                1 + members.iter().map(|(_, value)| value.size()).sum::<usize>()
+            }
+            _ => 1,
+        }
+    }
+
+    /// Return member `key` of an object at document order `index` with its own index
+    pub(crate) fn get_indexed(&self, index: usize, key: &str) -> Option<(&Json, usize)> {
+        let Json::Object(members) = self else {
+            return None;
+        };
+        let mut index = index + 1;
+        for (name, value) in members {
+            if name == key {
+                return Some((value, index));
+            }
+            index += value.size();
+        }
+        None
+    }
+
+    /// Return items of an array at document order `index` with their own indexes
+    pub(crate) fn items_indexed(&self, index: usize) -> Option<Vec<(&Json, usize)>> {
+        let items = self.as_array()?;
+        let mut index = index + 1;
+        Some(
+            items
+                .iter()
+                .map(|item| {
+                    let item_index = index;
+                    index += item.size();
+                    (item, item_index)
+                })
+                .collect(),
+        )
+    }
+
+    /// Return member `key` of an object
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name.eq(key))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Return JSON text indented by two spaces per level
+    pub(crate) fn pretty(&self) -> String {
+        let mut output = String::new();
+        self.write_pretty(&mut output, 0);
+        output
+    }
+
+    fn write_pretty(&self, output: &mut String, level: usize) {
+        let indent = "  ".repeat(level + 1);
+        match self {
+            Json::Array(items) if !items.is_empty() => {
+                output.push_str("[\n");
+                for (idx, item) in items.iter().enumerate() {
+                    output.push_str(&indent);
+                    item.write_pretty(output, level + 1);
+                    output.push_str(if idx + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                output.push_str(&"  ".repeat(level));
+                output.push(']');
+            }
+            Json::Object(members) if !members.is_empty() => {
+                output.push_str("{\n");
+                for (idx, (name, value)) in members.iter().enumerate() {
+                    output.push_str(&indent);
+                    write_string(output, name);
+                    output.push_str(": ");
+                    value.write_pretty(output, level + 1);
+                    output.push_str(if idx + 1 < members.len() { ",\n" } else { "\n" });
+                }
+                output.push_str(&"  ".repeat(level));
+                output.push('}');
+            }
+            _ => output.push_str(&self.to_string()),
+        }
+    }
+}
+
+/// Append JSON string literal of `value`
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{0}'..='\u{1f}' => output.push_str(&format!("\\u{:04x}", character as u32)),
+            _ => output.push(character),
+        }
+    }
+    output.push('"');
+}
+
+impl fmt::Display for Json {
+    /// Compact JSON text
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) if !value.is_finite() => write!(f, "null"),
+            Json::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+                write!(f, "{}", *value as i64)
+            }
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => {
+                let mut output = String::new();
+                write_string(&mut output, value);
+                write!(f, "{output}")
+            }
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (idx, (name, value)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    let mut output = String::new();
+                    write_string(&mut output, name);
+                    write!(f, "{output}:{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    /// Spans of the values read so far, in document order
+    spans: Vec<Span>,
+}
+
+impl Parser<'_> {
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.input.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    fn literal(&mut self, text: &str, value: Json) -> Result<Json, &'static str> {
+        if self.input[self.position..].starts_with(text.as_bytes()) {
+            self.position += text.len();
+            Ok(value)
+        } else {
+            Err("Invalid JSON literal")
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, &'static str> {
+        if depth > MAX_DEPTH {
+            return Err("JSON nesting is too deep");
+        }
+        self.whitespace();
+        let index = self.spans.len();
+        let start = self.position;
+        self.spans.push(Span { start, end: start });
+        let value = self.parse_value(depth)?;
+        self.spans[index].end = self.position;
+        Ok(value)
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Json, &'static str> {
+        match self.input.get(self.position) {
+            None => Err("Unexpected end of JSON"),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.input.get(self.position) == Some(&b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.whitespace();
+                    match self.input.get(self.position) {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err("Expected ',' or ']' in JSON array"),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut members = Vec::new();
+                self.whitespace();
+                if self.input.get(self.position) == Some(&b'}') {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.whitespace();
+                    if self.input.get(self.position) != Some(&b'"') {
+                        return Err("Expected string key in JSON object");
+                    }
+                    let name = self.string()?;
+                    self.whitespace();
+                    if self.input.get(self.position) != Some(&b':') {
+                        return Err("Expected ':' in JSON object");
+                    }
+                    self.position += 1;
+                    members.push((name, self.value(depth + 1)?));
+                    self.whitespace();
+                    match self.input.get(self.position) {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(Json::Object(members));
+                        }
+                        _ => return Err("Expected ',' or '}' in JSON object"),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err("Unexpected character in JSON"),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, &'static str> {
+        let start = self.position;
+        let digits = |parser: &mut Self| {
+            let begin = parser.position;
+            while parser.input.get(parser.position).is_some_and(u8::is_ascii_digit) {
+                parser.position += 1;
+            }
+            parser.position > begin
+        };
+        if self.input[self.position] == b'-' {
+            self.position += 1;
+        }
+        let integer_start = self.position;
+        if !digits(self) || (self.input[integer_start] == b'0' && self.position - integer_start > 1)
+        {
+            return Err("Invalid JSON number");
+        }
+        if self.input.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            if !digits(self) {
+                return Err("Invalid JSON number");
+            }
+        }
+        if let Some(b'e' | b'E') = self.input.get(self.position) {
+            self.position += 1;
+            if let Some(b'+' | b'-') = self.input.get(self.position) {
+                self.position += 1;
+            }
+            if !digits(self) {
+                return Err("Invalid JSON number");
+            }
+        }
+        std::str::from_utf8(&self.input[start..self.position])
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .map(Json::Number)
+            .ok_or("Invalid JSON number")
+    }
+
+    fn hex4(&mut self) -> Result<u32, &'static str> {
+        let digits = self
+            .input
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or("Invalid JSON unicode escape")?;
+        self.position += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        self.position += 1;
+        let mut result = Vec::new();
+        loop {
+            let Some(byte) = self.input.get(self.position).copied() else {
+                return Err("Unterminated JSON string");
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(escape) = self.input.get(self.position).copied() else {
+                        return Err("Unterminated JSON string");
+                    };
+                    self.position += 1;
+                    let character = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            if (0xd800..0xdc00).contains(&code) {
+                                if !self.input[self.position..].starts_with(b"\\u") {
+                                    return Err("Invalid JSON unicode escape");
+                                }
+                                self.position += 2;
+                                let low = self.hex4()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err("Invalid JSON unicode escape");
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or("Invalid JSON unicode escape")?
+                        }
+                        _ => return Err("Invalid JSON string escape"),
+                    };
+                    let mut buffer = [0u8; 4];
+                    result.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+                }
+                0x00..=0x1f => return Err("Control character in JSON string"),
+                _ => result.push(byte),
+            }
+        }
+        String::from_utf8(result).map_err(|_| "JSON string is not valid UTF-8")
+    }
+}
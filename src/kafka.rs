@@ -0,0 +1,149 @@
+//! Kafka record headers in the record batch v2 encoding.
+//!
+//! Headers are ordered, may repeat a key and carry binary values which may be null,
+//! so they map onto [`RecordHeaders`], a `MultiDict` of optional byte values. Counts and
+//! lengths are zigzag varints, a null value has length -1.
+use crate::{MultiDict, MultiElement};
+
+/// Headers of one Kafka record
+pub type RecordHeaders<M = ()> = MultiDict<M, Option<Vec<u8>>>;
+
+/// Append zigzag varint encoding of `value`
+///
+/// # Examples
+/// ```
+/// use multidict::kafka::encode_varint;
+///
+/// let mut out = Vec::new();
+/// encode_varint(-1, &mut out);
+/// encode_varint(150, &mut out);
+/// assert_eq!(out, [0x01, 0xac, 0x02]);
+/// ```
+pub fn encode_varint(value: i32, out: &mut Vec<u8>) {
+    let mut value = ((value << 1) ^ (value >> 31)) as u32;
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Decode zigzag varint at `position`, `position` is moved past it
+///
+/// # Examples
+/// ```
+/// use multidict::kafka::decode_varint;
+///
+/// let mut position = 0;
+/// assert_eq!(decode_varint(&[0x03], &mut position).unwrap(), -2);
+/// assert_eq!(position, 1);
+/// ```
+pub fn decode_varint(input: &[u8], position: &mut usize) -> Result<i32, &'static str> {
+    let mut value: u32 = 0;
+    for shift in 0..5 {
+        let byte = *input.get(*position).ok_or("Truncated varint")?;
+        *position += 1;
+        if shift == 4 && byte > 0x0f {
+            return Err("Varint is too long");
+        }
+        value |= ((byte & 0x7f) as u32) << (7 * shift);
+        if byte & 0x80 == 0 {
+            return Ok((value >> 1) as i32 ^ -((value & 1) as i32));
+        }
+    }
+    Err("Varint is too long")
+}
+
+/// Return `length` bytes at `position` and move past them
+fn take<'a>(
+    input: &'a [u8],
+    position: &mut usize,
+    length: usize,
+) -> Result<&'a [u8], &'static str> {
+    let data = input
+        .get(*position..*position + length)
+        .ok_or("Truncated record header")?;
+    *position += length;
+    Ok(data)
+}
+
+impl<M> MultiDict<M, Option<Vec<u8>>> {
+    /// Return headers count and headers in the record encoding
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::kafka::RecordHeaders;
+    ///
+    /// let headers: RecordHeaders = MultiDict::from_query_string("trace=ab")
+    ///     .unwrap()
+    ///     .map_values(|value| Some(value.into_bytes()));
+    /// assert_eq!(headers.to_kafka_headers().unwrap(),
+    ///            [0x02, 0x0a, b't', b'r', b'a', b'c', b'e', 0x04, b'a', b'b']);
+    /// ```
+    pub fn to_kafka_headers(&self) -> Result<Vec<u8>, &'static str> {
+        let mut out = Vec::new();
+        let length = |value: usize| i32::try_from(value).map_err(|_| "Record header is too long");
+        encode_varint(length(self.len())?, &mut out);
+        for item in self.iter() {
+            encode_varint(length(item.key.len())?, &mut out);
+            out.extend_from_slice(item.key.as_bytes());
+            match &item.value {
+                Some(value) => {
+                    encode_varint(length(value.len())?, &mut out);
+                    out.extend_from_slice(value);
+                }
+                None => encode_varint(-1, &mut out),
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl RecordHeaders {
+    /// Decode headers count and headers at the start of `input`, return the headers and
+    /// the number of bytes read
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::kafka::RecordHeaders;
+    ///
+    /// // two headers: ("id", 0x00ff) and ("tombstone", null)
+    /// let input = [0x04, 0x04, b'i', b'd', 0x04, 0x00, 0xff,
+    ///              0x12, b't', b'o', b'm', b'b', b's', b't', b'o', b'n', b'e', 0x01];
+    /// let (headers, length) = RecordHeaders::from_kafka_headers(&input).unwrap();
+    /// println!("{headers}");
+    /// // MultiDict < "id":b"\x00\xff", "tombstone":null >
+    /// assert_eq!(length, input.len());
+    /// assert_eq!(headers.get("tombstone").unwrap().value, None);
+    /// ```
+    pub fn from_kafka_headers(input: &[u8]) -> Result<(RecordHeaders, usize), &'static str> {
+        let mut position = 0;
+        let count = decode_varint(input, &mut position)?;
+        if count < 0 {
+            return Err("Negative record header count");
+        }
+        let mut results = RecordHeaders::default();
+        for _ in 0..count {
+            let key_length = decode_varint(input, &mut position)?;
+            if key_length < 0 {
+                return Err("Record header key is null");
+            }
+            let key = take(input, &mut position, key_length as usize)?;
+            let key =
+                std::str::from_utf8(key).map_err(|_| "Record header key is not valid UTF-8")?;
+            let value = match decode_varint(input, &mut position)? {
+                -1 => None,
+                length if length < 0 => return Err("Invalid record header value length"),
+                length => Some(take(input, &mut position, length as usize)?.to_vec()),
+            };
+            results.add(MultiElement {
+                key: key.to_string(),
+                value,
+                meta: None,
+            });
+        }
+        Ok((results, position))
+    }
+}
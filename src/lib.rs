@@ -16,11 +16,13 @@
 //! map.add(MultiElement {
 //!             key: "some_key".to_string(),
 //!             value: "some_value_1".to_string(),
+//!             meta: None,
 //!         });
 //! println!("{map}"); // MultiDict < "some_key":"some_value_1" >
 //! map.add(MultiElement {
 //!             key: "some_key".to_string(),
 //!             value: "some_value_2".to_string(),
+//!             meta: None,
 //!         });
 //! println!("{map}");
 //! // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
@@ -34,14 +36,17 @@
 //! map.add(MultiElement {
 //!             key: "some_key".to_string(),
 //!             value: "some_value_1".to_string(),
+//!             meta: None,
 //!         });
 //! map.add(MultiElement {
 //!             key: "some_key".to_string(),
 //!             value: "some_value_2".to_string(),
+//!             meta: None,
 //!         });
 //! map.add(MultiElement {
 //!             key: "some_other_key".to_string(),
 //!             value: "some_value_3".to_string(),
+//!             meta: None,
 //!         });
 //! println!("{}", map.getall("some_key").unwrap());
 //! // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
@@ -55,14 +60,17 @@
 //! map.add(MultiElement {
 //!             key: "some_key".to_string(),
 //!             value: "some_value_1".to_string(),
+//!             meta: None,
 //!         });
 //! map.add(MultiElement {
 //!             key: "some_key".to_string(),
 //!             value: "some_value_2".to_string(),
+//!             meta: None,
 //!         });
 //! map.add(MultiElement {
 //!             key: "some_other_key".to_string(),
 //!             value: "some_value_3".to_string(),
+//!             meta: None,
 //!         });
 //! println!("{map:?}");
 //! // MultiDict { elements: [
@@ -71,16 +79,76 @@
 //! //  MultiElement { key: "some_other_key", value: "some_value_3" }
 //! // ] }
 //! ```
+//!
+//! Parse URL query string, nested conventions are available in [`query`] module
+//! ```
+//! use multidict::MultiDict;
+//!
+//! let map = MultiDict::from_query_string("a=1&a=2&b=3").unwrap();
+//! println!("{map}");
+//! // MultiDict < "a":"1", "a":"2", "b":"3" >
+//! assert_eq!(map.to_query_string(), "a=1&a=2&b=3");
+//! ```
 use core::slice::Iter;
 use std::fmt;
 
+mod digest;
+mod json;
+mod percent;
+pub mod query;
+pub mod openapi;
+pub mod uri_template;
+pub mod signing;
+pub mod structured;
+pub mod message_signatures;
+pub mod hpack;
+pub mod qpack;
+pub mod har;
+pub mod command_line;
+pub mod redaction;
+pub mod metadata;
+pub mod value;
+pub mod chain;
+pub mod merge;
+pub mod diff;
+pub mod views;
+pub mod mqtt;
+pub mod kafka;
+pub mod grpc;
+pub mod sip;
+pub mod stomp;
+pub mod nats;
+pub mod resp;
+pub mod mail;
+pub mod trailers;
+pub mod deb822;
+pub mod ini;
+
+use metadata::ElementMeta;
+use value::ElementValue;
+
 /// `MultiElement` - element of `MultiDict` structure Vec.
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct MultiElement {
+///
+/// `meta` is an optional payload carried along with the pair, see [`metadata`].
+/// Values are strings unless another [`value::ElementValue`] type is given.
+#[derive(Clone, Eq, PartialEq)]
+pub struct MultiElement<M = (), V = String> {
     pub key: String,
-    pub value: String,
+    pub value: V,
+    pub meta: Option<M>,
+}
+impl<M: fmt::Debug, V: fmt::Debug> fmt::Debug for MultiElement<M, V> {
+    /// `MultiElement` debug formatter, `meta` is shown only when set
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("MultiElement");
+        debug.field("key", &self.key).field("value", &self.value);
+        if let Some(meta) = &self.meta {
+            debug.field("meta", meta);
+        }
+        debug.finish()
+    }
 }
-impl fmt::Display for MultiElement {
+impl<M, V: ElementValue> fmt::Display for MultiElement<M, V> {
     /// `MultiElement` instance formatter
     ///
     /// # Examples
@@ -94,7 +162,9 @@ impl fmt::Display for MultiElement {
     /// // MultiElement < "some_key":"some_value_2" >
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, r#"MultiElement < "{}":"{}" >"#, self.key, self.value)
+        write!(f, r#"MultiElement < "{}":"#, self.key)?;
+        self.value.fmt_value(f)?;
+        write!(f, " >")
     }
 }
 impl MultiElement {
@@ -112,6 +182,28 @@ impl MultiElement {
         MultiElement {
             key: new_element[0].clone(),
             value: new_element[1].clone(),
+            meta: None,
+        }
+    }
+}
+impl<M> MultiElement<M> {
+    /// Return element with `meta` payload
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiElement;
+    /// use multidict::metadata::Span;
+    ///
+    /// let element = MultiElement::with_meta(["a".to_string(), "1".to_string()],
+    ///                                       Span { start: 0, end: 3 });
+    /// println!("{element:?}");
+    /// // MultiElement { key: "a", value: "1", meta: Span { start: 0, end: 3 } }
+    /// ```
+    pub fn with_meta(new_element: [String; 2], meta: M) -> Self {
+        MultiElement {
+            key: new_element[0].clone(),
+            value: new_element[1].clone(),
+            meta: Some(meta),
         }
     }
 }
@@ -120,11 +212,44 @@ impl MultiElement {
 /// similar keys with different values in map-like structure.
 ///
 /// Was inspired by Python `MultiDict` library
-#[derive(Default, Debug, Clone)]
-pub struct MultiDict {
-    pub elements: Vec<MultiElement>,
+#[derive(Clone)]
+pub struct MultiDict<M = (), V = String> {
+    pub elements: Vec<MultiElement<M, V>>,
+    redaction: redaction::RedactionPolicy,
+}
+impl<M, V> Default for MultiDict<M, V> {
+    fn default() -> Self {
+        MultiDict {
+            elements: Vec::new(),
+            redaction: redaction::RedactionPolicy::Disabled,
+        }
+    }
+}
+impl<M, V, K: Into<String>, T: Into<V>> FromIterator<(K, T)> for MultiDict<M, V> {
+    /// Collect key-value pairs in order, elements get no metadata
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let map: MultiDict = [("a", "1"), ("b", "2"), ("a", "3")].into_iter().collect();
+    /// assert_eq!(map.to_query_string(), "a=1&b=2&a=3");
+    /// ```
+    fn from_iter<I: IntoIterator<Item = (K, T)>>(pairs: I) -> Self {
+        MultiDict {
+            elements: pairs
+                .into_iter()
+                .map(|(key, value)| MultiElement {
+                    key: key.into(),
+                    value: value.into(),
+                    meta: None,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
 }
-impl fmt::Display for MultiDict {
+impl<M: ElementMeta, V: ElementValue> fmt::Display for MultiDict<M, V> {
     /// `MultiDict` instance formatter
     ///
     /// # Examples
@@ -135,24 +260,48 @@ impl fmt::Display for MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map);
     /// // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "MultiDict < {} >",
-            self.elements
-                .iter()
-                .map(|item| format!(r#""{}":"{}""#, item.key, item.value))
-                .collect::<Vec<_>>()
-                .join(", ")
-        )
+        write!(f, "MultiDict < ")?;
+        for (idx, item) in self.elements.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, r#""{}":"#, item.key)?;
+            if self.is_redacted(item) {
+                write!(f, r#""{}""#, redaction::REDACTED)?;
+            } else {
+                item.value.fmt_value(f)?;
+            }
+        }
+        write!(f, " >")
+    }
+}
+impl<M: ElementMeta + fmt::Debug, V: fmt::Debug> fmt::Debug for MultiDict<M, V> {
+    /// `MultiDict` debug formatter, values are shown under the redaction policy
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elements: Vec<MultiElement<&M, redaction::Shown<'_, V>>> = self
+            .iter()
+            .map(|item| MultiElement {
+                key: item.key.clone(),
+                value: if self.is_redacted(item) {
+                    redaction::Shown::Redacted
+                } else {
+                    redaction::Shown::Value(&item.value)
+                },
+                meta: item.meta.as_ref(),
+            })
+            .collect();
+        f.debug_struct("MultiDict").field("elements", &elements).finish()
     }
 }
 impl MultiDict {
@@ -167,6 +316,7 @@ impl MultiDict {
     pub fn new() -> Self {
         MultiDict {
             elements: Vec::new(),
+            redaction: redaction::RedactionPolicy::Disabled,
         }
     }
 
@@ -183,10 +333,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map.len());
     /// // 2
@@ -198,14 +350,17 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_other_key".to_string(),
     ///             value: "some_value_3".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map.len());
     /// // 3
@@ -213,9 +368,11 @@ impl MultiDict {
     pub fn new_capacity(capacity: &usize) -> Self {
         MultiDict {
             elements: Vec::with_capacity(*capacity),
+            redaction: redaction::RedactionPolicy::Disabled,
         }
     }
-
+}
+impl<M, V> MultiDict<M, V> {
     /// Return the number of items in MultiDict
     ///
     /// # Examples
@@ -226,10 +383,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map.len());
     /// // 2
@@ -250,10 +409,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// for el in map.iter(){
     ///     println!("{el}")
@@ -269,20 +430,23 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// let mut iterator = map.iter();
     /// assert_eq!(iterator.next(), Some(map.elements.iter().nth(0).unwrap()));
     /// assert_eq!(iterator.next(), Some(&MultiElement {
     ///                                     key: "some_key".to_string(),
     ///                                     value: "some_value_2".to_string(),
+    ///                                     meta: None,
     ///                                 })
     ///         );
     /// ```
-    pub fn iter(&self) -> Iter<MultiElement> {
+    pub fn iter(&self) -> Iter<'_, MultiElement<M, V>> {
         self.elements.iter()
     }
 
@@ -299,10 +463,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map.is_empty());
     /// // false
@@ -328,17 +494,19 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{map}");
     /// // MultiDict < "some_key":"some_value_1" >
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{map}");
     /// // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
     /// ```
-    pub fn add(&mut self, new_item: MultiElement) {
+    pub fn add(&mut self, new_item: MultiElement<M, V>) {
         self.elements.push(new_item);
     }
 
@@ -354,10 +522,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map.get("some_key").unwrap());
     /// // MultiElement < "some_key":"some_value_1" >
@@ -371,15 +541,17 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{:?}", map.get("some_other_key"));
     /// // Err("No matching key found")
     /// ```
-    pub fn get(&self, key: &str) -> Result<&MultiElement, &str> {
+    pub fn get(&self, key: &str) -> Result<&MultiElement<M, V>, &str> {
         for item in &self.elements {
             if item.key.eq(key) {
                 return Ok(item);
@@ -401,10 +573,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map);
     /// // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
@@ -422,10 +596,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{:?}", map.popone("some_other_key"));
     /// // Err("No matching key found")
@@ -433,7 +609,7 @@ impl MultiDict {
     /// // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
     /// ```
     ///
-    pub fn popone(&mut self, key: &str) -> Result<MultiElement, &str> {
+    pub fn popone(&mut self, key: &str) -> Result<MultiElement<M, V>, &str> {
         for (idx, item) in self.elements.iter().enumerate() {
             if item.key.eq(key) {
                 return Ok(self.elements.remove(idx));
@@ -442,57 +618,6 @@ impl MultiDict {
         Err("No matching key found")
     }
 
-    /// Return a list of all key-values for key if key is in the MultiDict
-    /// else - return error
-    ///
-    /// # Examples
-    ///
-    /// If key exists
-    /// ```
-    /// use multidict::{MultiDict, MultiElement};
-    ///
-    /// let mut map = MultiDict::new();
-    /// map.add(MultiElement {
-    ///             key: "some_key".to_string(),
-    ///             value: "some_value_1".to_string(),
-    ///         });
-    /// map.add(MultiElement {
-    ///             key: "some_key".to_string(),
-    ///             value: "some_value_2".to_string(),
-    ///         });
-    /// println!("{}", map.getall("some_key").unwrap());
-    /// // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
-    /// ```
-    ///
-    /// If key not exists
-    /// ```
-    /// use multidict::{MultiDict, MultiElement};
-    ///
-    /// let mut map = MultiDict::new();
-    /// map.add(MultiElement {
-    ///             key: "some_key".to_string(),
-    ///             value: "some_value_1".to_string(),
-    ///         });
-    /// map.add(MultiElement {
-    ///             key: "some_key".to_string(),
-    ///             value: "some_value_2".to_string(),
-    ///         });
-    /// println!("{:?}", map.getall("some_other_key")); // Err("No matching key found")
-    /// ```
-    pub fn getall(&self, key: &str) -> Result<MultiDict, &str> {
-        let mut results = MultiDict::new();
-        for item in &self.elements {
-            if item.key.eq(key) {
-                results.add(item.clone());
-            }
-        }
-        if !results.is_empty() {
-            Ok(results)
-        } else {
-            Err("No matching key found")
-        }
-    }
-
     /// Return True if MultiDict has a key, else False.
     ///
     /// # Examples
@@ -505,10 +630,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map.contains("some_key"));
     /// // true
@@ -522,10 +649,12 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{}", map.contains("some_other_key"));
     /// // false
@@ -551,14 +680,17 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_other_key".to_string(),
     ///             value: "some_value_3".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{:?}", map.keys());
     /// // ["some_key", "some_key", "some_other_key"]
@@ -582,25 +714,87 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_other_key".to_string(),
     ///             value: "some_value_3".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{:?}", map.values());
     /// // ["some_value_1", "some_value_2", "some_value_3"]
     /// ```
-    pub fn values(&self) -> Vec<&String> {
-        let mut results: Vec<&String> = Vec::with_capacity(self.elements.len());
+    pub fn values(&self) -> Vec<&V> {
+        let mut results: Vec<&V> = Vec::with_capacity(self.elements.len());
         for item in &self.elements {
             results.push(&item.value);
         }
         results
     }
+}
+impl<M: Clone, V: Clone> MultiDict<M, V> {
+    /// Return a list of all key-values for key if key is in the MultiDict
+    /// else - return error
+    ///
+    /// # Examples
+    ///
+    /// If key exists
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    ///
+    /// let mut map = MultiDict::new();
+    /// map.add(MultiElement {
+    ///             key: "some_key".to_string(),
+    ///             value: "some_value_1".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.add(MultiElement {
+    ///             key: "some_key".to_string(),
+    ///             value: "some_value_2".to_string(),
+    ///             meta: None,
+    ///         });
+    /// println!("{}", map.getall("some_key").unwrap());
+    /// // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
+    /// ```
+    ///
+    /// If key not exists
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    ///
+    /// let mut map = MultiDict::new();
+    /// map.add(MultiElement {
+    ///             key: "some_key".to_string(),
+    ///             value: "some_value_1".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.add(MultiElement {
+    ///             key: "some_key".to_string(),
+    ///             value: "some_value_2".to_string(),
+    ///             meta: None,
+    ///         });
+    /// println!("{:?}", map.getall("some_other_key")); // Err("No matching key found")
+    /// ```
+    pub fn getall(&self, key: &str) -> Result<MultiDict<M, V>, &str> {
+        let mut results = MultiDict {
+            elements: Vec::new(),
+            redaction: self.redaction.clone(),
+        };
+        for item in &self.elements {
+            if item.key.eq(key) {
+                results.add(item.clone());
+            }
+        }
+        if !results.is_empty() {
+            Ok(results)
+        } else {
+            Err("No matching key found")
+        }
+    }
 
     /// Update the MultiDict with the key/value pairs,
     /// overwriting existing keys/values
@@ -615,16 +809,19 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_other_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{map}");
     /// // MultiDict < "some_key":"some_value_1", "some_other_key":"some_value_2" >
     /// map.update(MultiElement {
     ///             key: "some_other_key".to_string(),
     ///             value: "some_value_3".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{map}");
     /// // MultiDict < "some_key":"some_value_1", "some_other_key":"some_value_3" >
@@ -638,32 +835,46 @@ impl MultiDict {
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_1".to_string(),
+    ///             meta: None,
     ///         });
     /// map.add(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_2".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{map}");
     /// // MultiDict < "some_key":"some_value_1", "some_key":"some_value_2" >
     /// map.update(MultiElement {
     ///             key: "some_key".to_string(),
     ///             value: "some_value_3".to_string(),
+    ///             meta: None,
     ///         });
     /// println!("{map}");
     /// // MultiDict < "some_key":"some_value_3", "some_key":"some_value_3" >
     /// ```
-    pub fn update(&mut self, new_item: MultiElement) {
-        let new_item_key = &new_item.key;
-        let mut ids_for_replace = Vec::new();
-
-        for (idx, item) in self.elements.iter().enumerate() {
-            if item.key.eq(new_item_key) {
-                ids_for_replace.push(idx);
+    ///
+    /// Metadata of replaced elements is kept unless the new element carries its own
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::metadata::Span;
+    ///
+    /// let mut map: MultiDict<Span> = MultiDict::from_query_string_spanned("a=1&b=2").unwrap();
+    /// map.update(MultiElement {
+    ///             key: "b".to_string(),
+    ///             value: "3".to_string(),
+    ///             meta: None,
+    ///         });
+    /// assert_eq!(map.get("b").unwrap().value, "3");
+    /// assert_eq!(map.get("b").unwrap().meta, Some(Span { start: 4, end: 7 }));
+    /// ```
+    pub fn update(&mut self, new_item: MultiElement<M, V>) {
+        for item in self.elements.iter_mut() {
+            if item.key.eq(&new_item.key) {
+                item.value = new_item.value.clone();
+                if new_item.meta.is_some() {
+                    item.meta = new_item.meta.clone();
+                }
             }
         }
-        for idx in ids_for_replace {
-            self.elements.remove(idx);
-            self.elements.insert(idx, new_item.clone());
-        }
     }
 }
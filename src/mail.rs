@@ -0,0 +1,433 @@
+//! Email message headers (RFC 5322) with RFC 2047 encoded words.
+//!
+//! Header names are case-insensitive for lookups but kept as written, and headers such
+//! as `Received` repeat with their order kept exactly. Values are stored unfolded and
+//! undecoded; [`decode_words`] and [`encode_words`] convert between encoded words such
+//! as `=?utf-8?b?...?=` and text. Serialization folds lines longer than 78 characters
+//! at whitespace.
+use std::fmt;
+
+use crate::digest::{base64_decode, base64_encode};
+use crate::metadata::{ElementMeta, Span};
+use crate::{MultiDict, MultiElement};
+
+/// Preferred maximum line length, without CRLF
+pub const LINE_LENGTH: usize = 78;
+
+/// Largest number of UTF-8 bytes in one encoded word, which then stays within 75 characters
+const WORD_BYTES: usize = 45;
+
+/// Return decoded text of encoded word bytes in `charset`
+fn decode_charset(charset: &str, bytes: Vec<u8>) -> Result<String, &'static str> {
+    let charset = charset.split('*').next().unwrap_or_default();
+    match charset.to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" | "us-ascii" => {
+            String::from_utf8(bytes).map_err(|_| "Encoded word is not valid in its charset")
+        }
+        "iso-8859-1" | "latin1" => Ok(bytes.into_iter().map(char::from).collect()),
+        _ => Err("Unsupported encoded word charset"),
+    }
+}
+
+/// Return decoded bytes of Q encoded `text`
+fn decode_q(text: &str) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'_' => out.push(b' '),
+            b'=' => {
+                let pair = [
+                    bytes.next().ok_or("Invalid Q encoding")?,
+                    bytes.next().ok_or("Invalid Q encoding")?,
+                ];
+                let pair = std::str::from_utf8(&pair).map_err(|_| "Invalid Q encoding")?;
+                out.push(u8::from_str_radix(pair, 16).map_err(|_| "Invalid Q encoding")?);
+            }
+            _ => out.push(byte),
+        }
+    }
+    Ok(out)
+}
+
+/// Encoded word with its decoded bytes and its length in the value
+struct EncodedWord<'a> {
+    charset: &'a str,
+    bytes: Vec<u8>,
+    length: usize,
+}
+
+/// Return the encoded word at the start of `input`, `None` if it is not one
+fn encoded_word(input: &str) -> Result<Option<EncodedWord<'_>>, &'static str> {
+    let Some(rest) = input.strip_prefix("=?") else {
+        return Ok(None);
+    };
+    let mut parts = rest.splitn(3, '?');
+    let (Some(charset), Some(encoding), Some(rest)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Ok(None);
+    };
+    let Some(end) = rest.find("?=") else {
+        return Ok(None);
+    };
+    let text = &rest[..end];
+    let token = |part: &str| !part.is_empty() && !part.contains(|char: char| char.is_whitespace());
+    if !token(charset) || text.contains(|char: char| char.is_whitespace()) {
+        return Ok(None);
+    }
+    let bytes = match encoding {
+        "B" | "b" => base64_decode(text).map_err(|_| "Invalid base64 in encoded word")?,
+        "Q" | "q" => decode_q(text)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(EncodedWord {
+        charset,
+        bytes,
+        length: 2 + charset.len() + 1 + encoding.len() + 1 + end + 2,
+    }))
+}
+
+/// Return `value` with RFC 2047 encoded words decoded, whitespace between adjacent
+/// encoded words is dropped
+///
+/// # Examples
+/// ```
+/// use multidict::mail::decode_words;
+///
+/// assert_eq!(decode_words("=?utf-8?b?SGVsbG8sIOS4lueVjA==?=").unwrap(), "Hello, 世界");
+/// assert_eq!(decode_words("=?ISO-8859-1?Q?Andr=E9?= Pirard").unwrap(), "André Pirard");
+/// assert_eq!(decode_words("=?utf-8?q?a?= =?utf-8?q?b?=").unwrap(), "ab");
+/// ```
+pub fn decode_words(value: &str) -> Result<String, &'static str> {
+    let mut out = String::with_capacity(value.len());
+    let mut pending: Option<(&str, Vec<u8>)> = None;
+    let mut rest = value;
+    while let Some(start) = rest.find("=?") {
+        let Some(EncodedWord {
+            charset,
+            bytes,
+            length,
+        }) = encoded_word(&rest[start..])?
+        else {
+            if let Some((charset, bytes)) = pending.take() {
+                out.push_str(&decode_charset(charset, bytes)?);
+            }
+            out.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        };
+        let before = &rest[..start];
+        let adjacent = before.trim().is_empty();
+        match pending.as_mut() {
+            Some((known, data)) if adjacent && known.eq_ignore_ascii_case(charset) => {
+                data.extend(bytes)
+            }
+            _ => {
+                if let Some((known, data)) = pending.take() {
+                    out.push_str(&decode_charset(known, data)?);
+                    if !adjacent {
+                        out.push_str(before);
+                    }
+                } else {
+                    out.push_str(before);
+                }
+                pending = Some((charset, bytes));
+            }
+        }
+        rest = &rest[start + length..];
+    }
+    if let Some((charset, bytes)) = pending {
+        out.push_str(&decode_charset(charset, bytes)?);
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Return `text` unchanged if it is printable ASCII, otherwise as UTF-8 base64 encoded
+/// words of at most 75 characters separated by spaces
+///
+/// # Examples
+/// ```
+/// use multidict::mail::{decode_words, encode_words};
+///
+/// assert_eq!(encode_words("Lunch today?"), "Lunch today?");
+/// assert_eq!(encode_words("Grüße"), "=?utf-8?b?R3LDvMOfZQ==?=");
+/// assert_eq!(decode_words(&encode_words("Grüße")).unwrap(), "Grüße");
+/// ```
+pub fn encode_words(text: &str) -> String {
+    let plain = |byte: u8| byte == b' ' || byte == b'\t' || (0x21..0x7f).contains(&byte);
+    if text.bytes().all(plain) && !text.contains("=?") {
+        return text.to_string();
+    }
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for char in text.chars() {
+        if chunk.len() + char.len_utf8() > WORD_BYTES {
+            words.push(format!("=?utf-8?b?{}?=", base64_encode(chunk.as_bytes())));
+            chunk.clear();
+        }
+        chunk.push(char);
+    }
+    words.push(format!("=?utf-8?b?{}?=", base64_encode(chunk.as_bytes())));
+    words.join(" ")
+}
+
+/// Return header line folded before whitespace so lines stay within [`LINE_LENGTH`]
+/// where possible, each line ending with CRLF. Words too long for any line are not
+/// moved.
+fn fold(name: &str, value: &str) -> String {
+    let line = format!("{name}: {value}");
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut previous = ' ';
+    for (index, char) in line.char_indices() {
+        if (char == ' ' || char == '\t') && previous != ' ' && previous != '\t' {
+            segments.push(&line[start..index]);
+            start = index;
+        }
+        previous = char;
+    }
+    segments.push(&line[start..]);
+    let mut lines = vec![String::new()];
+    for (index, segment) in segments.into_iter().enumerate() {
+        let current = lines.last_mut().expect("lines start with one line");
+        if index > 0 && current.len() + segment.len() > LINE_LENGTH && segment.len() <= LINE_LENGTH
+        {
+            lines.push(segment.to_string());
+        } else {
+            current.push_str(segment);
+        }
+    }
+    lines.join("\r\n") + "\r\n"
+}
+
+/// Return true if `name` is a valid header field name
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|byte| (0x21..0x7f).contains(&byte) && byte != b':')
+}
+
+/// Header section of an email message
+#[derive(Clone)]
+pub struct MailHeaders<M = ()> {
+    headers: MultiDict<M>,
+}
+
+impl<M> Default for MailHeaders<M> {
+    fn default() -> Self {
+        MailHeaders {
+            headers: MultiDict::default(),
+        }
+    }
+}
+
+impl<M: ElementMeta> fmt::Display for MailHeaders<M> {
+    /// Headers in `MultiDict` format, values unfolded and undecoded
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.headers)
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for MailHeaders<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MailHeaders")
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+impl MailHeaders {
+    /// Return empty headers
+    pub fn new() -> Self {
+        MailHeaders::default()
+    }
+
+    /// Parse header section of a message, return the headers and the body after the
+    /// empty line
+    ///
+    /// Lines may end with CRLF or LF, folded lines are unfolded by removing the line
+    /// break.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::mail::MailHeaders;
+    ///
+    /// let message = "Received: from a.example by b.example;\r\n\
+    ///                \tTue, 1 Jul 2003 10:52:37 +0200\r\n\
+    ///                Received: from c.example by a.example\r\n\
+    ///                Subject: =?utf-8?q?caf=C3=A9?=\r\n\r\nHello\r\n";
+    /// let (headers, body) = MailHeaders::from_mail(message).unwrap();
+    /// assert_eq!(headers.getall("received"),
+    ///            vec!["from a.example by b.example;\tTue, 1 Jul 2003 10:52:37 +0200",
+    ///                 "from c.example by a.example"]);
+    /// assert_eq!(headers.get_text("Subject").unwrap().as_deref(), Some("café"));
+    /// assert_eq!(body, "Hello\r\n");
+    /// ```
+    pub fn from_mail(input: &str) -> Result<(MailHeaders, &str), &'static str> {
+        parse_mail(input)
+    }
+}
+
+impl<M: ElementMeta> MailHeaders<M> {
+    /// Parse header section like [`MailHeaders::from_mail`], every field gets metadata
+    /// for the byte range of its lines including folded ones
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::mail::MailHeaders;
+    /// use multidict::metadata::Span;
+    ///
+    /// let message = "From: alice@example.com\r\nDate: someday\r\n\r\nHi\r\n";
+    /// let (headers, _) = MailHeaders::<Span>::from_mail_spanned(message).unwrap();
+    /// let date = headers.headers().get("Date").unwrap();
+    /// assert_eq!(date.meta.unwrap().slice(message), "Date: someday");
+    /// ```
+    pub fn from_mail_spanned(input: &str) -> Result<(MailHeaders<M>, &str), &'static str> {
+        parse_mail(input)
+    }
+}
+
+impl<M> MailHeaders<M> {
+    /// Return the number of header fields
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Return true if there are no header fields
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Return header fields in message order, values unfolded and undecoded
+    pub fn headers(&self) -> &MultiDict<M> {
+        &self.headers
+    }
+
+    /// Append header field with a value which is already encoded
+    pub fn add(&mut self, name: &str, value: &str) -> Result<(), &'static str> {
+        self.add_with_meta(name, value, None)
+    }
+
+    /// Append header field like [`MailHeaders::add`] with metadata
+    fn add_with_meta(
+        &mut self,
+        name: &str,
+        value: &str,
+        meta: Option<M>,
+    ) -> Result<(), &'static str> {
+        if !is_valid_name(name) {
+            return Err("Invalid mail header name");
+        }
+        if value.contains(['\r', '\n']) {
+            return Err("Mail header value contains a line break");
+        }
+        self.headers.add(MultiElement {
+            key: name.to_string(),
+            value: value.trim().to_string(),
+            meta,
+        });
+        Ok(())
+    }
+
+    /// Append header field with `text` as encoded words where it is not plain ASCII
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::mail::MailHeaders;
+    ///
+    /// let mut headers = MailHeaders::new();
+    /// headers.add_text("Subject", "Café").unwrap();
+    /// assert_eq!(headers.get("subject"), Some("=?utf-8?b?Q2Fmw6k=?="));
+    /// assert_eq!(headers.get_text("SUBJECT").unwrap().as_deref(), Some("Café"));
+    /// ```
+    pub fn add_text(&mut self, name: &str, text: &str) -> Result<(), &'static str> {
+        self.add(name, &encode_words(text))
+    }
+
+    /// Return the **first** value of header, ignoring ASCII case
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|item| item.key.eq_ignore_ascii_case(name))
+            .map(|item| item.value.as_str())
+    }
+
+    /// Return all values of header in message order, ignoring ASCII case
+    pub fn getall(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|item| item.key.eq_ignore_ascii_case(name))
+            .map(|item| item.value.as_str())
+            .collect()
+    }
+
+    /// Return the first value of header with encoded words decoded
+    pub fn get_text(&self, name: &str) -> Result<Option<String>, &'static str> {
+        self.get(name).map(decode_words).transpose()
+    }
+
+    /// Remove all values of header, return true if any was present
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.headers.len();
+        self.headers
+            .elements
+            .retain(|item| !item.key.eq_ignore_ascii_case(name));
+        self.headers.len() != count
+    }
+
+    /// Return header section with lines folded at [`LINE_LENGTH`], ending with CRLF
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::mail::MailHeaders;
+    ///
+    /// let mut headers = MailHeaders::new();
+    /// headers.add("To", "alice@example.com, bob@example.com, carol@example.com, dave@example.com").unwrap();
+    /// assert_eq!(headers.to_mail(),
+    ///            "To: alice@example.com, bob@example.com, carol@example.com, dave@example.com\r\n");
+    /// headers.add("Cc", "erin@example.com, frank@example.com, grace@example.com, heidi@example.com, \
+    ///                    ivan@example.com").unwrap();
+    /// assert!(headers.to_mail().ends_with("heidi@example.com,\r\n ivan@example.com\r\n"));
+    /// ```
+    pub fn to_mail(&self) -> String {
+        let mut out = String::new();
+        for item in self.headers.iter() {
+            out.push_str(&fold(&item.key, &item.value));
+        }
+        out
+    }
+}
+
+/// Parse header fields, metadata is built from the span of each field
+fn parse_mail<M: ElementMeta>(input: &str) -> Result<(MailHeaders<M>, &str), &'static str> {
+    let mut fields: Vec<(String, Span)> = Vec::new();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let start = input.len() - rest.len();
+        let (line, next) = match rest.find('\n') {
+            Some(end) => (&rest[..end], &rest[end + 1..]),
+            None => (rest, ""),
+        };
+        rest = next;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() {
+            break;
+        }
+        let end = start + line.len();
+        if line.starts_with([' ', '\t']) {
+            let (previous, span) = fields.last_mut().ok_or("Mail header continues nothing")?;
+            previous.push_str(line);
+            span.end = end;
+        } else {
+            fields.push((line.to_string(), Span { start, end }));
+        }
+    }
+    let mut results = MailHeaders::default();
+    for (field, span) in fields {
+        let (name, value) = field.split_once(':').ok_or("Mail header has no colon")?;
+        results.add_with_meta(name, value, M::from_span(span))?;
+    }
+    Ok((results, rest))
+}
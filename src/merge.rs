@@ -0,0 +1,203 @@
+//! Combining two `MultiDict` with a [`MergeStrategy`].
+//!
+//! Strategies differ in what happens to keys present in both maps. Elements of keys
+//! found only in one map are always kept, new keys of `other` go to the end.
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{MultiDict, MultiElement};
+
+/// Closure of [`MergeStrategy::PerKey`], called with the key, its existing and incoming
+/// elements and returning the elements to keep
+pub type MergeFn<M> =
+    dyn Fn(&str, Vec<MultiElement<M>>, Vec<MultiElement<M>>) -> Vec<MultiElement<M>> + Send + Sync;
+
+/// How elements of a key present in both maps are combined
+#[derive(Clone, Default)]
+pub enum MergeStrategy<M = ()> {
+    /// Keep every element, `other` elements are appended
+    #[default]
+    Append,
+    /// Elements of `other` replace all existing elements of their key,
+    /// at the position of the first existing one
+    ReplaceAll,
+    /// Keep only the first existing element of the key
+    KeepFirst,
+    /// Keep only the last element of `other` at the position of the first existing one
+    KeepLast,
+    /// Values of `other` replace existing values of their key one by one in order,
+    /// surplus existing elements are removed and surplus new elements appended
+    ReplacePerKey,
+    /// Closure decides about every key of `other`, its result is placed at the
+    /// position of the first existing element
+    PerKey(Arc<MergeFn<M>>),
+}
+
+impl<M> fmt::Debug for MergeStrategy<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeStrategy::Append => write!(f, "Append"),
+            MergeStrategy::ReplaceAll => write!(f, "ReplaceAll"),
+            MergeStrategy::KeepFirst => write!(f, "KeepFirst"),
+            MergeStrategy::KeepLast => write!(f, "KeepLast"),
+            MergeStrategy::ReplacePerKey => write!(f, "ReplacePerKey"),
+            MergeStrategy::PerKey(_) => write!(f, "PerKey(..)"),
+        }
+    }
+}
+
+impl<M> MergeStrategy<M> {
+    /// Return [`MergeStrategy::PerKey`] strategy of `merge`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::merge::MergeStrategy;
+    ///
+    /// let defaults = MultiDict::from_query_string("accept=a&accept=b&lang=en").unwrap();
+    /// let request = MultiDict::from_query_string("accept=c&lang=de").unwrap();
+    /// // accumulate `accept`, override everything else
+    /// let strategy = MergeStrategy::per_key(|key, mut existing, incoming| {
+    ///     if key == "accept" {
+    ///         existing.extend(incoming);
+    ///         existing
+    ///     } else {
+    ///         incoming
+    ///     }
+    /// });
+    /// assert_eq!(defaults.merged(&request, strategy).to_query_string(),
+    ///            "accept=a&accept=b&accept=c&lang=de");
+    /// ```
+    pub fn per_key(
+        merge: impl Fn(&str, Vec<MultiElement<M>>, Vec<MultiElement<M>>) -> Vec<MultiElement<M>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        MergeStrategy::PerKey(Arc::new(merge))
+    }
+}
+
+impl<M: Clone> MultiDict<M> {
+    /// Merge elements of `other` into the MultiDict according to `strategy`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::merge::MergeStrategy;
+    ///
+    /// let other = MultiDict::from_query_string("a=3&c=4&a=5").unwrap();
+    ///
+    /// let mut map = MultiDict::from_query_string("a=1&b=2&a=2").unwrap();
+    /// map.merge(&other, MergeStrategy::Append);
+    /// assert_eq!(map.to_query_string(), "a=1&b=2&a=2&a=3&c=4&a=5");
+    ///
+    /// let mut map = MultiDict::from_query_string("a=1&b=2&a=2").unwrap();
+    /// map.merge(&other, MergeStrategy::ReplaceAll);
+    /// assert_eq!(map.to_query_string(), "a=3&a=5&b=2&c=4");
+    ///
+    /// let mut map = MultiDict::from_query_string("a=1&b=2&a=2").unwrap();
+    /// map.merge(&other, MergeStrategy::KeepFirst);
+    /// assert_eq!(map.to_query_string(), "a=1&b=2&c=4");
+    ///
+    /// let mut map = MultiDict::from_query_string("a=1&b=2&a=2").unwrap();
+    /// map.merge(&other, MergeStrategy::KeepLast);
+    /// assert_eq!(map.to_query_string(), "a=5&b=2&c=4");
+    ///
+    /// let mut map = MultiDict::from_query_string("a=1&b=2&a=2").unwrap();
+    /// map.merge(&other, MergeStrategy::ReplacePerKey);
+    /// assert_eq!(map.to_query_string(), "a=3&b=2&a=5&c=4");
+    /// ```
+    pub fn merge(&mut self, other: &MultiDict<M>, strategy: MergeStrategy<M>) {
+        match strategy {
+            MergeStrategy::Append => self.elements.extend(other.elements.iter().cloned()),
+            MergeStrategy::ReplaceAll => self.replace_groups(other, |_, _, incoming| incoming),
+            MergeStrategy::KeepFirst => self.replace_groups(other, |_, existing, incoming| {
+                if existing.is_empty() {
+                    incoming
+                } else {
+                    existing.into_iter().take(1).collect()
+                }
+            }),
+            MergeStrategy::KeepLast => self.replace_groups(other, |_, existing, incoming| {
+                if existing.is_empty() {
+                    incoming
+                } else {
+                    incoming.into_iter().last().into_iter().collect()
+                }
+            }),
+            MergeStrategy::ReplacePerKey => {
+                let mut incoming: Vec<Option<&MultiElement<M>>> =
+                    other.elements.iter().map(Some).collect();
+                let mut results = Vec::with_capacity(self.len());
+                for item in self.elements.drain(..) {
+                    if !other.contains(&item.key) {
+                        results.push(item);
+                        continue;
+                    }
+                    let next = incoming.iter_mut().find(|new_item| {
+                        new_item.is_some_and(|new_item| new_item.key.eq(&item.key))
+                    });
+                    if let Some(new_item) = next.and_then(Option::take) {
+                        results.push(new_item.clone());
+                    }
+                }
+                results.extend(incoming.into_iter().flatten().cloned());
+                self.elements = results;
+            }
+            MergeStrategy::PerKey(merge) => self.replace_groups(other, &*merge),
+        }
+    }
+
+    /// Return new MultiDict of elements merged according to `strategy`,
+    /// neither input is changed
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::merge::MergeStrategy;
+    ///
+    /// let defaults = MultiDict::from_query_string("page=1&per_page=20").unwrap();
+    /// let request = MultiDict::from_query_string("page=3&q=rust").unwrap();
+    /// let params = defaults.merged(&request, MergeStrategy::ReplaceAll);
+    /// println!("{params}");
+    /// // MultiDict < "page":"3", "per_page":"20", "q":"rust" >
+    /// assert_eq!(defaults.get("page").unwrap().value, "1");
+    /// ```
+    pub fn merged(&self, other: &MultiDict<M>, strategy: MergeStrategy<M>) -> MultiDict<M> {
+        let mut results = self.clone();
+        results.merge(other, strategy);
+        results
+    }
+
+    /// Replace elements of every key of `other` by the result of `merge`,
+    /// placed at the first existing element of the key or at the end
+    fn replace_groups(
+        &mut self,
+        other: &MultiDict<M>,
+        mut merge: impl FnMut(&str, Vec<MultiElement<M>>, Vec<MultiElement<M>>) -> Vec<MultiElement<M>>,
+    ) {
+        let mut groups: Vec<(String, Vec<MultiElement<M>>)> = Vec::new();
+        for key in other.keys() {
+            if groups.iter().any(|(name, _)| name.eq(key)) {
+                continue;
+            }
+            let select = |map: &MultiDict<M>| -> Vec<MultiElement<M>> {
+                map.iter().filter(|item| item.key.eq(key)).cloned().collect()
+            };
+            let replacement = merge(key, select(self), select(other));
+            groups.push((key.clone(), replacement));
+        }
+        let mut results = Vec::with_capacity(self.len());
+        for item in self.elements.drain(..) {
+            match groups.iter_mut().find(|(name, _)| name.eq(&item.key)) {
+                Some((_, replacement)) => results.append(replacement),
+                None => results.push(item),
+            }
+        }
+        for (_, mut replacement) in groups {
+            results.append(&mut replacement);
+        }
+        self.elements = results;
+    }
+}
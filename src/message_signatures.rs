@@ -0,0 +1,471 @@
+//! HTTP Message Signatures (RFC 9421) with message fields taken from a header `MultiDict`.
+//!
+//! Header names are matched case-insensitively, repeated headers are combined
+//! in order with `", "`. Covered components support the `sf`, `key`, `bs` and
+//! `name` parameters; `req` and `tr` are rejected.
+//!
+//! `hmac-sha256` keys are provided by [`HmacSha256Key`], other algorithms
+//! are plugged in by implementing [`SigningKey`] and [`VerifyingKey`].
+//! Asymmetric algorithms (`rsa-pss-sha512`, `ecdsa-p256-sha256`, `ed25519`) are out of
+//! scope, their RFC test vectors are covered up to the signature base.
+use std::fmt;
+
+use crate::structured::{self, BareItem, Item, Member, Parameters};
+use crate::{digest, percent, MultiDict, MultiElement};
+
+/// HTTP message whose components are signed
+#[derive(Debug, Clone, Copy)]
+pub struct HttpMessage<'a> {
+    pub method: Option<&'a str>,
+    pub target_uri: Option<&'a str>,
+    pub status: Option<u16>,
+    pub headers: &'a MultiDict,
+}
+
+impl<'a> HttpMessage<'a> {
+    /// Return request message, `target_uri` is the absolute request URI
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::message_signatures::HttpMessage;
+    ///
+    /// let headers = MultiDict::new();
+    /// let message = HttpMessage::request("POST", "https://example.com/foo?param=Value&Pet=dog", &headers);
+    /// ```
+    pub fn request(method: &'a str, target_uri: &'a str, headers: &'a MultiDict) -> Self {
+        HttpMessage {
+            method: Some(method),
+            target_uri: Some(target_uri),
+            status: None,
+            headers,
+        }
+    }
+
+    /// Return response message
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::message_signatures::HttpMessage;
+    ///
+    /// let headers = MultiDict::new();
+    /// let message = HttpMessage::response(200, &headers);
+    /// ```
+    pub fn response(status: u16, headers: &'a MultiDict) -> Self {
+        HttpMessage {
+            method: None,
+            target_uri: None,
+            status: Some(status),
+            headers,
+        }
+    }
+
+    /// Return `(scheme, authority, path, query)` parts of target URI
+    fn uri_parts(&self) -> Result<(String, String, &'a str, Option<&'a str>), &'static str> {
+        let Some(uri) = self.target_uri else {
+            return Err("Derived component requires request target URI");
+        };
+        let uri = uri.split('#').next().unwrap_or_default();
+        let Some((scheme, rest)) = uri.split_once("://") else {
+            return Err("Target URI must be absolute");
+        };
+        let scheme = scheme.to_lowercase();
+        let end = rest.find(['/', '?']).unwrap_or(rest.len());
+        let mut authority = rest[..end].to_lowercase();
+        let default_port = match scheme.as_str() {
+            "https" => Some(":443"),
+            "http" => Some(":80"),
+            _ => None,
+        };
+        if let Some(port) = default_port {
+            if authority.ends_with(port) {
+                authority.truncate(authority.len() - port.len());
+            }
+        }
+        let (path, query) = match rest[end..].split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (&rest[end..], None),
+        };
+        Ok((scheme, authority, path, query))
+    }
+}
+
+/// Signature parameters: covered components and metadata such as `created` or `keyid`.
+///
+/// Serialized form is the inner list used in `Signature-Input` and `@signature-params`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureParams {
+    pub components: Vec<Item>,
+    pub parameters: Parameters,
+}
+
+impl fmt::Display for SignatureParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let member = Member::InnerList(self.components.clone(), self.parameters.clone());
+        write!(f, "{member}")
+    }
+}
+
+impl SignatureParams {
+    /// Return signature parameters covering plain components without parameters
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::message_signatures::SignatureParams;
+    /// use multidict::structured::BareItem;
+    ///
+    /// let mut params = SignatureParams::new(&["@method", "content-type"]);
+    /// params.parameters.push(("created".to_string(), BareItem::Integer(1618884473)));
+    /// assert_eq!(params.to_string(), r#"("@method" "content-type");created=1618884473"#);
+    /// ```
+    pub fn new(components: &[&str]) -> Self {
+        SignatureParams {
+            components: components
+                .iter()
+                .map(|name| Item {
+                    value: BareItem::String(name.to_string()),
+                    parameters: Vec::new(),
+                })
+                .collect(),
+            parameters: Vec::new(),
+        }
+    }
+
+    /// Parse serialized signature parameters (inner list)
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::message_signatures::SignatureParams;
+    ///
+    /// let input = r#"("@query-param";name="Pet" "example-dict";key="a");keyid="k""#;
+    /// let params = SignatureParams::parse(input).unwrap();
+    /// assert_eq!(params.components.len(), 2);
+    /// assert_eq!(params.to_string(), input);
+    /// assert!(SignatureParams::parse("sig").is_err());
+    /// ```
+    pub fn parse(input: &str) -> Result<SignatureParams, &'static str> {
+        match structured::parse_list(input)?.as_slice() {
+            [member] => SignatureParams::from_member(member),
+            _ => Err("Signature parameters must be a single inner list"),
+        }
+    }
+
+    fn from_member(member: &Member) -> Result<SignatureParams, &'static str> {
+        match member {
+            Member::InnerList(components, parameters) => Ok(SignatureParams {
+                components: components.clone(),
+                parameters: parameters.clone(),
+            }),
+            Member::Item(_) => Err("Signature parameters must be an inner list"),
+        }
+    }
+}
+
+/// Key producing signatures over a signature base
+pub trait SigningKey {
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, &'static str>;
+}
+
+/// Key verifying signatures over a signature base
+pub trait VerifyingKey {
+    fn verify(&self, data: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Shared secret key of `hmac-sha256` algorithm
+#[derive(Clone, Eq, PartialEq)]
+pub struct HmacSha256Key {
+    key: Vec<u8>,
+}
+
+impl fmt::Debug for HmacSha256Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HmacSha256Key {{ .. }}")
+    }
+}
+
+impl HmacSha256Key {
+    /// Return key from raw key material
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::message_signatures::HmacSha256Key;
+    ///
+    /// let key = HmacSha256Key::new(b"secret");
+    /// ```
+    pub fn new(key: &[u8]) -> Self {
+        HmacSha256Key { key: key.to_vec() }
+    }
+
+    /// Return key from base64 encoded key material
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::message_signatures::HmacSha256Key;
+    ///
+    /// assert!(HmacSha256Key::from_base64("c2VjcmV0").is_ok());
+    /// assert!(HmacSha256Key::from_base64("not base64!").is_err());
+    /// ```
+    pub fn from_base64(key: &str) -> Result<Self, &'static str> {
+        Ok(HmacSha256Key {
+            key: digest::base64_decode(key.trim())?,
+        })
+    }
+}
+
+impl SigningKey for HmacSha256Key {
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+        Ok(digest::hmac_sha256(&self.key, data).to_vec())
+    }
+}
+
+impl VerifyingKey for HmacSha256Key {
+    fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        digest::constant_time_eq(&digest::hmac_sha256(&self.key, data), signature)
+    }
+}
+
+/// Return string parameter of component
+fn string_parameter<'a>(component: &'a Item, name: &str) -> Result<Option<&'a str>, &'static str> {
+    match component.parameters.iter().find(|(key, _)| key.eq(name)) {
+        Some((_, BareItem::String(value))) => Ok(Some(value)),
+        Some(_) => Err("Component parameter must be a string"),
+        None => Ok(None),
+    }
+}
+
+/// Return true if boolean flag parameter of component is set
+fn flag_parameter(component: &Item, name: &str) -> Result<bool, &'static str> {
+    match component.parameters.iter().find(|(key, _)| key.eq(name)) {
+        Some((_, BareItem::Boolean(value))) => Ok(*value),
+        Some(_) => Err("Component flag parameter must be a boolean"),
+        None => Ok(false),
+    }
+}
+
+/// Return values of all field lines of header `name`, obs-fold replaced by space
+fn field_lines(headers: &MultiDict, name: &str) -> Vec<String> {
+    headers
+        .iter()
+        .filter(|item| item.key.eq_ignore_ascii_case(name))
+        .map(|item| {
+            item.value
+                .lines()
+                .map(|line| line.trim())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+fn derived_value(
+    message: &HttpMessage,
+    name: &str,
+    component: &Item,
+) -> Result<String, &'static str> {
+    match name {
+        "@method" => message
+            .method
+            .map(str::to_string)
+            .ok_or("Missing request method"),
+        "@status" => message
+            .status
+            .map(|status| format!("{status:03}"))
+            .ok_or("Missing response status"),
+        "@target-uri" => message
+            .target_uri
+            .map(str::to_string)
+            .ok_or("Missing request target URI"),
+        "@scheme" => Ok(message.uri_parts()?.0),
+        "@authority" => Ok(message.uri_parts()?.1),
+        "@path" => {
+            let path = message.uri_parts()?.2;
+            Ok(if path.is_empty() { "/" } else { path }.to_string())
+        }
+        "@query" => Ok(format!("?{}", message.uri_parts()?.3.unwrap_or_default())),
+        "@request-target" => {
+            let (_, _, path, query) = message.uri_parts()?;
+            let path = if path.is_empty() { "/" } else { path };
+            Ok(match query {
+                Some(query) => format!("{path}?{query}"),
+                None => path.to_string(),
+            })
+        }
+        "@query-param" => {
+            let Some(param) = string_parameter(component, "name")? else {
+                return Err("@query-param requires name parameter");
+            };
+            let query = MultiDict::from_query_string(message.uri_parts()?.3.unwrap_or_default())?;
+            let encode = |value: &str| percent::encode(value, percent::is_unreserved, false);
+            let values: Vec<String> = query
+                .iter()
+                .filter(|item| encode(&item.key).eq(param))
+                .map(|item| encode(&item.value))
+                .collect();
+            match values.as_slice() {
+                [value] => Ok(value.clone()),
+                [] => Err("Covered query parameter not found"),
+                _ => Err("Covered query parameter occurs more than once"),
+            }
+        }
+        _ => Err("Unknown derived component"),
+    }
+}
+
+fn field_value(headers: &MultiDict, name: &str, component: &Item) -> Result<String, &'static str> {
+    let lines = field_lines(headers, name);
+    if lines.is_empty() {
+        return Err("Covered field not found");
+    }
+    let key = string_parameter(component, "key")?;
+    let strict = flag_parameter(component, "sf")?;
+    if flag_parameter(component, "bs")? {
+        if key.is_some() || strict {
+            return Err("bs parameter can not be combined with sf or key");
+        }
+        return Ok(lines
+            .iter()
+            .map(|line| BareItem::ByteSequence(line.as_bytes().to_vec()).to_string())
+            .collect::<Vec<_>>()
+            .join(", "));
+    }
+    let combined = lines.join(", ");
+    if let Some(key) = key {
+        let dictionary = structured::parse_dictionary(&combined)?;
+        return match dictionary.iter().find(|(name, _)| name.eq(key)) {
+            Some((_, member)) => Ok(member.to_string()),
+            None => Err("Covered dictionary key not found"),
+        };
+    }
+    if strict {
+        if let Ok(dictionary) = structured::parse_dictionary(&combined) {
+            return Ok(structured::serialize_dictionary(&dictionary));
+        }
+        return Ok(structured::serialize_list(&structured::parse_list(
+            &combined,
+        )?));
+    }
+    Ok(combined)
+}
+
+/// Return signature base for covered components of `params`
+///
+/// # Examples
+/// ```
+/// use multidict::MultiDict;
+/// use multidict::message_signatures::{signature_base, HttpMessage, SignatureParams};
+///
+/// let headers = MultiDict::from_query_string("Cache-Control=max-age%3D60&cache-control=must-revalidate").unwrap();
+/// let message = HttpMessage::request("GET", "https://example.com/a?b=c", &headers);
+/// let params = SignatureParams::parse(r#"("@method" "@request-target" "cache-control")"#).unwrap();
+/// assert_eq!(
+///     signature_base(&message, &params).unwrap(),
+///     "\"@method\": GET\n\
+///      \"@request-target\": /a?b=c\n\
+///      \"cache-control\": max-age=60, must-revalidate\n\
+///      \"@signature-params\": (\"@method\" \"@request-target\" \"cache-control\")"
+/// );
+/// ```
+pub fn signature_base(
+    message: &HttpMessage,
+    params: &SignatureParams,
+) -> Result<String, &'static str> {
+    let mut lines: Vec<String> = Vec::with_capacity(params.components.len() + 1);
+    for (idx, component) in params.components.iter().enumerate() {
+        let BareItem::String(name) = &component.value else {
+            return Err("Component identifier must be a string");
+        };
+        if params.components[..idx].contains(component) {
+            return Err("Component is covered more than once");
+        }
+        if name.eq("@signature-params") || name.chars().any(|c| c.is_ascii_uppercase()) {
+            return Err("Invalid component name");
+        }
+        if flag_parameter(component, "req")? || flag_parameter(component, "tr")? {
+            return Err("req and tr component parameters are not supported");
+        }
+        let value = if name.starts_with('@') {
+            derived_value(message, name, component)?
+        } else {
+            field_value(message.headers, name, component)?
+        };
+        lines.push(format!("{component}: {value}"));
+    }
+    lines.push(format!("\"@signature-params\": {params}"));
+    Ok(lines.join("\n"))
+}
+
+/// Sign message and return `Signature-Input` and `Signature` headers for `label`
+///
+/// # Examples
+/// ```
+/// use multidict::MultiDict;
+/// use multidict::message_signatures::{sign, verify, HmacSha256Key, HttpMessage, SignatureParams};
+///
+/// let mut headers = MultiDict::from_query_string("Content-Type=application/json").unwrap();
+/// let key = HmacSha256Key::new(b"secret");
+/// let message = HttpMessage::request("POST", "https://example.com/foo", &headers);
+/// let signature = sign(&message, "sig1", &SignatureParams::new(&["@method", "content-type"]), &key).unwrap();
+/// println!("{signature}");
+/// // MultiDict < "Signature-Input":"sig1=("@method" "content-type")", "Signature":"sig1=:...:" >
+/// headers.elements.extend(signature.elements);
+///
+/// let message = HttpMessage::request("POST", "https://example.com/foo", &headers);
+/// assert!(verify(&message, "sig1", &key).is_ok());
+/// let message = HttpMessage::request("PUT", "https://example.com/foo", &headers);
+/// assert_eq!(verify(&message, "sig1", &key).unwrap_err(), "Signature verification failed");
+/// ```
+pub fn sign(
+    message: &HttpMessage,
+    label: &str,
+    params: &SignatureParams,
+    key: &dyn SigningKey,
+) -> Result<MultiDict, &'static str> {
+    let base = signature_base(message, params)?;
+    let signature = BareItem::ByteSequence(key.sign(base.as_bytes())?);
+    let mut results = MultiDict::new_capacity(&2);
+    results.add(MultiElement {
+        key: "Signature-Input".to_string(),
+        value: format!("{label}={params}"),
+        meta: None,
+    });
+    results.add(MultiElement {
+        key: "Signature".to_string(),
+        value: format!("{label}={signature}"),
+        meta: None,
+    });
+    Ok(results)
+}
+
+/// Verify signature `label` of message using its `Signature-Input` and `Signature`
+/// headers, return verified signature parameters.
+///
+/// Checking `created`, `expires` and `keyid` against application policy is left to caller.
+pub fn verify(
+    message: &HttpMessage,
+    label: &str,
+    key: &dyn VerifyingKey,
+) -> Result<SignatureParams, &'static str> {
+    let member = |name: &str| -> Result<Member, &'static str> {
+        let dictionary =
+            structured::parse_dictionary(&field_lines(message.headers, name).join(", "))?;
+        match dictionary.into_iter().find(|(key, _)| key.eq(label)) {
+            Some((_, member)) => Ok(member),
+            None => Err("Signature label not found"),
+        }
+    };
+    let params = SignatureParams::from_member(&member("signature-input")?)?;
+    let Member::Item(Item {
+        value: BareItem::ByteSequence(signature),
+        ..
+    }) = member("signature")?
+    else {
+        return Err("Signature must be a byte sequence");
+    };
+    let base = signature_base(message, &params)?;
+    if key.verify(base.as_bytes(), &signature) {
+        Ok(params)
+    } else {
+        Err("Signature verification failed")
+    }
+}
@@ -0,0 +1,139 @@
+//! Per-element metadata carried by `MultiElement::meta`.
+//!
+//! `MultiDict<M>` stores an optional payload of type `M` next to every pair. The
+//! default `M = ()` keeps plain maps free of metadata. [`Span`] records the byte range
+//! of an element in parsed input and [`Metadata`] adds sensitivity and origin.
+//! Parsers with a `_spanned` variant fill in spans through [`ElementMeta::from_span`]:
+//! [`MultiDict::from_query_string_spanned`], the line based formats such as
+//! [`SipHeaders::from_sip_spanned`](crate::sip::SipHeaders::from_sip_spanned) and
+//! [`parse_stanzas_spanned`](crate::deb822::parse_stanzas_spanned), HAR exports and curl
+//! command lines. An element of a line based format spans its line or field including
+//! continuation lines. Parse errors carry no offset, spans locate elements whose values
+//! fail validation after parsing.
+use crate::{MultiDict, MultiElement};
+
+/// Byte range `start..end` of an element in parsed input
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// Return the bytes of `input` covered by the span
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::metadata::Span;
+    ///
+    /// assert_eq!(Span { start: 4, end: 7 }.slice("a=1&b=2"), "b=2");
+    /// ```
+    pub fn slice<'a>(&self, input: &'a str) -> &'a str {
+        &input[self.start..self.end]
+    }
+}
+
+/// Source span, sensitivity and origin of an element
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Metadata {
+    /// Byte range in parsed input
+    pub span: Option<Span>,
+    /// Value is redacted when the map is printed
+    pub sensitive: bool,
+    /// Name of the layer or source which added the element
+    pub origin: Option<String>,
+}
+
+/// Metadata payload understood by parsers and printers
+///
+/// Both methods have defaults, so any type can be used as metadata with an empty impl.
+pub trait ElementMeta: Sized {
+    /// Return metadata for an element parsed from `span`, `None` to leave it unset
+    fn from_span(_span: Span) -> Option<Self> {
+        None
+    }
+
+    /// Return true if the element value must be redacted when printed
+    fn is_sensitive(&self) -> bool {
+        false
+    }
+}
+
+impl ElementMeta for () {}
+
+impl ElementMeta for Span {
+    fn from_span(span: Span) -> Option<Self> {
+        Some(span)
+    }
+}
+
+impl ElementMeta for Metadata {
+    fn from_span(span: Span) -> Option<Self> {
+        Some(Metadata {
+            span: Some(span),
+            ..Default::default()
+        })
+    }
+
+    fn is_sensitive(&self) -> bool {
+        self.sensitive
+    }
+}
+
+impl<M, V> MultiDict<M, V> {
+    /// Return map with every metadata payload converted by `convert`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::metadata::{Metadata, Span};
+    ///
+    /// let map: MultiDict<Span> = MultiDict::from_query_string_spanned("token=s3cr3t&q=1").unwrap();
+    /// let map = map.map_meta(|span| Metadata {
+    ///     span: Some(span),
+    ///     sensitive: span.start == 0,
+    ///     origin: Some("query".to_string()),
+    /// });
+    /// println!("{map}");
+    /// // MultiDict < "token":"***", "q":"1" >
+    /// assert_eq!(map.to_string(), r#"MultiDict < "token":"***", "q":"1" >"#);
+    /// ```
+    pub fn map_meta<N>(self, mut convert: impl FnMut(M) -> N) -> MultiDict<N, V> {
+        let mut results = MultiDict::default();
+        results.set_redaction(self.redaction().clone());
+        for item in self.elements {
+            results.add(MultiElement {
+                key: item.key,
+                value: item.value,
+                meta: item.meta.map(&mut convert),
+            });
+        }
+        results
+    }
+
+    /// Return copy of keys and values without metadata
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::metadata::Span;
+    ///
+    /// let map: MultiDict<Span> = MultiDict::from_query_string_spanned("a=1").unwrap();
+    /// assert_eq!(map.without_meta().get("a").unwrap().meta, None);
+    /// ```
+    pub fn without_meta(&self) -> MultiDict<(), V>
+    where
+        V: Clone,
+    {
+        let mut results = MultiDict::default();
+        results.set_redaction(self.redaction().clone());
+        for item in self.iter() {
+            results.add(MultiElement {
+                key: item.key.clone(),
+                value: item.value.clone(),
+                meta: None,
+            });
+        }
+        results
+    }
+}
@@ -0,0 +1,259 @@
+//! MQTT 5 user properties of a property section.
+//!
+//! A property section is a variable byte integer length followed by properties.
+//! User properties (identifier `0x26`) are UTF-8 string pairs that may repeat a key and
+//! map onto `MultiDict` in order. Other properties are skipped when decoding.
+use crate::{MultiDict, MultiElement};
+
+/// Property identifier of a user property
+pub const USER_PROPERTY: u8 = 0x26;
+
+/// Largest value of a variable byte integer
+pub const MAXIMUM_VARIABLE_BYTE_INTEGER: usize = 268_435_455;
+
+/// Largest possible MQTT packet size, used when no Maximum Packet Size is negotiated
+pub const MAXIMUM_PACKET_SIZE: u32 = 268_435_460;
+
+/// Append variable byte integer encoding of `value`
+///
+/// # Examples
+/// ```
+/// use multidict::mqtt::encode_variable_byte_integer;
+///
+/// let mut out = Vec::new();
+/// encode_variable_byte_integer(16_384, &mut out).unwrap();
+/// assert_eq!(out, [0x80, 0x80, 0x01]);
+/// assert!(encode_variable_byte_integer(268_435_456, &mut out).is_err());
+/// ```
+pub fn encode_variable_byte_integer(value: usize, out: &mut Vec<u8>) -> Result<(), &'static str> {
+    if value > MAXIMUM_VARIABLE_BYTE_INTEGER {
+        return Err("Variable byte integer is too large");
+    }
+    let mut value = value;
+    loop {
+        let byte = (value % 128) as u8;
+        value /= 128;
+        if value == 0 {
+            out.push(byte);
+            return Ok(());
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Decode variable byte integer at `position`, `position` is moved past it
+///
+/// # Examples
+/// ```
+/// use multidict::mqtt::decode_variable_byte_integer;
+///
+/// let mut position = 0;
+/// assert_eq!(decode_variable_byte_integer(&[0xff, 0x7f], &mut position).unwrap(), 16_383);
+/// assert_eq!(position, 2);
+/// ```
+pub fn decode_variable_byte_integer(
+    input: &[u8],
+    position: &mut usize,
+) -> Result<usize, &'static str> {
+    let mut value = 0;
+    for shift in 0..4 {
+        let byte = *input.get(*position).ok_or("Truncated variable byte integer")?;
+        *position += 1;
+        value += ((byte & 0x7f) as usize) << (7 * shift);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("Malformed variable byte integer")
+}
+
+/// Return the number of bytes of the variable byte integer encoding of `value`
+fn variable_byte_integer_len(value: usize) -> usize {
+    match value {
+        0..=127 => 1,
+        128..=16_383 => 2,
+        16_384..=2_097_151 => 3,
+        _ => 4,
+    }
+}
+
+/// Return size of the packet starting at `packet` read from its fixed header,
+/// packets larger than `maximum_packet_size` are rejected before being read
+///
+/// # Examples
+/// ```
+/// use multidict::mqtt::packet_size;
+///
+/// // PUBLISH with remaining length 200
+/// let header = [0x30, 0xc8, 0x01];
+/// assert_eq!(packet_size(&header, 1024).unwrap(), 203);
+/// assert_eq!(packet_size(&header, 128).unwrap_err(), "MQTT packet exceeds maximum packet size");
+/// ```
+pub fn packet_size(packet: &[u8], maximum_packet_size: u32) -> Result<usize, &'static str> {
+    let mut position = 1;
+    if packet.is_empty() {
+        return Err("Truncated MQTT fixed header");
+    }
+    let remaining_length = decode_variable_byte_integer(packet, &mut position)?;
+    let size = position + remaining_length;
+    if size > maximum_packet_size as usize {
+        return Err("MQTT packet exceeds maximum packet size");
+    }
+    Ok(size)
+}
+
+/// Append two byte length and UTF-8 bytes of an MQTT string
+fn encode_string(value: &str, out: &mut Vec<u8>) -> Result<(), &'static str> {
+    if value.len() > usize::from(u16::MAX) {
+        return Err("MQTT string is too long");
+    }
+    if value.contains('\u{0}') {
+        return Err("MQTT string contains U+0000");
+    }
+    out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+/// Return two byte length prefixed data at `position`
+fn decode_binary<'a>(input: &'a [u8], position: &mut usize) -> Result<&'a [u8], &'static str> {
+    let length = input
+        .get(*position..*position + 2)
+        .ok_or("Truncated MQTT property")?;
+    let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+    let data = input
+        .get(*position + 2..*position + 2 + length)
+        .ok_or("Truncated MQTT property")?;
+    *position += 2 + length;
+    Ok(data)
+}
+
+fn decode_string(input: &[u8], position: &mut usize) -> Result<String, &'static str> {
+    let data = decode_binary(input, position)?;
+    match std::str::from_utf8(data) {
+        Ok(value) if !value.contains('\u{0}') => Ok(value.to_string()),
+        _ => Err("Invalid MQTT UTF-8 string"),
+    }
+}
+
+impl MultiDict {
+    /// Return MQTT 5 property section holding all elements as user properties
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let properties = MultiDict::from_query_string("unit=C&tag=a&tag=b").unwrap();
+    /// let section = properties.to_mqtt_properties().unwrap();
+    /// assert_eq!(section[..11], [0x1c, 0x26, 0, 4, b'u', b'n', b'i', b't', 0, 1, b'C']);
+    /// let (decoded, length) = MultiDict::from_mqtt_properties(&section).unwrap();
+    /// assert_eq!(decoded.to_string(), properties.to_string());
+    /// assert_eq!(length, section.len());
+    /// ```
+    pub fn to_mqtt_properties(&self) -> Result<Vec<u8>, &'static str> {
+        let mut properties = Vec::new();
+        for item in self.iter() {
+            properties.push(USER_PROPERTY);
+            encode_string(&item.key, &mut properties)?;
+            encode_string(&item.value, &mut properties)?;
+        }
+        let mut section = Vec::with_capacity(properties.len() + 4);
+        encode_variable_byte_integer(properties.len(), &mut section)?;
+        section.extend(properties);
+        Ok(section)
+    }
+
+    /// Return property section like [`MultiDict::to_mqtt_properties`], checking that the
+    /// packet still fits in `maximum_packet_size`
+    ///
+    /// `packet_length` is the length of the variable header and payload without the
+    /// property section, the fixed header is added here.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let properties = MultiDict::from_query_string("trace=0af7651916cd43dd").unwrap();
+    /// // section is 27 bytes, fixed header 2 bytes
+    /// assert!(properties.to_mqtt_properties_limited(100, 129).is_ok());
+    /// assert_eq!(properties.to_mqtt_properties_limited(100, 128).unwrap_err(),
+    ///            "MQTT packet exceeds maximum packet size");
+    /// ```
+    pub fn to_mqtt_properties_limited(
+        &self,
+        packet_length: usize,
+        maximum_packet_size: u32,
+    ) -> Result<Vec<u8>, &'static str> {
+        let section = self.to_mqtt_properties()?;
+        let remaining_length = packet_length + section.len();
+        if remaining_length > MAXIMUM_VARIABLE_BYTE_INTEGER
+            || 1 + variable_byte_integer_len(remaining_length) + remaining_length
+                > maximum_packet_size as usize
+        {
+            return Err("MQTT packet exceeds maximum packet size");
+        }
+        Ok(section)
+    }
+
+    /// Decode MQTT 5 property section at the start of `input`, return its user properties
+    /// and the section length in bytes. Other properties are validated and skipped.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// // Content Type "json", User Property ("a", "1"), Message Expiry Interval 60
+    /// let section = [0x13, 0x03, 0x00, 0x04, b'j', b's', b'o', b'n',
+    ///                0x26, 0x00, 0x01, b'a', 0x00, 0x01, b'1',
+    ///                0x02, 0x00, 0x00, 0x00, 0x3c, 0xff];
+    /// let (properties, length) = MultiDict::from_mqtt_properties(&section).unwrap();
+    /// println!("{properties}");
+    /// // MultiDict < "a":"1" >
+    /// assert_eq!(length, 20);
+    /// ```
+    pub fn from_mqtt_properties(input: &[u8]) -> Result<(MultiDict, usize), &'static str> {
+        let mut position = 0;
+        let length = decode_variable_byte_integer(input, &mut position)?;
+        let end = position + length;
+        if end > input.len() {
+            return Err("Truncated MQTT property section");
+        }
+        let section = &input[..end];
+        let mut results = MultiDict::new();
+        while position < end {
+            let identifier = decode_variable_byte_integer(section, &mut position)?;
+            let fixed = |size: usize, position: &mut usize| {
+                if *position + size > end {
+                    return Err("Truncated MQTT property");
+                }
+                *position += size;
+                Ok(())
+            };
+            match identifier {
+                0x01 | 0x17 | 0x19 | 0x24 | 0x25 | 0x28 | 0x29 | 0x2a => fixed(1, &mut position)?,
+                0x13 | 0x21 | 0x22 | 0x23 => fixed(2, &mut position)?,
+                0x02 | 0x11 | 0x18 | 0x27 => fixed(4, &mut position)?,
+                0x0b => {
+                    decode_variable_byte_integer(section, &mut position)?;
+                }
+                0x03 | 0x08 | 0x12 | 0x15 | 0x1a | 0x1c | 0x1f => {
+                    decode_string(section, &mut position)?;
+                }
+                0x09 | 0x16 => {
+                    decode_binary(section, &mut position)?;
+                }
+                0x26 => {
+                    let key = decode_string(section, &mut position)?;
+                    let value = decode_string(section, &mut position)?;
+                    results.add(MultiElement {
+                        key,
+                        value,
+                        meta: None,
+                    });
+                }
+                _ => return Err("Unknown MQTT property identifier"),
+            }
+        }
+        Ok((results, end))
+    }
+}
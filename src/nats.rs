@@ -0,0 +1,196 @@
+//! NATS message headers in the `NATS/1.0` header block of `HPUB` and `HMSG`.
+//!
+//! The block is a version line with an optional status code and description, followed by
+//! `Name: value` lines which may repeat, and ends with an empty line. Lines end with CRLF.
+//! Header names are case-sensitive and kept as sent.
+use std::fmt;
+
+use crate::metadata::{ElementMeta, Span};
+use crate::{MultiDict, MultiElement};
+
+/// Version at the start of every header block
+pub const NATS_VERSION: &str = "NATS/1.0";
+
+/// Inline status of a header block, such as `503` for no responders
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NatsStatus {
+    pub code: u16,
+    pub description: Option<String>,
+}
+
+impl fmt::Display for NatsStatus {
+    /// Status as written after the version
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.description {
+            Some(description) => write!(f, "{} {}", self.code, description),
+            None => write!(f, "{}", self.code),
+        }
+    }
+}
+
+impl NatsStatus {
+    /// Return status of `code` without description
+    pub fn new(code: u16) -> Self {
+        NatsStatus {
+            code,
+            description: None,
+        }
+    }
+
+    /// Parse status after the version, `code` is three digits
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::nats::NatsStatus;
+    ///
+    /// let status = NatsStatus::parse("408 Request Timeout").unwrap();
+    /// assert_eq!(status.code, 408);
+    /// assert_eq!(status.description.as_deref(), Some("Request Timeout"));
+    /// assert_eq!(NatsStatus::parse("5o3").unwrap_err(), "Invalid NATS status code");
+    /// ```
+    pub fn parse(input: &str) -> Result<NatsStatus, &'static str> {
+        let (code, description) = match input.split_once(' ') {
+            Some((code, description)) => (code, Some(description.trim())),
+            None => (input, None),
+        };
+        if code.len() != 3 || !code.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err("Invalid NATS status code");
+        }
+        Ok(NatsStatus {
+            code: code.parse().map_err(|_| "Invalid NATS status code")?,
+            description: description
+                .filter(|description| !description.is_empty())
+                .map(str::to_string),
+        })
+    }
+}
+
+impl<M: ElementMeta> MultiDict<M> {
+    /// Decode header block like [`MultiDict::from_nats_headers`], every element gets
+    /// metadata for the byte range of its header line in `input`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::metadata::Span;
+    ///
+    /// let block = b"NATS/1.0\r\nNats-Expected-Last-Sequence: x\r\n\r\n";
+    /// let (headers, _) = MultiDict::<Span>::from_nats_headers_spanned(block).unwrap();
+    /// let span = headers.get("Nats-Expected-Last-Sequence").unwrap().meta.unwrap();
+    /// assert_eq!(&block[span.start..span.end], b"Nats-Expected-Last-Sequence: x");
+    /// ```
+    pub fn from_nats_headers_spanned(
+        input: &[u8],
+    ) -> Result<(MultiDict<M>, Option<NatsStatus>), &'static str> {
+        parse_nats_headers(input)
+    }
+}
+
+impl MultiDict {
+    /// Decode a complete header block, return its headers and status
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let block = b"NATS/1.0\r\nNats-Msg-Id: 1\r\nTrace: a\r\nTrace: b\r\n\r\n";
+    /// let (headers, status) = MultiDict::from_nats_headers(block).unwrap();
+    /// println!("{headers}");
+    /// // MultiDict < "Nats-Msg-Id":"1", "Trace":"a", "Trace":"b" >
+    /// assert_eq!(status, None);
+    /// ```
+    pub fn from_nats_headers(
+        input: &[u8],
+    ) -> Result<(MultiDict, Option<NatsStatus>), &'static str> {
+        parse_nats_headers(input)
+    }
+
+    /// Return header block of elements with an optional status
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::nats::NatsStatus;
+    ///
+    /// let status = NatsStatus::parse("100 Idle Heartbeat").unwrap();
+    /// let headers = MultiDict::from_query_string("Nats-Last-Consumer=0").unwrap();
+    /// assert_eq!(headers.to_nats_headers(Some(&status)).unwrap(),
+    ///            b"NATS/1.0 100 Idle Heartbeat\r\nNats-Last-Consumer: 0\r\n\r\n");
+    /// assert_eq!(MultiDict::new().to_nats_headers(None).unwrap(), b"NATS/1.0\r\n\r\n");
+    /// ```
+    pub fn to_nats_headers(&self, status: Option<&NatsStatus>) -> Result<Vec<u8>, &'static str> {
+        let mut out = String::from(NATS_VERSION);
+        if let Some(status) = status {
+            if !(100..1000).contains(&status.code) {
+                return Err("Invalid NATS status code");
+            }
+            if status
+                .description
+                .as_deref()
+                .is_some_and(|description| description.contains(['\r', '\n']))
+            {
+                return Err("Invalid NATS status description");
+            }
+            out.push(' ');
+            out.push_str(&status.to_string());
+        }
+        out.push_str("\r\n");
+        for item in self.iter() {
+            if item.key.is_empty()
+                || item
+                    .key
+                    .contains(|char: char| char == ':' || char.is_whitespace() || char.is_control())
+            {
+                return Err("Invalid NATS header name");
+            }
+            if item.value.contains(['\r', '\n']) {
+                return Err("Invalid NATS header value");
+            }
+            out.push_str(&item.key);
+            out.push_str(": ");
+            out.push_str(&item.value);
+            out.push_str("\r\n");
+        }
+        out.push_str("\r\n");
+        Ok(out.into_bytes())
+    }
+}
+
+/// Decode header block, metadata is built from the span of each header line
+fn parse_nats_headers<M: ElementMeta>(
+    input: &[u8],
+) -> Result<(MultiDict<M>, Option<NatsStatus>), &'static str> {
+    let input = std::str::from_utf8(input).map_err(|_| "NATS headers are not valid UTF-8")?;
+    let input = input
+        .strip_suffix("\r\n\r\n")
+        .ok_or("NATS header block does not end with an empty line")?;
+    let mut lines = input.split("\r\n");
+    let version = lines.next().unwrap_or_default();
+    let status = match version.strip_prefix(NATS_VERSION) {
+        Some("") => None,
+        Some(status) if status.starts_with(' ') => Some(NatsStatus::parse(status.trim_start())?),
+        _ => return Err("Unsupported NATS header version"),
+    };
+    let mut results = MultiDict::default();
+    let mut start = version.len() + 2;
+    for line in lines {
+        let span = Span {
+            start,
+            end: start + line.len(),
+        };
+        start = span.end + 2;
+        let (key, value) = line.split_once(':').ok_or("NATS header has no colon")?;
+        if key.is_empty() || key.contains(|char: char| char.is_whitespace() || char.is_control()) {
+            return Err("Invalid NATS header name");
+        }
+        if value.contains(['\r', '\n']) {
+            return Err("Invalid NATS header value");
+        }
+        results.add(MultiElement {
+            key: key.to_string(),
+            value: value.trim().to_string(),
+            meta: M::from_span(span),
+        });
+    }
+    Ok((results, status))
+}
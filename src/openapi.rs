@@ -0,0 +1,411 @@
+//! OpenAPI 3 parameter style serialization for `MultiDict`.
+//!
+//! Every distinct key of a `MultiDict` is treated as one array parameter,
+//! whose items are all values of that key in order. Exploded styles repeat the key
+//! for every item, non-exploded styles join items with the style delimiter.
+//! [`MultiDict::to_openapi_object`] instead writes the whole `MultiDict` as one object
+//! parameter with its keys as properties.
+use crate::{percent, MultiDict, MultiElement};
+
+/// OpenAPI 3 parameter `style`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ParameterStyle {
+    /// `a=1&a=2` (explode) or `a=1,2`
+    Form,
+    /// `a=1&a=2` (explode) or `a=1%202`
+    SpaceDelimited,
+    /// `a=1&a=2` (explode) or `a=1|2`
+    PipeDelimited,
+    /// `;a=1;a=2` (explode) or `;a=1,2`
+    Matrix,
+    /// `.1.2` (explode) or `.1,2`, parameter name is not part of the output and `.` in
+    /// values is written as `%2E`
+    Label,
+}
+
+impl ParameterStyle {
+    /// Delimiter between items of a non-exploded array
+    fn delimiter(&self) -> &'static str {
+        match self {
+            ParameterStyle::SpaceDelimited => "%20",
+            ParameterStyle::PipeDelimited => "|",
+            ParameterStyle::Form | ParameterStyle::Matrix | ParameterStyle::Label => ",",
+        }
+    }
+
+    /// Return true for styles used in the query component
+    fn is_query(&self) -> bool {
+        matches!(
+            self,
+            ParameterStyle::Form | ParameterStyle::SpaceDelimited | ParameterStyle::PipeDelimited
+        )
+    }
+}
+
+/// Percent-encode everything but unreserved characters, label style also encodes `.`
+/// which separates its items
+fn encode(value: &str, style: ParameterStyle) -> String {
+    let encoded = percent::encode(value, percent::is_unreserved, false);
+    match style {
+        ParameterStyle::Label => encoded.replace('.', "%2E"),
+        _ => encoded,
+    }
+}
+
+fn serialize(name: &str, values: &[&String], style: ParameterStyle, explode: bool) -> String {
+    let name = encode(name, style);
+    let items: Vec<String> = values.iter().map(|value| encode(value, style)).collect();
+    match style {
+        ParameterStyle::Label if explode => items.iter().map(|item| format!(".{item}")).collect(),
+        ParameterStyle::Label => format!(".{}", items.join(style.delimiter())),
+        ParameterStyle::Matrix => {
+            let pair = |item: &str| {
+                if item.is_empty() {
+                    format!(";{name}")
+                } else {
+                    format!(";{name}={item}")
+                }
+            };
+            if explode {
+                items.iter().map(|item| pair(item)).collect()
+            } else {
+                pair(&items.join(style.delimiter()))
+            }
+        }
+        _ if explode => items
+            .iter()
+            .map(|item| format!("{name}={item}"))
+            .collect::<Vec<_>>()
+            .join("&"),
+        _ => format!("{name}={}", items.join(style.delimiter())),
+    }
+}
+
+/// Split non-exploded array value by the style delimiter
+fn split_items(value: &str, style: ParameterStyle) -> Vec<&str> {
+    match style {
+        ParameterStyle::SpaceDelimited => value
+            .split([' ', '+'])
+            .flat_map(|part| part.split("%20"))
+            .collect(),
+        _ => value.split(style.delimiter()).collect(),
+    }
+}
+
+/// Serialize `pairs` as object parameter `name`, exploded objects write the properties
+/// as parameters of their own
+fn serialize_object(
+    name: &str,
+    pairs: &[(String, String)],
+    style: ParameterStyle,
+    explode: bool,
+) -> Result<String, &'static str> {
+    let name = encode(name, style);
+    let joined = |delimiter: &str| {
+        pairs
+            .iter()
+            .map(|(key, value)| format!("{key}{delimiter}{value}"))
+            .collect::<Vec<_>>()
+    };
+    Ok(match (style, explode) {
+        (ParameterStyle::Form, true) => joined("=").join("&"),
+        (ParameterStyle::Matrix, true) => {
+            joined("=").iter().map(|pair| format!(";{pair}")).collect()
+        }
+        (ParameterStyle::Label, true) => {
+            joined("=").iter().map(|pair| format!(".{pair}")).collect()
+        }
+        (_, true) => return Err("Style does not support exploded objects"),
+        (ParameterStyle::Matrix, false) => format!(";{name}={}", joined(",").join(",")),
+        (ParameterStyle::Label, false) => format!(".{}", joined(",").join(",")),
+        (_, false) => {
+            let delimiter = style.delimiter();
+            format!("{name}={}", joined(delimiter).join(delimiter))
+        }
+    })
+}
+
+impl MultiDict {
+    /// Return values of key serialized as OpenAPI array parameter,
+    /// `None` if key is not in the MultiDict
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::openapi::ParameterStyle;
+    ///
+    /// let mut map = MultiDict::new();
+    /// map.add(MultiElement {
+    ///             key: "color".to_string(),
+    ///             value: "blue".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.add(MultiElement {
+    ///             key: "color".to_string(),
+    ///             value: "black".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let param = |style, explode| map.to_openapi_parameter("color", style, explode).unwrap();
+    /// assert_eq!(param(ParameterStyle::Form, true), "color=blue&color=black");
+    /// assert_eq!(param(ParameterStyle::Form, false), "color=blue,black");
+    /// assert_eq!(param(ParameterStyle::SpaceDelimited, false), "color=blue%20black");
+    /// assert_eq!(param(ParameterStyle::PipeDelimited, false), "color=blue|black");
+    /// assert_eq!(param(ParameterStyle::Matrix, true), ";color=blue;color=black");
+    /// assert_eq!(param(ParameterStyle::Matrix, false), ";color=blue,black");
+    /// assert_eq!(param(ParameterStyle::Label, true), ".blue.black");
+    /// assert_eq!(param(ParameterStyle::Label, false), ".blue,black");
+    /// assert_eq!(map.to_openapi_parameter("size", ParameterStyle::Form, true), None);
+    /// ```
+    pub fn to_openapi_parameter(
+        &self,
+        name: &str,
+        style: ParameterStyle,
+        explode: bool,
+    ) -> Option<String> {
+        let values: Vec<&String> = self
+            .elements
+            .iter()
+            .filter(|item| item.key.eq(name))
+            .map(|item| &item.value)
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(serialize(name, &values, style, explode))
+    }
+
+    /// Return all keys serialized as OpenAPI array parameters.
+    ///
+    /// Exploded query styles keep the original element order, other styles
+    /// group values by key in order of the first key appearance.
+    /// Label style has no parameter names, so it only makes sense for a
+    /// MultiDict with a single key.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::openapi::ParameterStyle;
+    ///
+    /// let map = MultiDict::from_query_string("a=1&b=x%20y&a=2").unwrap();
+    /// assert_eq!(map.to_openapi(ParameterStyle::Form, true), "a=1&b=x%20y&a=2");
+    /// assert_eq!(map.to_openapi(ParameterStyle::Form, false), "a=1,2&b=x%20y");
+    /// assert_eq!(map.to_openapi(ParameterStyle::Matrix, true), ";a=1;a=2;b=x%20y");
+    /// ```
+    pub fn to_openapi(&self, style: ParameterStyle, explode: bool) -> String {
+        if style.is_query() && explode {
+            return self
+                .elements
+                .iter()
+                .map(|item| serialize(&item.key, &[&item.value], style, explode))
+                .collect::<Vec<_>>()
+                .join("&");
+        }
+        let mut keys: Vec<&String> = Vec::new();
+        for key in self.keys() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        let parameters = keys
+            .iter()
+            .filter_map(|key| self.to_openapi_parameter(key, style, explode));
+        if style.is_query() {
+            parameters.collect::<Vec<_>>().join("&")
+        } else {
+            parameters.collect()
+        }
+    }
+
+    /// Parse single OpenAPI array parameter `name` into MultiDict
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::openapi::ParameterStyle;
+    ///
+    /// let map = MultiDict::from_openapi_parameter("id", ".3.4.5", ParameterStyle::Label, true).unwrap();
+    /// assert_eq!(map.keys(), vec!["id", "id", "id"]);
+    /// assert_eq!(map.values(), vec!["3", "4", "5"]);
+    ///
+    /// let map = MultiDict::from_openapi_parameter("id", ".3,4,5", ParameterStyle::Label, false).unwrap();
+    /// assert_eq!(map.values(), vec!["3", "4", "5"]);
+    ///
+    /// let map = MultiDict::from_openapi_parameter("id", "id=3|4", ParameterStyle::PipeDelimited, false).unwrap();
+    /// assert_eq!(map.values(), vec!["3", "4"]);
+    ///
+    /// let result = MultiDict::from_openapi_parameter("id", "other=3", ParameterStyle::Form, true);
+    /// assert_eq!(result.unwrap_err(), "Unexpected parameter name");
+    /// ```
+    pub fn from_openapi_parameter(
+        name: &str,
+        input: &str,
+        style: ParameterStyle,
+        explode: bool,
+    ) -> Result<MultiDict, &'static str> {
+        if style != ParameterStyle::Label {
+            let results = MultiDict::from_openapi(input, style, explode)?;
+            if results.iter().any(|item| item.key.ne(name)) {
+                return Err("Unexpected parameter name");
+            }
+            return Ok(results);
+        }
+        let Some(input) = input.strip_prefix('.') else {
+            return Err("Label parameter must start with '.'");
+        };
+        let items = if explode {
+            input.split('.').collect()
+        } else {
+            split_items(input, style)
+        };
+        let mut results = MultiDict::new();
+        for item in items {
+            results.add(MultiElement {
+                key: name.to_string(),
+                value: percent::decode(item, false)?,
+                meta: None,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Parse OpenAPI array parameters into MultiDict.
+    /// Label style carries no parameter names, use
+    /// [`MultiDict::from_openapi_parameter`] for it.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::openapi::ParameterStyle;
+    ///
+    /// let map = MultiDict::from_openapi("a=1,2&b=3", ParameterStyle::Form, false).unwrap();
+    /// println!("{map}");
+    /// // MultiDict < "a":"1", "a":"2", "b":"3" >
+    /// assert_eq!(map.keys(), vec!["a", "a", "b"]);
+    ///
+    /// let map = MultiDict::from_openapi(";a=1;a=2;empty", ParameterStyle::Matrix, true).unwrap();
+    /// assert_eq!(map.values(), vec!["1", "2", ""]);
+    ///
+    /// let map = MultiDict::from_openapi("a=1%202%203", ParameterStyle::SpaceDelimited, false).unwrap();
+    /// assert_eq!(map.values(), vec!["1", "2", "3"]);
+    /// ```
+    pub fn from_openapi(
+        input: &str,
+        style: ParameterStyle,
+        explode: bool,
+    ) -> Result<MultiDict, &'static str> {
+        let pairs: Vec<&str> = match style {
+            ParameterStyle::Label => return Err("Label style does not carry parameter names"),
+            ParameterStyle::Matrix => match input.strip_prefix(';') {
+                Some(input) => input.split(';').collect(),
+                None if input.is_empty() => Vec::new(),
+                None => return Err("Matrix parameter must start with ';'"),
+            },
+            _ => input
+                .strip_prefix('?')
+                .unwrap_or(input)
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .collect(),
+        };
+        let plus_as_space = style.is_query();
+        let mut results = MultiDict::new();
+        for pair in pairs {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = percent::decode(key, plus_as_space)?;
+            let items = if explode {
+                vec![value]
+            } else {
+                split_items(value, style)
+            };
+            for item in items {
+                results.add(MultiElement {
+                    key: key.clone(),
+                    value: percent::decode(item, plus_as_space)?,
+                    meta: None,
+                });
+            }
+        }
+        Ok(results)
+    }
+
+    /// Return all elements serialized as OpenAPI object parameter `name`, keys are the
+    /// property names. `SpaceDelimited` and `PipeDelimited` have no exploded object form.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::openapi::ParameterStyle;
+    ///
+    /// let map = MultiDict::from_query_string("R=100&G=200&B=150").unwrap();
+    /// let param = |style, explode| map.to_openapi_object("color", style, explode).unwrap();
+    /// assert_eq!(param(ParameterStyle::Form, true), "R=100&G=200&B=150");
+    /// assert_eq!(param(ParameterStyle::Form, false), "color=R,100,G,200,B,150");
+    /// assert_eq!(param(ParameterStyle::Matrix, true), ";R=100;G=200;B=150");
+    /// assert_eq!(param(ParameterStyle::Label, false), ".R,100,G,200,B,150");
+    /// ```
+    pub fn to_openapi_object(
+        &self,
+        name: &str,
+        style: ParameterStyle,
+        explode: bool,
+    ) -> Result<String, &'static str> {
+        let pairs: Vec<(String, String)> = self
+            .elements
+            .iter()
+            .map(|item| (encode(&item.key, style), encode(&item.value, style)))
+            .collect();
+        serialize_object(name, &pairs, style, explode)
+    }
+
+    /// Parse OpenAPI object parameter `name` into MultiDict of its properties
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::openapi::ParameterStyle;
+    ///
+    /// let map = MultiDict::from_openapi_object("color", ".R=100.G=200", ParameterStyle::Label, true).unwrap();
+    /// assert_eq!(map.to_query_string(), "R=100&G=200");
+    ///
+    /// let map = MultiDict::from_openapi_object("color", "color=R|100|G|200", ParameterStyle::PipeDelimited, false).unwrap();
+    /// assert_eq!(map.to_query_string(), "R=100&G=200");
+    ///
+    /// let result = MultiDict::from_openapi_object("color", "color=R,100,G", ParameterStyle::Form, false);
+    /// assert_eq!(result.unwrap_err(), "Object parameter has a property without value");
+    /// ```
+    pub fn from_openapi_object(
+        name: &str,
+        input: &str,
+        style: ParameterStyle,
+        explode: bool,
+    ) -> Result<MultiDict, &'static str> {
+        if explode {
+            return match style {
+                ParameterStyle::Form | ParameterStyle::Matrix => {
+                    MultiDict::from_openapi(input, style, true)
+                }
+                ParameterStyle::Label => {
+                    let input = input
+                        .strip_prefix('.')
+                        .ok_or("Label parameter must start with '.'")?;
+                    let input = format!(";{}", input.replace('.', ";"));
+                    MultiDict::from_openapi(&input, ParameterStyle::Matrix, true)
+                }
+                _ => Err("Style does not support exploded objects"),
+            };
+        }
+        let parameter = MultiDict::from_openapi_parameter(name, input, style, false)?;
+        if parameter.len() % 2 != 0 {
+            return Err("Object parameter has a property without value");
+        }
+        let mut results = MultiDict::new_capacity(&(parameter.len() / 2));
+        for pair in parameter.elements.chunks(2) {
+            results.add(MultiElement {
+                key: pair[0].value.clone(),
+                value: pair[1].value.clone(),
+                meta: None,
+            });
+        }
+        Ok(results)
+    }
+}
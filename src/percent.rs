@@ -0,0 +1,55 @@
+//! Percent-encoding helpers shared by the query string, URI and signing modules.
+
+/// Return true for RFC 3986 `unreserved` characters: `ALPHA / DIGIT / "-" / "." / "_" / "~"`
+pub(crate) fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+/// Percent-encode every byte of `input` for which `keep` returns false.
+///
+/// When `space_as_plus` is set, a space is written as `+`
+/// (`application/x-www-form-urlencoded` convention).
+pub(crate) fn encode(input: &str, keep: impl Fn(u8) -> bool, space_as_plus: bool) -> String {
+    let mut result = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if keep(byte) {
+            result.push(byte as char);
+        } else if space_as_plus && byte == b' ' {
+            result.push('+');
+        } else {
+            result.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    result
+}
+
+/// Decode `%XX` escapes of `input`, optionally treating `+` as a space.
+///
+/// Malformed escapes and escapes which do not form valid UTF-8 are rejected.
+pub(crate) fn decode(input: &str, plus_as_space: bool) -> Result<String, &'static str> {
+    let bytes = input.as_bytes();
+    let mut result: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'%' => {
+                let high = bytes.get(idx + 1).and_then(|b| (*b as char).to_digit(16));
+                let low = bytes.get(idx + 2).and_then(|b| (*b as char).to_digit(16));
+                match (high, low) {
+                    (Some(high), Some(low)) => result.push((high * 16 + low) as u8),
+                    _ => return Err("Invalid percent-encoding"),
+                }
+                idx += 3;
+            }
+            b'+' if plus_as_space => {
+                result.push(b' ');
+                idx += 1;
+            }
+            byte => {
+                result.push(byte);
+                idx += 1;
+            }
+        }
+    }
+    String::from_utf8(result).map_err(|_| "Percent-encoded data is not valid UTF-8")
+}
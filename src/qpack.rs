@@ -0,0 +1,893 @@
+//! QPACK (RFC 9204) field compression for HTTP/3 with `MultiDict` field sections.
+//!
+//! [`Encoder`] turns a `MultiDict` into an encoded field section and writes dynamic
+//! table updates to its encoder stream; [`Decoder`] reads the encoder stream, decodes
+//! field sections and writes acknowledgments to its decoder stream. Streams are plain
+//! byte buffers, so an encoder and a decoder can be wired together in memory.
+//!
+//! The encoder never blocks a stream: it only references dynamic table entries the
+//! decoder has acknowledged. The decoder accepts blocking peers up to
+//! `max_blocked_streams`.
+use std::collections::VecDeque;
+
+use crate::hpack::{decode_integer, decode_string, encode_integer, encode_string};
+use crate::{MultiDict, MultiElement};
+
+/// Static table of RFC 9204 Appendix A, index 0 is the first entry
+const STATIC_TABLE: [(&str, &str); 99] = [
+    (":authority", ""),
+    (":path", "/"),
+    ("age", "0"),
+    ("content-disposition", ""),
+    ("content-length", "0"),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("referer", ""),
+    ("set-cookie", ""),
+    (":method", "CONNECT"),
+    (":method", "DELETE"),
+    (":method", "GET"),
+    (":method", "HEAD"),
+    (":method", "OPTIONS"),
+    (":method", "POST"),
+    (":method", "PUT"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "103"),
+    (":status", "200"),
+    (":status", "304"),
+    (":status", "404"),
+    (":status", "503"),
+    ("accept", "*/*"),
+    ("accept", "application/dns-message"),
+    ("accept-encoding", "gzip, deflate, br"),
+    ("accept-ranges", "bytes"),
+    ("access-control-allow-headers", "cache-control"),
+    ("access-control-allow-headers", "content-type"),
+    ("access-control-allow-origin", "*"),
+    ("cache-control", "max-age=0"),
+    ("cache-control", "max-age=2592000"),
+    ("cache-control", "max-age=604800"),
+    ("cache-control", "no-cache"),
+    ("cache-control", "no-store"),
+    ("cache-control", "public, max-age=31536000"),
+    ("content-encoding", "br"),
+    ("content-encoding", "gzip"),
+    ("content-type", "application/dns-message"),
+    ("content-type", "application/javascript"),
+    ("content-type", "application/json"),
+    ("content-type", "application/x-www-form-urlencoded"),
+    ("content-type", "image/gif"),
+    ("content-type", "image/jpeg"),
+    ("content-type", "image/png"),
+    ("content-type", "text/css"),
+    ("content-type", "text/html; charset=utf-8"),
+    ("content-type", "text/plain"),
+    ("content-type", "text/plain;charset=utf-8"),
+    ("range", "bytes=0-"),
+    ("strict-transport-security", "max-age=31536000"),
+    (
+        "strict-transport-security",
+        "max-age=31536000; includesubdomains",
+    ),
+    (
+        "strict-transport-security",
+        "max-age=31536000; includesubdomains; preload",
+    ),
+    ("vary", "accept-encoding"),
+    ("vary", "origin"),
+    ("x-content-type-options", "nosniff"),
+    ("x-xss-protection", "1; mode=block"),
+    (":status", "100"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "302"),
+    (":status", "400"),
+    (":status", "403"),
+    (":status", "421"),
+    (":status", "425"),
+    (":status", "500"),
+    ("accept-language", ""),
+    ("access-control-allow-credentials", "FALSE"),
+    ("access-control-allow-credentials", "TRUE"),
+    ("access-control-allow-headers", "*"),
+    ("access-control-allow-methods", "get"),
+    ("access-control-allow-methods", "get, post, options"),
+    ("access-control-allow-methods", "options"),
+    ("access-control-expose-headers", "content-length"),
+    ("access-control-request-headers", "content-type"),
+    ("access-control-request-method", "get"),
+    ("access-control-request-method", "post"),
+    ("alt-svc", "clear"),
+    ("authorization", ""),
+    (
+        "content-security-policy",
+        "script-src 'none'; object-src 'none'; base-uri 'none'",
+    ),
+    ("early-data", "1"),
+    ("expect-ct", ""),
+    ("forwarded", ""),
+    ("if-range", ""),
+    ("origin", ""),
+    ("purpose", "prefetch"),
+    ("server", ""),
+    ("timing-allow-origin", "*"),
+    ("upgrade-insecure-requests", "1"),
+    ("user-agent", ""),
+    ("x-forwarded-for", ""),
+    ("x-frame-options", "deny"),
+    ("x-frame-options", "sameorigin"),
+];
+
+/// Return static table entry of `index`
+fn static_entry(index: usize) -> Result<(&'static str, &'static str), &'static str> {
+    STATIC_TABLE
+        .get(index)
+        .copied()
+        .ok_or("Static table index out of range")
+}
+
+/// Return `(index of exact match, index of name match)` in the static table
+fn find_static(name: &str, value: &str) -> (Option<usize>, Option<usize>) {
+    let mut name_index = None;
+    for (idx, (entry_name, entry_value)) in STATIC_TABLE.iter().enumerate() {
+        if entry_name.eq(&name) {
+            if entry_value.eq(&value) {
+                return (Some(idx), Some(idx));
+            }
+            name_index = name_index.or(Some(idx));
+        }
+    }
+    (None, name_index)
+}
+
+/// Instructions may be split across stream reads, a truncated one waits for more data
+fn is_truncated(error: &str) -> bool {
+    error.starts_with("Truncated")
+}
+
+/// Dynamic table with absolute indexing, oldest entry first
+#[derive(Debug, Clone, Default)]
+struct DynamicTable {
+    entries: VecDeque<(String, String)>,
+    evicted: usize,
+    size: usize,
+    capacity: usize,
+}
+
+impl DynamicTable {
+    fn entry_size(name: &str, value: &str) -> usize {
+        name.len() + value.len() + 32
+    }
+
+    fn insert_count(&self) -> usize {
+        self.evicted + self.entries.len()
+    }
+
+    fn get(&self, absolute: usize) -> Result<(&str, &str), &'static str> {
+        absolute
+            .checked_sub(self.evicted)
+            .and_then(|idx| self.entries.get(idx))
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .ok_or("Dynamic table index out of range")
+    }
+
+    /// Return whether the table shrinks to `limit` bytes without evicting entries
+    /// from absolute index `protected` on
+    fn can_evict(&self, limit: usize, protected: usize) -> bool {
+        let mut remaining = self.size;
+        for (absolute, (name, value)) in (self.evicted..).zip(self.entries.iter()) {
+            if remaining <= limit {
+                break;
+            }
+            if absolute >= protected {
+                return false;
+            }
+            remaining -= DynamicTable::entry_size(name, value);
+        }
+        true
+    }
+
+    fn evict(&mut self, limit: usize) {
+        while self.size > limit {
+            let Some((name, value)) = self.entries.pop_front() else {
+                break;
+            };
+            self.size -= DynamicTable::entry_size(&name, &value);
+            self.evicted += 1;
+        }
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict(capacity);
+    }
+
+    fn insert(&mut self, name: String, value: String) -> Result<(), &'static str> {
+        let size = DynamicTable::entry_size(&name, &value);
+        if size > self.capacity {
+            return Err("Dynamic table entry exceeds capacity");
+        }
+        self.evict(self.capacity - size);
+        self.size += size;
+        self.entries.push_back((name, value));
+        Ok(())
+    }
+
+    /// Return absolute index of the newest exact match and of the newest name match
+    fn find(&self, name: &str, value: &str) -> (Option<usize>, Option<usize>) {
+        let mut name_index = None;
+        for (idx, (entry_name, entry_value)) in self.entries.iter().enumerate().rev() {
+            if entry_name.eq(name) {
+                if entry_value.eq(value) {
+                    return (
+                        Some(self.evicted + idx),
+                        name_index.or(Some(self.evicted + idx)),
+                    );
+                }
+                name_index = name_index.or(Some(self.evicted + idx));
+            }
+        }
+        (None, name_index)
+    }
+
+    fn to_multidict(&self) -> MultiDict {
+        let mut results = MultiDict::new_capacity(&self.entries.len());
+        for (name, value) in &self.entries {
+            results.add(MultiElement {
+                key: name.clone(),
+                value: value.clone(),
+                meta: None,
+            });
+        }
+        results
+    }
+}
+
+/// Field line representation chosen by the encoder
+enum FieldLine {
+    Static(usize),
+    Dynamic(usize),
+    StaticName(usize, String, bool),
+    DynamicName(usize, String, bool),
+    Literal(String, String, bool),
+}
+
+/// Field section the decoder has not acknowledged yet
+#[derive(Debug, Clone)]
+struct Outstanding {
+    stream_id: u64,
+    required_insert_count: usize,
+    min_reference: usize,
+}
+
+/// Decoded field section of a request or push stream
+#[derive(Debug, Clone, Default)]
+pub struct FieldSection {
+    pub stream_id: u64,
+    pub headers: MultiDict,
+    pub never_indexed: Vec<bool>,
+}
+
+/// QPACK encoder of one HTTP/3 connection
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    table: DynamicTable,
+    max_table_capacity: usize,
+    known_received_count: usize,
+    outstanding: Vec<Outstanding>,
+    instructions: Vec<u8>,
+    received: Vec<u8>,
+    huffman: bool,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Encoder::new(0)
+    }
+}
+
+impl Encoder {
+    /// Return new encoder, `max_table_capacity` is the peer
+    /// `SETTINGS_QPACK_MAX_TABLE_CAPACITY`; zero restricts the encoder to the static table.
+    /// The whole capacity is taken into use with an instruction on the encoder stream.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::qpack::Encoder;
+    ///
+    /// let mut encoder = Encoder::new(220);
+    /// assert_eq!(encoder.encoder_stream(), vec![0x3f, 0xbd, 0x01]);
+    /// ```
+    pub fn new(max_table_capacity: usize) -> Self {
+        let mut encoder = Encoder {
+            table: DynamicTable::default(),
+            max_table_capacity,
+            known_received_count: 0,
+            outstanding: Vec::new(),
+            instructions: Vec::new(),
+            received: Vec::new(),
+            huffman: true,
+        };
+        if max_table_capacity > 0 {
+            encoder.set_capacity(max_table_capacity).unwrap();
+        }
+        encoder
+    }
+
+    /// Enable or disable Huffman coding of string literals
+    pub fn set_huffman(&mut self, enabled: bool) {
+        self.huffman = enabled;
+    }
+
+    /// Change dynamic table capacity, entries referenced by unacknowledged field sections
+    /// can not be evicted
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::qpack::Encoder;
+    ///
+    /// let mut encoder = Encoder::new(4096);
+    /// assert!(encoder.set_capacity(8192).is_err());
+    /// assert!(encoder.set_capacity(0).is_ok());
+    /// ```
+    pub fn set_capacity(&mut self, capacity: usize) -> Result<(), &'static str> {
+        if capacity > self.max_table_capacity {
+            return Err("Dynamic table capacity exceeds the limit");
+        }
+        if !self.table.can_evict(capacity, self.protected()) {
+            return Err("Dynamic table entry is still referenced");
+        }
+        self.table.set_capacity(capacity);
+        encode_integer(capacity, 5, 0x20, &mut self.instructions);
+        Ok(())
+    }
+
+    /// Return current dynamic table size in bytes
+    pub fn table_size(&self) -> usize {
+        self.table.size
+    }
+
+    /// Return dynamic table entries in absolute index order
+    pub fn dynamic_table(&self) -> MultiDict {
+        self.table.to_multidict()
+    }
+
+    /// Return pending encoder stream instructions, the buffer is emptied
+    pub fn encoder_stream(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.instructions)
+    }
+
+    /// Encode field section of `stream_id`, names are lowercased as HTTP/3 requires
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::qpack::{Decoder, Encoder};
+    ///
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: ":path".to_string(),
+    ///             value: "/index.html".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let mut encoder = Encoder::default();
+    /// encoder.set_huffman(false);
+    /// let section = encoder.encode(0, &headers);
+    /// assert_eq!(section, b"\x00\x00\x51\x0b/index.html");
+    ///
+    /// let decoded = Decoder::default().decode(0, &section).unwrap().unwrap();
+    /// println!("{decoded}");
+    /// // MultiDict < ":path":"/index.html" >
+    /// ```
+    pub fn encode(&mut self, stream_id: u64, headers: &MultiDict) -> Vec<u8> {
+        self.encode_fields(stream_id, headers.iter().map(|item| (item, false)))
+    }
+
+    /// Encode field section, `never_indexed[idx]` marks element `idx` as sensitive:
+    /// it is sent as never-indexed literal and is not inserted into the dynamic table
+    pub fn encode_flagged(
+        &mut self,
+        stream_id: u64,
+        headers: &MultiDict,
+        never_indexed: &[bool],
+    ) -> Result<Vec<u8>, &'static str> {
+        if never_indexed.len() != headers.len() {
+            return Err("Never indexed flags must match header count");
+        }
+        Ok(self.encode_fields(stream_id, headers.iter().zip(never_indexed.iter().copied())))
+    }
+
+    /// Process decoder stream data, incomplete instructions wait for the next call
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::qpack::Encoder;
+    ///
+    /// let mut encoder = Encoder::new(4096);
+    /// // Insert Count Increment before anything was inserted
+    /// assert_eq!(encoder.receive_decoder_stream(&[0x01]).unwrap_err(),
+    ///            "Invalid insert count increment");
+    /// ```
+    pub fn receive_decoder_stream(&mut self, data: &[u8]) -> Result<(), &'static str> {
+        let mut buffer = std::mem::take(&mut self.received);
+        buffer.extend_from_slice(data);
+        let mut position = 0;
+        while position < buffer.len() {
+            let mut cursor = position;
+            match self.decoder_instruction(&buffer, &mut cursor) {
+                Ok(()) => position = cursor,
+                Err(error) if is_truncated(error) => break,
+                Err(error) => return Err(error),
+            }
+        }
+        buffer.drain(..position);
+        self.received = buffer;
+        Ok(())
+    }
+
+    fn decoder_instruction(
+        &mut self,
+        input: &[u8],
+        position: &mut usize,
+    ) -> Result<(), &'static str> {
+        let byte = input[*position];
+        if byte & 0x80 != 0 {
+            let stream_id = decode_integer(input, position, 7)? as u64;
+            let Some(idx) = self
+                .outstanding
+                .iter()
+                .position(|section| section.stream_id == stream_id)
+            else {
+                return Err("Section acknowledgment for unknown stream");
+            };
+            let section = self.outstanding.remove(idx);
+            self.known_received_count =
+                self.known_received_count.max(section.required_insert_count);
+        } else if byte & 0x40 != 0 {
+            let stream_id = decode_integer(input, position, 6)? as u64;
+            self.outstanding.retain(|section| section.stream_id != stream_id);
+        } else {
+            let increment = decode_integer(input, position, 6)?;
+            match self.known_received_count.checked_add(increment) {
+                Some(count) if increment > 0 && count <= self.table.insert_count() => {
+                    self.known_received_count = count;
+                }
+                _ => return Err("Invalid insert count increment"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Return lowest absolute index referenced by unacknowledged field sections
+    fn protected(&self) -> usize {
+        self.outstanding
+            .iter()
+            .map(|section| section.min_reference)
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Insert entry through the encoder stream when it fits
+    fn try_insert(&mut self, name: &str, value: &str, static_name: Option<usize>) {
+        let size = DynamicTable::entry_size(name, value);
+        if size > self.table.capacity
+            || !self
+                .table
+                .can_evict(self.table.capacity - size, self.protected())
+        {
+            return;
+        }
+        match static_name {
+            Some(index) => encode_integer(index, 6, 0xc0, &mut self.instructions),
+            None => encode_string(name, 5, 0x40, self.huffman, &mut self.instructions),
+        }
+        encode_string(value, 7, 0, self.huffman, &mut self.instructions);
+        self.table.insert(name.to_string(), value.to_string()).unwrap();
+    }
+
+    fn encode_fields<'a>(
+        &mut self,
+        stream_id: u64,
+        fields: impl Iterator<Item = (&'a MultiElement, bool)>,
+    ) -> Vec<u8> {
+        let known_received_count = self.known_received_count;
+        let acknowledged = |absolute: &usize| *absolute < known_received_count;
+        let mut lines = Vec::new();
+        for (item, never_indexed) in fields {
+            let name = item.key.to_lowercase();
+            let value = item.value.clone();
+            let (static_exact, static_name) = find_static(&name, &value);
+            if let Some(index) = static_exact {
+                lines.push(FieldLine::Static(index));
+                continue;
+            }
+            let (dynamic_exact, dynamic_name) = self.table.find(&name, &value);
+            if let Some(absolute) = dynamic_exact.filter(acknowledged) {
+                if !never_indexed {
+                    lines.push(FieldLine::Dynamic(absolute));
+                    continue;
+                }
+            }
+            let dynamic_name = dynamic_name.filter(acknowledged);
+            if !never_indexed && dynamic_exact.is_none() {
+                self.try_insert(&name, &value, static_name);
+            }
+            lines.push(match (static_name, dynamic_name) {
+                (Some(index), _) => FieldLine::StaticName(index, value, never_indexed),
+                (None, Some(absolute)) => FieldLine::DynamicName(absolute, value, never_indexed),
+                (None, None) => FieldLine::Literal(name, value, never_indexed),
+            });
+        }
+
+        let references = lines.iter().filter_map(|line| match line {
+            FieldLine::Dynamic(absolute) | FieldLine::DynamicName(absolute, _, _) => {
+                Some(*absolute)
+            }
+            _ => None,
+        });
+        let min_reference = references.clone().min();
+        let required_insert_count = references.max().map_or(0, |absolute| absolute + 1);
+        let mut output = Vec::new();
+        let encoded_insert_count = match required_insert_count {
+            0 => 0,
+            count => count % (2 * (self.max_table_capacity / 32)) + 1,
+        };
+        encode_integer(encoded_insert_count, 8, 0, &mut output);
+        // Base equals the Required Insert Count, so the delta is zero
+        output.push(0);
+        let base = required_insert_count;
+        for line in lines {
+            match line {
+                FieldLine::Static(index) => encode_integer(index, 6, 0xc0, &mut output),
+                FieldLine::Dynamic(absolute) => {
+                    encode_integer(base - 1 - absolute, 6, 0x80, &mut output)
+                }
+                FieldLine::StaticName(index, value, never_indexed) => {
+                    let flags = if never_indexed { 0x70 } else { 0x50 };
+                    encode_integer(index, 4, flags, &mut output);
+                    encode_string(&value, 7, 0, self.huffman, &mut output);
+                }
+                FieldLine::DynamicName(absolute, value, never_indexed) => {
+                    let flags = if never_indexed { 0x60 } else { 0x40 };
+                    encode_integer(base - 1 - absolute, 4, flags, &mut output);
+                    encode_string(&value, 7, 0, self.huffman, &mut output);
+                }
+                FieldLine::Literal(name, value, never_indexed) => {
+                    let flags = if never_indexed { 0x30 } else { 0x20 };
+                    encode_string(&name, 3, flags, self.huffman, &mut output);
+                    encode_string(&value, 7, 0, self.huffman, &mut output);
+                }
+            }
+        }
+        if let Some(min_reference) = min_reference {
+            self.outstanding.push(Outstanding {
+                stream_id,
+                required_insert_count,
+                min_reference,
+            });
+        }
+        output
+    }
+}
+
+/// QPACK decoder of one HTTP/3 connection
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    table: DynamicTable,
+    max_table_capacity: usize,
+    max_blocked_streams: usize,
+    blocked: Vec<(u64, usize, Vec<u8>)>,
+    acknowledged_insert_count: usize,
+    instructions: Vec<u8>,
+    received: Vec<u8>,
+}
+
+impl Decoder {
+    /// Return new decoder, `max_table_capacity` and `max_blocked_streams` are the
+    /// `SETTINGS_QPACK_MAX_TABLE_CAPACITY` and `SETTINGS_QPACK_BLOCKED_STREAMS` it announced
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::qpack::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(4096, 16);
+    /// ```
+    pub fn new(max_table_capacity: usize, max_blocked_streams: usize) -> Self {
+        Decoder {
+            max_table_capacity,
+            max_blocked_streams,
+            ..Default::default()
+        }
+    }
+
+    /// Return current dynamic table size in bytes
+    pub fn table_size(&self) -> usize {
+        self.table.size
+    }
+
+    /// Return dynamic table entries in absolute index order
+    pub fn dynamic_table(&self) -> MultiDict {
+        self.table.to_multidict()
+    }
+
+    /// Return pending decoder stream instructions, the buffer is emptied.
+    /// Inserts not acknowledged by a section acknowledgment are acknowledged here.
+    pub fn decoder_stream(&mut self) -> Vec<u8> {
+        let insert_count = self.table.insert_count();
+        if insert_count > self.acknowledged_insert_count {
+            encode_integer(
+                insert_count - self.acknowledged_insert_count,
+                6,
+                0,
+                &mut self.instructions,
+            );
+            self.acknowledged_insert_count = insert_count;
+        }
+        std::mem::take(&mut self.instructions)
+    }
+
+    /// Abandon field sections of a reset stream and signal it on the decoder stream
+    pub fn cancel_stream(&mut self, stream_id: u64) {
+        self.blocked
+            .retain(|(blocked_id, _, _)| *blocked_id != stream_id);
+        if self.max_table_capacity > 0 {
+            encode_integer(stream_id as usize, 6, 0x40, &mut self.instructions);
+        }
+    }
+
+    /// Decode field section of `stream_id`, `None` when it waits for encoder stream inserts
+    ///
+    /// # Examples
+    ///
+    /// RFC 9204 Appendix B.2
+    /// ```
+    /// use multidict::qpack::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(220, 1);
+    /// // section arrives before the inserts it references
+    /// assert!(decoder.decode(4, &[0x03, 0x81, 0x10, 0x11]).unwrap().is_none());
+    ///
+    /// let mut encoder_stream = vec![0x3f, 0xbd, 0x01, 0xc0, 0x0f];
+    /// encoder_stream.extend_from_slice(b"www.example.com");
+    /// encoder_stream.extend_from_slice(&[0xc1, 0x0c]);
+    /// encoder_stream.extend_from_slice(b"/sample/path");
+    /// let unblocked = decoder.receive_encoder_stream(&encoder_stream).unwrap();
+    /// println!("{}", unblocked[0].headers);
+    /// // MultiDict < ":authority":"www.example.com", ":path":"/sample/path" >
+    /// assert_eq!(unblocked[0].stream_id, 4);
+    /// assert_eq!(decoder.decoder_stream(), vec![0x84]);
+    /// ```
+    pub fn decode(
+        &mut self,
+        stream_id: u64,
+        section: &[u8],
+    ) -> Result<Option<MultiDict>, &'static str> {
+        Ok(self
+            .decode_flagged(stream_id, section)?
+            .map(|decoded| decoded.headers))
+    }
+
+    /// Decode field section keeping the never-indexed flag of every element
+    pub fn decode_flagged(
+        &mut self,
+        stream_id: u64,
+        section: &[u8],
+    ) -> Result<Option<FieldSection>, &'static str> {
+        let mut position = 0;
+        let required_insert_count = self.required_insert_count(section, &mut position)?;
+        if required_insert_count > self.table.insert_count() {
+            if self.blocked.len() >= self.max_blocked_streams {
+                return Err("Too many blocked streams");
+            }
+            self.blocked
+                .push((stream_id, required_insert_count, section.to_vec()));
+            return Ok(None);
+        }
+        self.decode_lines(stream_id, section, position, required_insert_count)
+            .map(Some)
+    }
+
+    /// Process encoder stream data and return field sections it unblocked
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::qpack::Decoder;
+    ///
+    /// let mut decoder = Decoder::new(100, 0);
+    /// assert_eq!(decoder.receive_encoder_stream(&[0x3f, 0xe1, 0x1f]).unwrap_err(),
+    ///            "Dynamic table capacity exceeds the limit");
+    /// ```
+    pub fn receive_encoder_stream(
+        &mut self,
+        data: &[u8],
+    ) -> Result<Vec<FieldSection>, &'static str> {
+        let mut buffer = std::mem::take(&mut self.received);
+        buffer.extend_from_slice(data);
+        let mut position = 0;
+        while position < buffer.len() {
+            let mut cursor = position;
+            match self.encoder_instruction(&buffer, &mut cursor) {
+                Ok(()) => position = cursor,
+                Err(error) if is_truncated(error) => break,
+                Err(error) => return Err(error),
+            }
+        }
+        buffer.drain(..position);
+        self.received = buffer;
+
+        let insert_count = self.table.insert_count();
+        let (ready, blocked): (Vec<_>, Vec<_>) = std::mem::take(&mut self.blocked)
+            .into_iter()
+            .partition(|(_, required_insert_count, _)| *required_insert_count <= insert_count);
+        self.blocked = blocked;
+        let mut results = Vec::with_capacity(ready.len());
+        for (stream_id, _, section) in ready {
+            let mut position = 0;
+            let required_insert_count = self.required_insert_count(&section, &mut position)?;
+            results.push(self.decode_lines(
+                stream_id,
+                &section,
+                position,
+                required_insert_count,
+            )?);
+        }
+        Ok(results)
+    }
+
+    fn encoder_instruction(
+        &mut self,
+        input: &[u8],
+        position: &mut usize,
+    ) -> Result<(), &'static str> {
+        let byte = input[*position];
+        if byte & 0x80 != 0 {
+            let index = decode_integer(input, position, 6)?;
+            let value = decode_string(input, position, 7)?;
+            let name = if byte & 0x40 != 0 {
+                static_entry(index)?.0.to_string()
+            } else {
+                self.relative_entry(index)?.0.to_string()
+            };
+            self.table.insert(name, value)
+        } else if byte & 0x40 != 0 {
+            let name = decode_string(input, position, 5)?;
+            let value = decode_string(input, position, 7)?;
+            self.table.insert(name, value)
+        } else if byte & 0x20 != 0 {
+            let capacity = decode_integer(input, position, 5)?;
+            if capacity > self.max_table_capacity {
+                return Err("Dynamic table capacity exceeds the limit");
+            }
+            self.table.set_capacity(capacity);
+            Ok(())
+        } else {
+            let index = decode_integer(input, position, 5)?;
+            let (name, value) = self.relative_entry(index)?;
+            let (name, value) = (name.to_string(), value.to_string());
+            self.table.insert(name, value)
+        }
+    }
+
+    /// Return entry of encoder stream relative `index`
+    fn relative_entry(&self, index: usize) -> Result<(&str, &str), &'static str> {
+        let absolute = self
+            .table
+            .insert_count()
+            .checked_sub(index + 1)
+            .ok_or("Dynamic table index out of range")?;
+        self.table.get(absolute)
+    }
+
+    /// Decode Required Insert Count of field section prefix (RFC 9204 section 4.5.1.1)
+    fn required_insert_count(
+        &self,
+        section: &[u8],
+        position: &mut usize,
+    ) -> Result<usize, &'static str> {
+        let encoded = decode_integer(section, position, 8)?;
+        if encoded == 0 {
+            return Ok(0);
+        }
+        let max_entries = self.max_table_capacity / 32;
+        let full_range = 2 * max_entries;
+        if encoded > full_range {
+            return Err("Invalid required insert count");
+        }
+        let max_value = self.table.insert_count() + max_entries;
+        let max_wrapped = max_value / full_range * full_range;
+        let mut required_insert_count = max_wrapped + encoded - 1;
+        if required_insert_count > max_value {
+            if required_insert_count <= full_range {
+                return Err("Invalid required insert count");
+            }
+            required_insert_count -= full_range;
+        }
+        if required_insert_count == 0 {
+            return Err("Invalid required insert count");
+        }
+        Ok(required_insert_count)
+    }
+
+    fn decode_lines(
+        &mut self,
+        stream_id: u64,
+        section: &[u8],
+        mut position: usize,
+        required_insert_count: usize,
+    ) -> Result<FieldSection, &'static str> {
+        let Some(delta_byte) = section.get(position).copied() else {
+            return Err("Truncated field section prefix");
+        };
+        let delta_base = decode_integer(section, &mut position, 7)?;
+        let base = if delta_byte & 0x80 == 0 {
+            required_insert_count.checked_add(delta_base)
+        } else {
+            required_insert_count.checked_sub(delta_base + 1)
+        }
+        .ok_or("Invalid base")?;
+        let dynamic = |absolute: Option<usize>| -> Result<(&str, &str), &'static str> {
+            match absolute {
+                Some(absolute) if absolute < required_insert_count => self.table.get(absolute),
+                _ => Err("Dynamic table reference out of range"),
+            }
+        };
+
+        let mut decoded = FieldSection {
+            stream_id,
+            ..Default::default()
+        };
+        while let Some(byte) = section.get(position).copied() {
+            let (name, value, never_indexed) = if byte & 0x80 != 0 {
+                let index = decode_integer(section, &mut position, 6)?;
+                let (name, value) = if byte & 0x40 != 0 {
+                    static_entry(index)?
+                } else {
+                    dynamic(base.checked_sub(index + 1))?
+                };
+                (name.to_string(), value.to_string(), false)
+            } else if byte & 0x40 != 0 {
+                let index = decode_integer(section, &mut position, 4)?;
+                let name = if byte & 0x10 != 0 {
+                    static_entry(index)?.0
+                } else {
+                    dynamic(base.checked_sub(index + 1))?.0
+                };
+                let name = name.to_string();
+                let value = decode_string(section, &mut position, 7)?;
+                (name, value, byte & 0x20 != 0)
+            } else if byte & 0x20 != 0 {
+                let name = decode_string(section, &mut position, 3)?;
+                let value = decode_string(section, &mut position, 7)?;
+                (name, value, byte & 0x10 != 0)
+            } else if byte & 0x10 != 0 {
+                let index = decode_integer(section, &mut position, 4)?;
+                let (name, value) = dynamic(base.checked_add(index))?;
+                (name.to_string(), value.to_string(), false)
+            } else {
+                let index = decode_integer(section, &mut position, 3)?;
+                let name = dynamic(base.checked_add(index))?.0.to_string();
+                let value = decode_string(section, &mut position, 7)?;
+                (name, value, byte & 0x08 != 0)
+            };
+            decoded.headers.add(MultiElement {
+                key: name,
+                value,
+                meta: None,
+            });
+            decoded.never_indexed.push(never_indexed);
+        }
+        if required_insert_count > 0 {
+            encode_integer(stream_id as usize, 7, 0x80, &mut self.instructions);
+            self.acknowledged_insert_count =
+                self.acknowledged_insert_count.max(required_insert_count);
+        }
+        Ok(decoded)
+    }
+}
@@ -0,0 +1,394 @@
+//! URL query string support for `MultiDict`.
+//!
+//! Plain query strings map directly onto `MultiDict`: every `key=value` pair becomes one
+//! element and repeated keys are kept in order. Nested conventions
+//! (`a[]=1`, `filter[name]=x`, `user.address.city=y`) are read into a [`NestedValue`] tree
+//! according to a [`NestedStyle`].
+use crate::metadata::{ElementMeta, Span};
+use crate::{percent, MultiDict, MultiElement};
+
+/// Convention used to encode nesting inside query string keys.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum NestedStyle {
+    /// PHP / Rails style: `a[]=1&a[]=2`, `filter[name]=x`, `a[b][]=1`
+    Brackets,
+    /// OpenAPI `deepObject` style: `filter[name]=x`, arrays are repeated keys
+    DeepObject,
+    /// Dotted paths: `user.address.city=y`, arrays are repeated keys
+    Dotted,
+}
+
+/// Tree of nested query values.
+///
+/// Every leaf holds all values of its path in order of appearance,
+/// every node holds its children in order of first appearance.
+/// `List` is a leaf read from array brackets (`a[]=1`), it keeps its brackets when
+/// written in [`NestedStyle::Brackets`], even with a single value. An empty list
+/// has no query string form and is not written.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NestedValue {
+    Leaf(Vec<String>),
+    List(Vec<String>),
+    Node(Vec<(String, NestedValue)>),
+}
+
+/// One segment of a parsed nested key.
+enum Segment {
+    Name(String),
+    Append,
+}
+
+impl NestedValue {
+    /// Return the child for key if `self` is a node and has such key
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::query::{NestedStyle, NestedValue};
+    ///
+    /// let tree = NestedValue::from_query_string("filter[name]=x", NestedStyle::DeepObject).unwrap();
+    /// assert!(tree.get("filter").unwrap().get("name").is_some());
+    /// assert!(tree.get("other").is_none());
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&NestedValue> {
+        match self {
+            NestedValue::Node(children) => children
+                .iter()
+                .find(|(name, _)| name.eq(key))
+                .map(|(_, child)| child),
+            NestedValue::Leaf(_) | NestedValue::List(_) => None,
+        }
+    }
+
+    /// Return leaf values if `self` is a leaf or a list
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::query::{NestedStyle, NestedValue};
+    ///
+    /// let tree = NestedValue::from_query_string("a[]=1&a[]=2", NestedStyle::Brackets).unwrap();
+    /// assert_eq!(tree.get("a").unwrap().values().unwrap(), &vec!["1".to_string(), "2".to_string()]);
+    /// ```
+    pub fn values(&self) -> Option<&Vec<String>> {
+        match self {
+            NestedValue::Leaf(values) | NestedValue::List(values) => Some(values),
+            NestedValue::Node(_) => None,
+        }
+    }
+
+    /// Parse query string into nested tree using nesting `style`
+    ///
+    /// Keys are percent-decoded before nesting is resolved, so both
+    /// `filter[name]` and `filter%5Bname%5D` are understood.
+    /// A path used both as a leaf and as a node is an error.
+    ///
+    /// # Examples
+    ///
+    /// PHP / Rails brackets
+    /// ```
+    /// use multidict::query::{NestedStyle, NestedValue};
+    ///
+    /// let tree = NestedValue::from_query_string("a[]=1&a[]=2&filter[name]=x",
+    ///                                           NestedStyle::Brackets).unwrap();
+    /// assert_eq!(tree, NestedValue::Node(vec![
+    ///     ("a".to_string(), NestedValue::List(vec!["1".to_string(), "2".to_string()])),
+    ///     ("filter".to_string(), NestedValue::Node(vec![
+    ///         ("name".to_string(), NestedValue::Leaf(vec!["x".to_string()])),
+    ///     ])),
+    /// ]));
+    /// ```
+    ///
+    /// Dotted paths
+    /// ```
+    /// use multidict::query::{NestedStyle, NestedValue};
+    ///
+    /// let tree = NestedValue::from_query_string("user.address.city=y&user.tag=a&user.tag=b",
+    ///                                           NestedStyle::Dotted).unwrap();
+    /// let user = tree.get("user").unwrap();
+    /// assert_eq!(user.get("address").unwrap().get("city").unwrap().values().unwrap(), &vec!["y"]);
+    /// assert_eq!(user.get("tag").unwrap().values().unwrap(), &vec!["a", "b"]);
+    /// ```
+    ///
+    /// Conflicting paths
+    /// ```
+    /// use multidict::query::{NestedStyle, NestedValue};
+    ///
+    /// let result = NestedValue::from_query_string("a=1&a[b]=2", NestedStyle::Brackets);
+    /// assert_eq!(result, Err("Conflicting nested key"));
+    /// ```
+    pub fn from_query_string(query: &str, style: NestedStyle) -> Result<NestedValue, &'static str> {
+        MultiDict::from_query_string(query)?.to_nested(style)
+    }
+
+    /// Return flat `MultiDict` with keys rendered in nesting `style`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::query::{NestedStyle, NestedValue};
+    ///
+    /// let tree = NestedValue::Node(vec![
+    ///     ("user".to_string(), NestedValue::Node(vec![
+    ///         ("city".to_string(), NestedValue::Leaf(vec!["y".to_string()])),
+    ///     ])),
+    /// ]);
+    /// assert_eq!(tree.to_multidict(NestedStyle::Dotted).keys(), vec!["user.city"]);
+    /// assert_eq!(tree.to_multidict(NestedStyle::DeepObject).keys(), vec!["user[city]"]);
+    /// ```
+    pub fn to_multidict(&self, style: NestedStyle) -> MultiDict {
+        let mut results = MultiDict::new();
+        self.flatten("", style, &mut results);
+        results
+    }
+
+    /// Return query string with keys rendered in nesting `style`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::query::{NestedStyle, NestedValue};
+    ///
+    /// let query = "a%5B%5D=1&a%5B%5D=2&filter%5Bname%5D=x";
+    /// let tree = NestedValue::from_query_string(query, NestedStyle::Brackets).unwrap();
+    /// assert_eq!(tree.to_query_string(NestedStyle::Brackets), query);
+    /// assert_eq!(tree.to_query_string(NestedStyle::Dotted), "a=1&a=2&filter.name=x");
+    /// ```
+    pub fn to_query_string(&self, style: NestedStyle) -> String {
+        self.to_multidict(style).to_query_string()
+    }
+
+    fn flatten(&self, path: &str, style: NestedStyle, results: &mut MultiDict) {
+        match self {
+            NestedValue::Leaf(values) | NestedValue::List(values) => {
+                let key = match self {
+                    NestedValue::List(_) if style == NestedStyle::Brackets => format!("{path}[]"),
+                    _ => path.to_string(),
+                };
+                for value in values {
+                    results.add(MultiElement {
+                        key: key.clone(),
+                        value: value.clone(),
+                        meta: None,
+                    });
+                }
+            }
+            NestedValue::Node(children) => {
+                for (name, child) in children {
+                    let child_path = if path.is_empty() {
+                        name.clone()
+                    } else if style == NestedStyle::Dotted {
+                        format!("{path}.{name}")
+                    } else {
+                        format!("{path}[{name}]")
+                    };
+                    child.flatten(&child_path, style, results);
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, segments: &[Segment], value: String) -> Result<(), &'static str> {
+        match (self, segments) {
+            (NestedValue::Leaf(values), []) => {
+                values.push(value);
+                Ok(())
+            }
+            (NestedValue::Node(children), [Segment::Name(name), rest @ ..]) => {
+                let idx = match children.iter().position(|(child, _)| child.eq(name)) {
+                    Some(idx) => idx,
+                    None => {
+                        let child = match rest {
+                            [] => NestedValue::Leaf(Vec::new()),
+                            [Segment::Append] => NestedValue::List(Vec::new()),
+                            _ => NestedValue::Node(Vec::new()),
+                        };
+                        children.push((name.clone(), child));
+                        children.len() - 1
+                    }
+                };
+                children[idx].1.insert(rest, value)
+            }
+            (NestedValue::List(values), [Segment::Append]) => {
+                values.push(value);
+                Ok(())
+            }
+            _ => Err("Conflicting nested key"),
+        }
+    }
+}
+
+/// Split nested key into path segments according to `style`
+fn split_key(key: &str, style: NestedStyle) -> Result<Vec<Segment>, &'static str> {
+    if style == NestedStyle::Dotted {
+        if key.split('.').any(|segment| segment.is_empty()) {
+            return Err("Empty key segment");
+        }
+        return Ok(key.split('.').map(|s| Segment::Name(s.to_string())).collect());
+    }
+
+    let Some(open) = key.find('[') else {
+        return Ok(vec![Segment::Name(key.to_string())]);
+    };
+    let mut segments = vec![Segment::Name(key[..open].to_string())];
+    let mut rest = &key[open..];
+    while !rest.is_empty() {
+        // Anything which is not a sequence of `[...]` groups is a literal key
+        let close = match (rest.strip_prefix('['), rest.find(']')) {
+            (Some(_), Some(close)) if !rest[1..close].contains('[') => close,
+            _ => return Ok(vec![Segment::Name(key.to_string())]),
+        };
+        let name = &rest[1..close];
+        if name.is_empty() {
+            if style == NestedStyle::DeepObject {
+                return Err("Array brackets are not allowed in deepObject keys");
+            }
+            segments.push(Segment::Append);
+        } else {
+            segments.push(Segment::Name(name.to_string()));
+        }
+        rest = &rest[close + 1..];
+    }
+    if segments[..segments.len() - 1]
+        .iter()
+        .any(|segment| matches!(segment, Segment::Append))
+    {
+        return Err("Array brackets must be the last key segment");
+    }
+    Ok(segments)
+}
+
+impl MultiDict {
+    /// Parse `application/x-www-form-urlencoded` query string into MultiDict.
+    /// Leading `?` is ignored, `+` is decoded as space.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let map = MultiDict::from_query_string("?a=1&b=x+y&a=%C3%A9&flag").unwrap();
+    /// println!("{map}");
+    /// // MultiDict < "a":"1", "b":"x y", "a":"é", "flag":"" >
+    /// assert_eq!(map.values(), vec!["1", "x y", "é", ""]);
+    /// ```
+    ///
+    /// Malformed escapes are rejected
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// assert_eq!(MultiDict::from_query_string("a=%G1").unwrap_err(), "Invalid percent-encoding");
+    /// ```
+    pub fn from_query_string(query: &str) -> Result<MultiDict, &'static str> {
+        parse_query(query)
+    }
+
+    /// Return `application/x-www-form-urlencoded` query string of all elements in order
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    ///
+    /// let mut map = MultiDict::new();
+    /// map.add(MultiElement {
+    ///             key: "q".to_string(),
+    ///             value: "rust lang".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.add(MultiElement {
+    ///             key: "q".to_string(),
+    ///             value: "a&b".to_string(),
+    ///             meta: None,
+    ///         });
+    /// assert_eq!(map.to_query_string(), "q=rust+lang&q=a%26b");
+    /// ```
+    pub fn to_query_string(&self) -> String {
+        self.elements
+            .iter()
+            .map(|item| {
+                format!(
+                    "{}={}",
+                    percent::encode(&item.key, percent::is_unreserved, true),
+                    percent::encode(&item.value, percent::is_unreserved, true)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Read keys as nested paths in `style` and return tree of values
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::query::{NestedStyle, NestedValue};
+    ///
+    /// let mut map = MultiDict::new();
+    /// map.add(MultiElement {
+    ///             key: "filter[name]".to_string(),
+    ///             value: "x".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.add(MultiElement {
+    ///             key: "filter[name]".to_string(),
+    ///             value: "y".to_string(),
+    ///             meta: None,
+    ///         });
+    /// let tree = map.to_nested(NestedStyle::DeepObject).unwrap();
+    /// assert_eq!(tree.get("filter").unwrap().get("name").unwrap().values().unwrap(), &vec!["x", "y"]);
+    /// assert_eq!(map.to_nested(NestedStyle::Dotted).unwrap().get("filter[name]").is_some(), true);
+    /// ```
+    pub fn to_nested(&self, style: NestedStyle) -> Result<NestedValue, &'static str> {
+        let mut root = NestedValue::Node(Vec::new());
+        for item in &self.elements {
+            root.insert(&split_key(&item.key, style)?, item.value.clone())?;
+        }
+        Ok(root)
+    }
+}
+
+impl<M: ElementMeta> MultiDict<M> {
+    /// Parse query string like [`MultiDict::from_query_string`], every element gets
+    /// metadata for the byte range of its `key=value` pair in `query`
+    ///
+    /// Errors are the same as of [`MultiDict::from_query_string`] and hold no offset.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::metadata::Span;
+    ///
+    /// let query = "?page=1&limit=ten";
+    /// let map: MultiDict<Span> = MultiDict::from_query_string_spanned(query).unwrap();
+    /// for item in map.iter() {
+    ///     let span = item.meta.unwrap();
+    ///     println!("{} at {}..{}: {}", item.key, span.start, span.end, span.slice(query));
+    /// }
+    /// // page at 1..7: page=1
+    /// // limit at 8..17: limit=ten
+    /// let invalid = map.iter().find(|item| item.value.parse::<u32>().is_err()).unwrap();
+    /// assert_eq!(invalid.meta, Some(Span { start: 8, end: 17 }));
+    /// ```
+    pub fn from_query_string_spanned(query: &str) -> Result<MultiDict<M>, &'static str> {
+        parse_query(query)
+    }
+}
+
+/// Split query string into pairs, metadata is built from the span of each pair
+fn parse_query<M: ElementMeta>(query: &str) -> Result<MultiDict<M>, &'static str> {
+    let offset = usize::from(query.starts_with('?'));
+    let mut results = MultiDict::default();
+    let mut start = offset;
+    for pair in query[offset..].split('&') {
+        let span = Span {
+            start,
+            end: start + pair.len(),
+        };
+        start = span.end + 1;
+        if pair.is_empty() {
+            continue;
+        }
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        results.add(MultiElement {
+            key: percent::decode(key, true)?,
+            value: percent::decode(value, true)?,
+            meta: M::from_span(span),
+        });
+    }
+    Ok(results)
+}
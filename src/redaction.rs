@@ -0,0 +1,239 @@
+//! Redaction of sensitive values in `Display`, `Debug` and serializer output.
+//!
+//! A [`RedactionPolicy`] set on a `MultiDict` replaces values of matching keys
+//! with [`REDACTED`] when the map is printed, and in HAR, command line and diff output.
+//! Wire formats (query strings, HPACK, QPACK, signatures) always carry real values.
+use std::fmt;
+use std::sync::Arc;
+
+use crate::metadata::ElementMeta;
+use crate::{MultiDict, MultiElement};
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "***";
+
+/// HTTP headers redacted by [`RedactionPolicy::sensitive_headers`], lowercase
+pub const SENSITIVE_HEADERS: [&str; 12] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "api-key",
+    "x-auth-token",
+    "x-access-token",
+    "x-csrf-token",
+    "x-xsrf-token",
+    "x-amz-security-token",
+    "x-goog-api-key",
+];
+
+/// Rule selecting keys whose values are redacted
+#[derive(Clone, Default)]
+pub enum RedactionPolicy {
+    /// Print every value
+    #[default]
+    Disabled,
+    /// Redact keys equal to one of the list
+    Exact(Vec<String>),
+    /// Redact keys equal to one of the list ignoring ASCII case
+    CaseInsensitive(Vec<String>),
+    /// Redact keys for which the closure returns true
+    Predicate(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+impl fmt::Debug for RedactionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedactionPolicy::Disabled => write!(f, "Disabled"),
+            RedactionPolicy::Exact(keys) => f.debug_tuple("Exact").field(keys).finish(),
+            RedactionPolicy::CaseInsensitive(keys) => {
+                f.debug_tuple("CaseInsensitive").field(keys).finish()
+            }
+            RedactionPolicy::Predicate(_) => write!(f, "Predicate(..)"),
+        }
+    }
+}
+
+impl RedactionPolicy {
+    /// Return case-insensitive policy of the built-in [`SENSITIVE_HEADERS`]
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::redaction::RedactionPolicy;
+    ///
+    /// let policy = RedactionPolicy::sensitive_headers();
+    /// assert!(policy.is_sensitive("Authorization"));
+    /// assert!(!policy.is_sensitive("Accept"));
+    /// ```
+    pub fn sensitive_headers() -> Self {
+        RedactionPolicy::CaseInsensitive(
+            SENSITIVE_HEADERS.iter().map(|key| key.to_string()).collect(),
+        )
+    }
+
+    /// Return policy redacting keys for which `predicate` returns true
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::redaction::RedactionPolicy;
+    ///
+    /// let policy = RedactionPolicy::predicate(|key| key.ends_with("_secret"));
+    /// assert!(policy.is_sensitive("client_secret"));
+    /// ```
+    pub fn predicate(predicate: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        RedactionPolicy::Predicate(Arc::new(predicate))
+    }
+
+    /// Return true if values of `key` are redacted
+    pub fn is_sensitive(&self, key: &str) -> bool {
+        match self {
+            RedactionPolicy::Disabled => false,
+            RedactionPolicy::Exact(keys) => keys.iter().any(|item| item.eq(key)),
+            RedactionPolicy::CaseInsensitive(keys) => {
+                keys.iter().any(|item| item.eq_ignore_ascii_case(key))
+            }
+            RedactionPolicy::Predicate(predicate) => predicate(key),
+        }
+    }
+}
+
+impl<M, V> MultiDict<M, V> {
+    /// Set redaction policy, it is kept by clones and `getall` results
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::redaction::RedactionPolicy;
+    ///
+    /// let mut map = MultiDict::new();
+    /// map.add(MultiElement {
+    ///             key: "Accept".to_string(),
+    ///             value: "*/*".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.add(MultiElement {
+    ///             key: "Authorization".to_string(),
+    ///             value: "Bearer token".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.set_redaction(RedactionPolicy::sensitive_headers());
+    /// println!("{map}");
+    /// // MultiDict < "Accept":"*/*", "Authorization":"***" >
+    /// assert_eq!(map.to_string(), r#"MultiDict < "Accept":"*/*", "Authorization":"***" >"#);
+    /// assert_eq!(format!("{map:?}"),
+    ///            "MultiDict { elements: [MultiElement { key: \"Accept\", value: \"*/*\" }, \
+    ///             MultiElement { key: \"Authorization\", value: \"***\" }] }");
+    /// // values stay readable through the API
+    /// assert_eq!(map.get("Authorization").unwrap().value, "Bearer token");
+    /// ```
+    pub fn set_redaction(&mut self, policy: RedactionPolicy) {
+        self.redaction = policy;
+    }
+
+    /// Return redaction policy
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::command_line::HttpCommand;
+    /// use multidict::redaction::RedactionPolicy;
+    ///
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: "X-Session".to_string(),
+    ///             value: "s1".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "X-Session".to_string(),
+    ///             value: "s2".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.set_redaction(RedactionPolicy::predicate(|key| key.starts_with("X-Session")));
+    /// let sessions = headers.getall("X-Session").unwrap();
+    /// assert!(sessions.redaction().is_sensitive("X-Session"));
+    /// println!("{sessions}");
+    /// // MultiDict < "X-Session":"***", "X-Session":"***" >
+    ///
+    /// let command = HttpCommand::new("GET", "https://example.com/", headers);
+    /// assert_eq!(command.to_curl(),
+    ///            "curl https://example.com/ -H 'X-Session: ***' -H 'X-Session: ***'");
+    /// ```
+    pub fn redaction(&self) -> &RedactionPolicy {
+        &self.redaction
+    }
+}
+
+/// Value of an element as shown by `Debug` under the redaction policy
+pub(crate) enum Shown<'a, V> {
+    Redacted,
+    Value(&'a V),
+}
+
+impl<V: fmt::Debug> fmt::Debug for Shown<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shown::Redacted => fmt::Debug::fmt(REDACTED, f),
+            Shown::Value(value) => value.fmt(f),
+        }
+    }
+}
+
+impl<M: ElementMeta, V> MultiDict<M, V> {
+    /// Return true if the value of `item` is hidden when printed,
+    /// elements with sensitive metadata are redacted as well
+    pub(crate) fn is_redacted(&self, item: &MultiElement<M, V>) -> bool {
+        self.redaction.is_sensitive(&item.key)
+            || item.meta.as_ref().is_some_and(ElementMeta::is_sensitive)
+    }
+}
+
+impl<M: ElementMeta> MultiDict<M> {
+    /// Return value of `item` as printed under the redaction policy
+    pub(crate) fn shown_value<'a>(&self, item: &'a MultiElement<M>) -> &'a str {
+        if self.is_redacted(item) {
+            REDACTED
+        } else {
+            &item.value
+        }
+    }
+
+    /// Return copy with sensitive values replaced by [`REDACTED`], for serializers
+    /// that do not know about the policy
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::redaction::RedactionPolicy;
+    ///
+    /// let mut map = MultiDict::new();
+    /// map.add(MultiElement {
+    ///             key: "api_key".to_string(),
+    ///             value: "abc".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.add(MultiElement {
+    ///             key: "q".to_string(),
+    ///             value: "rust".to_string(),
+    ///             meta: None,
+    ///         });
+    /// map.set_redaction(RedactionPolicy::Exact(vec!["api_key".to_string()]));
+    /// assert_eq!(map.redacted().to_query_string(), "api_key=%2A%2A%2A&q=rust");
+    /// ```
+    pub fn redacted(&self) -> MultiDict<M>
+    where
+        M: Clone,
+    {
+        let mut results = MultiDict::default();
+        for item in self.iter() {
+            results.add(MultiElement {
+                key: item.key.clone(),
+                value: self.shown_value(item).to_string(),
+                meta: item.meta.clone(),
+            });
+        }
+        results.redaction = self.redaction.clone();
+        results
+    }
+}
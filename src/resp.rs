@@ -0,0 +1,310 @@
+//! Redis stream entries and RESP field-value arrays.
+//!
+//! Stream entries, as written by `XADD` and returned by `XRANGE`, are flat arrays of
+//! alternating fields and values in which fields may repeat, so they map onto
+//! `MultiDict` in order. [`RespValue`] covers the RESP2 types and the RESP3 null and map
+//! types, enough for commands, stream replies and `HELLO 3` map replies.
+use crate::{MultiDict, MultiElement};
+
+/// Deepest nesting of arrays and maps accepted by [`RespValue::decode`]
+pub const MAX_DEPTH: usize = 128;
+
+/// One RESP value
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RespValue {
+    /// `+OK`
+    SimpleString(String),
+    /// `-ERR message`
+    Error(String),
+    /// `:42`
+    Integer(i64),
+    /// `$3\r\nfoo`, `None` is the RESP2 null bulk string `$-1`
+    BulkString(Option<Vec<u8>>),
+    /// `*2\r\n...`, `None` is the RESP2 null array `*-1`
+    Array(Option<Vec<RespValue>>),
+    /// RESP3 `_`
+    Null,
+    /// RESP3 `%1\r\n...` with pairs in order
+    Map(Vec<(RespValue, RespValue)>),
+}
+
+/// One stream entry of an `XRANGE`, `XREVRANGE` or `XREAD` reply
+#[derive(Debug, Clone, Default)]
+pub struct StreamEntry {
+    pub id: String,
+    pub fields: MultiDict,
+}
+
+/// Return line at `position` without CRLF and move past it
+fn read_line<'a>(input: &'a [u8], position: &mut usize) -> Result<&'a str, &'static str> {
+    let rest = &input[*position..];
+    let end = rest
+        .windows(2)
+        .position(|pair| pair == b"\r\n")
+        .ok_or("Truncated RESP value")?;
+    *position += end + 2;
+    std::str::from_utf8(&rest[..end]).map_err(|_| "RESP line is not valid UTF-8")
+}
+
+/// Parse length of a bulk string, array or map, `-1` gives `None`
+fn read_length(line: &str) -> Result<Option<usize>, &'static str> {
+    match line {
+        "-1" => Ok(None),
+        _ => line.parse().map(Some).map_err(|_| "Invalid RESP length"),
+    }
+}
+
+impl RespValue {
+    /// Return bulk string of `value`
+    pub fn bulk(value: &str) -> RespValue {
+        RespValue::BulkString(Some(value.as_bytes().to_vec()))
+    }
+
+    /// Return text of a simple or bulk string
+    pub fn as_str(&self) -> Result<&str, &'static str> {
+        match self {
+            RespValue::SimpleString(value) => Ok(value),
+            RespValue::BulkString(Some(value)) => {
+                std::str::from_utf8(value).map_err(|_| "RESP string is not valid UTF-8")
+            }
+            _ => Err("RESP value is not a string"),
+        }
+    }
+
+    /// Append encoding of the value
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            RespValue::SimpleString(value) => {
+                out.extend_from_slice(format!("+{value}\r\n").as_bytes())
+            }
+            RespValue::Error(value) => out.extend_from_slice(format!("-{value}\r\n").as_bytes()),
+            RespValue::Integer(value) => out.extend_from_slice(format!(":{value}\r\n").as_bytes()),
+            RespValue::BulkString(None) => out.extend_from_slice(b"$-1\r\n"),
+            RespValue::BulkString(Some(value)) => {
+                out.extend_from_slice(format!("${}\r\n", value.len()).as_bytes());
+                out.extend_from_slice(value);
+                out.extend_from_slice(b"\r\n");
+            }
+            RespValue::Array(None) => out.extend_from_slice(b"*-1\r\n"),
+            RespValue::Array(Some(values)) => {
+                out.extend_from_slice(format!("*{}\r\n", values.len()).as_bytes());
+                for value in values {
+                    value.encode(out);
+                }
+            }
+            RespValue::Null => out.extend_from_slice(b"_\r\n"),
+            RespValue::Map(pairs) => {
+                out.extend_from_slice(format!("%{}\r\n", pairs.len()).as_bytes());
+                for (key, value) in pairs {
+                    key.encode(out);
+                    value.encode(out);
+                }
+            }
+        }
+    }
+
+    /// Return encoding of the value
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::resp::RespValue;
+    ///
+    /// let command = RespValue::Array(Some(vec![RespValue::bulk("PING")]));
+    /// assert_eq!(command.to_bytes(), b"*1\r\n$4\r\nPING\r\n");
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+
+    /// Decode the value at the start of `input`, return it and the number of bytes read.
+    /// Arrays and maps nested deeper than [`MAX_DEPTH`] are rejected.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::resp::RespValue;
+    ///
+    /// let (value, length) = RespValue::decode(b"%1\r\n+proto\r\n:3\r\n").unwrap();
+    /// assert_eq!(value, RespValue::Map(vec![(RespValue::SimpleString("proto".to_string()),
+    ///                                        RespValue::Integer(3))]));
+    /// assert_eq!(length, 16);
+    /// ```
+    pub fn decode(input: &[u8]) -> Result<(RespValue, usize), &'static str> {
+        let mut position = 0;
+        let value = RespValue::decode_at(input, &mut position, 0)?;
+        Ok((value, position))
+    }
+
+    fn decode_at(
+        input: &[u8],
+        position: &mut usize,
+        depth: usize,
+    ) -> Result<RespValue, &'static str> {
+        let kind = *input.get(*position).ok_or("Truncated RESP value")?;
+        *position += 1;
+        let line = read_line(input, position)?;
+        match kind {
+            b'+' => Ok(RespValue::SimpleString(line.to_string())),
+            b'-' => Ok(RespValue::Error(line.to_string())),
+            b':' => Ok(RespValue::Integer(
+                line.parse().map_err(|_| "Invalid RESP integer")?,
+            )),
+            b'_' if line.is_empty() => Ok(RespValue::Null),
+            b'_' => Err("Invalid RESP null"),
+            b'$' => {
+                let Some(length) = read_length(line)? else {
+                    return Ok(RespValue::BulkString(None));
+                };
+                let end = position.checked_add(length).ok_or("Invalid RESP length")?;
+                let next = end.checked_add(2).ok_or("Invalid RESP length")?;
+                let data = input.get(*position..end).ok_or("Truncated RESP value")?;
+                if input.get(end..next) != Some(b"\r\n") {
+                    return Err("RESP bulk string is not followed by CRLF");
+                }
+                *position = next;
+                Ok(RespValue::BulkString(Some(data.to_vec())))
+            }
+            b'*' => {
+                let Some(length) = read_length(line)? else {
+                    return Ok(RespValue::Array(None));
+                };
+                if depth >= MAX_DEPTH {
+                    return Err("RESP nesting too deep");
+                }
+                let mut values = Vec::new();
+                for _ in 0..length {
+                    values.push(RespValue::decode_at(input, position, depth + 1)?);
+                }
+                Ok(RespValue::Array(Some(values)))
+            }
+            b'%' => {
+                let length = read_length(line)?.ok_or("Invalid RESP length")?;
+                if depth >= MAX_DEPTH {
+                    return Err("RESP nesting too deep");
+                }
+                let mut pairs = Vec::new();
+                for _ in 0..length {
+                    let key = RespValue::decode_at(input, position, depth + 1)?;
+                    pairs.push((key, RespValue::decode_at(input, position, depth + 1)?));
+                }
+                Ok(RespValue::Map(pairs))
+            }
+            _ => Err("Unsupported RESP type"),
+        }
+    }
+}
+
+impl MultiDict {
+    /// Return flat array of alternating field and value bulk strings
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let fields = MultiDict::from_query_string("sensor=1&temp=20").unwrap();
+    /// assert_eq!(fields.to_resp_array().to_bytes(),
+    ///            b"*4\r\n$6\r\nsensor\r\n$1\r\n1\r\n$4\r\ntemp\r\n$2\r\n20\r\n");
+    /// ```
+    pub fn to_resp_array(&self) -> RespValue {
+        let mut values = Vec::with_capacity(self.len() * 2);
+        for item in self.iter() {
+            values.push(RespValue::bulk(&item.key));
+            values.push(RespValue::bulk(&item.value));
+        }
+        RespValue::Array(Some(values))
+    }
+
+    /// Return `XADD` command appending the elements to `stream` as entry `id`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let fields = MultiDict::from_query_string("tag=a&tag=b").unwrap();
+    /// let command = fields.to_xadd("events", "*").unwrap();
+    /// assert!(command.to_bytes().starts_with(b"*7\r\n$4\r\nXADD\r\n$6\r\nevents\r\n$1\r\n*\r\n"));
+    /// ```
+    pub fn to_xadd(&self, stream: &str, id: &str) -> Result<RespValue, &'static str> {
+        if self.is_empty() {
+            return Err("Stream entry needs at least one field");
+        }
+        let mut values = vec![
+            RespValue::bulk("XADD"),
+            RespValue::bulk(stream),
+            RespValue::bulk(id),
+        ];
+        if let RespValue::Array(Some(fields)) = self.to_resp_array() {
+            values.extend(fields);
+        }
+        Ok(RespValue::Array(Some(values)))
+    }
+
+    /// Read a flat field-value array or a RESP3 map reply, keeping repeated fields in
+    /// order
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::resp::RespValue;
+    ///
+    /// let reply = b"%2\r\n+server\r\n+redis\r\n+mode\r\n$10\r\nstandalone\r\n";
+    /// let (reply, _) = RespValue::decode(reply).unwrap();
+    /// println!("{}", MultiDict::from_resp(&reply).unwrap());
+    /// // MultiDict < "server":"redis", "mode":"standalone" >
+    /// ```
+    pub fn from_resp(value: &RespValue) -> Result<MultiDict, &'static str> {
+        let pairs: Vec<(&RespValue, &RespValue)> = match value {
+            RespValue::Array(Some(values)) => {
+                if values.len() % 2 != 0 {
+                    return Err("RESP field-value array has odd length");
+                }
+                values.chunks(2).map(|pair| (&pair[0], &pair[1])).collect()
+            }
+            RespValue::Map(pairs) => pairs.iter().map(|(key, value)| (key, value)).collect(),
+            _ => return Err("RESP value is not an array or map"),
+        };
+        let mut results = MultiDict::new_capacity(&pairs.len());
+        for (key, value) in pairs {
+            results.add(MultiElement {
+                key: key.as_str()?.to_string(),
+                value: value.as_str()?.to_string(),
+                meta: None,
+            });
+        }
+        Ok(results)
+    }
+}
+
+impl StreamEntry {
+    /// Read entries of an `XRANGE` reply, an array of `[id, [field, value, ...]]`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::resp::{RespValue, StreamEntry};
+    ///
+    /// let reply = b"*1\r\n*2\r\n$3\r\n1-0\r\n*4\r\n$3\r\ntag\r\n$1\r\na\r\n$3\r\ntag\r\n$1\r\nb\r\n";
+    /// let (reply, _) = RespValue::decode(reply).unwrap();
+    /// let entries = StreamEntry::from_xrange(&reply).unwrap();
+    /// assert_eq!(entries[0].id, "1-0");
+    /// assert_eq!(entries[0].fields.to_query_string(), "tag=a&tag=b");
+    /// ```
+    pub fn from_xrange(reply: &RespValue) -> Result<Vec<StreamEntry>, &'static str> {
+        let entries = match reply {
+            RespValue::Array(Some(entries)) => entries.as_slice(),
+            RespValue::Array(None) | RespValue::Null => &[],
+            _ => return Err("RESP value is not an array"),
+        };
+        let mut results = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match entry {
+                RespValue::Array(Some(parts)) if parts.len() == 2 => results.push(StreamEntry {
+                    id: parts[0].as_str()?.to_string(),
+                    fields: MultiDict::from_resp(&parts[1])?,
+                }),
+                _ => return Err("Invalid stream entry"),
+            }
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,460 @@
+//! Canonical forms of `MultiDict` for request signing and signature helpers
+//! for AWS Signature Version 4 and OAuth 1.0a (RFC 5849).
+//!
+//! Canonical query strings sort keys, then values of equal keys, after strict
+//! RFC 3986 percent-encoding (everything but unreserved characters is encoded,
+//! with uppercase hex digits). Canonical headers lowercase names, trim values and
+//! collapse inner whitespace runs to a single space.
+use crate::{digest, percent, MultiDict};
+
+/// Signing scheme whose canonicalization rules are applied
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SigningProfile {
+    /// AWS Signature Version 4, `X-Amz-Signature` is excluded from query
+    SigV4,
+    /// OAuth 1.0a, `oauth_signature` is excluded from parameters
+    OAuth1,
+}
+
+impl SigningProfile {
+    fn excluded_key(&self) -> &'static str {
+        match self {
+            SigningProfile::SigV4 => "X-Amz-Signature",
+            SigningProfile::OAuth1 => "oauth_signature",
+        }
+    }
+}
+
+/// Strict RFC 3986 percent-encoding used by both signing schemes
+fn encode(value: &str) -> String {
+    percent::encode(value, percent::is_unreserved, false)
+}
+
+/// Trim header value and collapse whitespace runs into a single space
+fn normalize_header_value(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl MultiDict {
+    /// Return canonical query string for signing `profile`.
+    ///
+    /// Pairs are encoded, then sorted by key and by value for equal keys.
+    /// Signature parameter of the profile is excluded.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::signing::SigningProfile;
+    ///
+    /// let map = MultiDict::from_query_string("b=2&a=z&a=y%20x&c=%2A&X-Amz-Signature=abc").unwrap();
+    /// assert_eq!(map.canonical_query_string(SigningProfile::SigV4), "a=y%20x&a=z&b=2&c=%2A");
+    /// ```
+    ///
+    /// RFC 5849 section 3.4.1.3.2 parameters normalization
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::signing::SigningProfile;
+    ///
+    /// let map = MultiDict::from_query_string(
+    ///     "b5=%3D%253D&a3=a&c%40=&a2=r%20b&oauth_consumer_key=9djdj82h48djs9d2\
+    ///      &oauth_token=kkk9d7dh3k39sjv7&oauth_signature_method=HMAC-SHA1\
+    ///      &oauth_timestamp=137131201&oauth_nonce=7d8f3e4a&c2&a3=2+q\
+    ///      &oauth_signature=bYT5CMsGcbgUdFHObYMEfcx6bsw%3D",
+    /// ).unwrap();
+    /// assert_eq!(
+    ///     map.canonical_query_string(SigningProfile::OAuth1),
+    ///     "a2=r%20b&a3=2%20q&a3=a&b5=%3D%253D&c%40=&c2=&oauth_consumer_key=9djdj82h48djs9d2\
+    ///      &oauth_nonce=7d8f3e4a&oauth_signature_method=HMAC-SHA1&oauth_timestamp=137131201\
+    ///      &oauth_token=kkk9d7dh3k39sjv7"
+    /// );
+    /// ```
+    pub fn canonical_query_string(&self, profile: SigningProfile) -> String {
+        let mut pairs: Vec<(String, String)> = self
+            .elements
+            .iter()
+            .filter(|item| item.key.ne(profile.excluded_key()))
+            .map(|item| (encode(&item.key), encode(&item.value)))
+            .collect();
+        pairs.sort();
+        pairs
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Return canonical headers block: one `name:value` line per distinct
+    /// lowercased name, sorted by name, each line terminated by newline.
+    /// Values of repeated headers are joined by comma in original order.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    ///
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: "My-Header2".to_string(),
+    ///             value: r#"  "a   b   c"  "#.to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "Host".to_string(),
+    ///             value: "example.amazonaws.com".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "my-header1".to_string(),
+    ///             value: "value2".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "My-Header1".to_string(),
+    ///             value: "value1".to_string(),
+    ///             meta: None,
+    ///         });
+    /// assert_eq!(
+    ///     headers.canonical_headers(),
+    ///     "host:example.amazonaws.com\nmy-header1:value2,value1\nmy-header2:\"a b c\"\n"
+    /// );
+    /// assert_eq!(headers.signed_headers(), "host;my-header1;my-header2");
+    /// ```
+    pub fn canonical_headers(&self) -> String {
+        let mut headers: Vec<(String, Vec<String>)> = Vec::new();
+        for item in &self.elements {
+            let name = item.key.to_lowercase();
+            let value = normalize_header_value(&item.value);
+            match headers.iter_mut().find(|(key, _)| key.eq(&name)) {
+                Some((_, values)) => values.push(value),
+                None => headers.push((name, vec![value])),
+            }
+        }
+        headers.sort_by(|left, right| left.0.cmp(&right.0));
+        headers
+            .iter()
+            .map(|(name, values)| format!("{name}:{}\n", values.join(",")))
+            .collect()
+    }
+
+    /// Return sorted, semicolon separated list of distinct lowercased header names
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let headers = MultiDict::from_query_string("X-Amz-Date=20150830T123600Z&Host=a&host=b").unwrap();
+    /// assert_eq!(headers.signed_headers(), "host;x-amz-date");
+    /// ```
+    pub fn signed_headers(&self) -> String {
+        let mut names: Vec<String> = self.keys().iter().map(|key| key.to_lowercase()).collect();
+        names.sort();
+        names.dedup();
+        names.join(";")
+    }
+}
+
+/// AWS Signature Version 4 credentials and scope
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SigV4 {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub region: String,
+    pub service: String,
+}
+
+impl SigV4 {
+    /// Return new SigV4 instance
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::signing::SigV4;
+    ///
+    /// let signer = SigV4::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+    ///                         "us-east-1", "iam");
+    /// ```
+    pub fn new(access_key_id: &str, secret_access_key: &str, region: &str, service: &str) -> Self {
+        SigV4 {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            region: region.to_string(),
+            service: service.to_string(),
+        }
+    }
+
+    /// Return canonical request.
+    ///
+    /// `path` is the decoded absolute path, every segment of it is URI-encoded once.
+    /// Payload hash is taken from `x-amz-content-sha256` header if present.
+    ///
+    /// # Examples
+    ///
+    /// `get-vanilla` case of the AWS SigV4 test suite
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::signing::SigV4;
+    ///
+    /// let headers = MultiDict::from_query_string("Host=example.amazonaws.com&X-Amz-Date=20150830T123600Z").unwrap();
+    /// assert_eq!(
+    ///     SigV4::canonical_request("GET", "/", &MultiDict::new(), &headers, b""),
+    ///     "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\n\
+    ///      e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    /// );
+    /// ```
+    pub fn canonical_request(
+        method: &str,
+        path: &str,
+        query: &MultiDict,
+        headers: &MultiDict,
+        payload: &[u8],
+    ) -> String {
+        let path = path.split('/').map(encode).collect::<Vec<_>>().join("/");
+        let payload_hash = match header(headers, "x-amz-content-sha256") {
+            Some(hash) => hash.to_string(),
+            None => digest::hex(&digest::sha256(payload)),
+        };
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method.to_uppercase(),
+            path,
+            query.canonical_query_string(SigningProfile::SigV4),
+            headers.canonical_headers(),
+            headers.signed_headers(),
+            payload_hash
+        )
+    }
+
+    /// Return credential scope `date/region/service/aws4_request`
+    fn scope(&self, datetime: &str) -> Result<String, &'static str> {
+        Ok(format!(
+            "{}/{}/{}/aws4_request",
+            date(datetime)?,
+            self.region,
+            self.service
+        ))
+    }
+
+    /// Return string to sign for `datetime` in `YYYYMMDD'T'HHMMSS'Z'` format
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::signing::SigV4;
+    ///
+    /// let signer = SigV4::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+    ///                         "us-east-1", "service");
+    /// let headers = MultiDict::from_query_string("Host=example.amazonaws.com&X-Amz-Date=20150830T123600Z").unwrap();
+    /// let request = SigV4::canonical_request("GET", "/", &MultiDict::new(), &headers, b"");
+    /// assert_eq!(
+    ///     signer.string_to_sign("20150830T123600Z", &request).unwrap(),
+    ///     "AWS4-HMAC-SHA256\n20150830T123600Z\n20150830/us-east-1/service/aws4_request\n\
+    ///      bb579772317eb040ac9ed261061d46c1f17a8133879d6129b6e1c25292927e63"
+    /// );
+    /// assert_eq!(signer.string_to_sign("2015-08-30", &request).unwrap_err(), "Invalid x-amz-date header");
+    /// ```
+    pub fn string_to_sign(
+        &self,
+        datetime: &str,
+        canonical_request: &str,
+    ) -> Result<String, &'static str> {
+        Ok(format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            datetime,
+            self.scope(datetime)?,
+            digest::hex(&digest::sha256(canonical_request.as_bytes()))
+        ))
+    }
+
+    /// Return signing key derived for `date` in `YYYYMMDD` format
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::signing::SigV4;
+    ///
+    /// let signer = SigV4::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+    ///                         "us-east-1", "iam");
+    /// let key: String = signer.signing_key("20150830").iter().map(|b| format!("{b:02x}")).collect();
+    /// assert_eq!(key, "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9");
+    /// ```
+    pub fn signing_key(&self, date: &str) -> [u8; 32] {
+        let secret = format!("AWS4{}", self.secret_access_key);
+        let key = digest::hmac_sha256(secret.as_bytes(), date.as_bytes());
+        let key = digest::hmac_sha256(&key, self.region.as_bytes());
+        let key = digest::hmac_sha256(&key, self.service.as_bytes());
+        digest::hmac_sha256(&key, b"aws4_request")
+    }
+
+    /// Return hex encoded request signature.
+    /// Request time is taken from required `x-amz-date` header.
+    ///
+    /// # Examples
+    ///
+    /// `get-vanilla` case of the AWS SigV4 test suite
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::signing::SigV4;
+    ///
+    /// let signer = SigV4::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+    ///                         "us-east-1", "service");
+    /// let headers = MultiDict::from_query_string("Host=example.amazonaws.com&X-Amz-Date=20150830T123600Z").unwrap();
+    /// assert_eq!(
+    ///     signer.signature("GET", "/", &MultiDict::new(), &headers, b"").unwrap(),
+    ///     "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+    /// );
+    /// assert_eq!(
+    ///     signer.signature("GET", "/", &MultiDict::new(), &MultiDict::new(), b"").unwrap_err(),
+    ///     "Missing x-amz-date header"
+    /// );
+    /// ```
+    pub fn signature(
+        &self,
+        method: &str,
+        path: &str,
+        query: &MultiDict,
+        headers: &MultiDict,
+        payload: &[u8],
+    ) -> Result<String, &'static str> {
+        let Some(datetime) = header(headers, "x-amz-date") else {
+            return Err("Missing x-amz-date header");
+        };
+        let request = SigV4::canonical_request(method, path, query, headers, payload);
+        let string_to_sign = self.string_to_sign(datetime, &request)?;
+        let key = self.signing_key(date(datetime)?);
+        Ok(digest::hex(&digest::hmac_sha256(
+            &key,
+            string_to_sign.as_bytes(),
+        )))
+    }
+
+    /// Return `Authorization` header value for request
+    ///
+    /// # Examples
+    ///
+    /// IAM `ListUsers` example of the AWS SigV4 documentation
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    /// use multidict::signing::SigV4;
+    ///
+    /// let signer = SigV4::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+    ///                         "us-east-1", "iam");
+    /// let query = MultiDict::from_query_string("Action=ListUsers&Version=2010-05-08").unwrap();
+    /// let mut headers = MultiDict::new();
+    /// headers.add(MultiElement {
+    ///             key: "Content-Type".to_string(),
+    ///             value: "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "Host".to_string(),
+    ///             value: "iam.amazonaws.com".to_string(),
+    ///             meta: None,
+    ///         });
+    /// headers.add(MultiElement {
+    ///             key: "X-Amz-Date".to_string(),
+    ///             value: "20150830T123600Z".to_string(),
+    ///             meta: None,
+    ///         });
+    /// assert_eq!(
+    ///     signer.authorization("GET", "/", &query, &headers, b"").unwrap(),
+    ///     "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+    ///      SignedHeaders=content-type;host;x-amz-date, \
+    ///      Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+    /// );
+    /// ```
+    pub fn authorization(
+        &self,
+        method: &str,
+        path: &str,
+        query: &MultiDict,
+        headers: &MultiDict,
+        payload: &[u8],
+    ) -> Result<String, &'static str> {
+        let signature = self.signature(method, path, query, headers, payload)?;
+        let datetime = header(headers, "x-amz-date").unwrap_or_default();
+        Ok(format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id,
+            self.scope(datetime)?,
+            headers.signed_headers(),
+            signature
+        ))
+    }
+}
+
+/// Return `YYYYMMDD` date of a `YYYYMMDD'T'HHMMSS'Z'` request time
+fn date(datetime: &str) -> Result<&str, &'static str> {
+    let valid = datetime.len() == 16
+        && datetime.bytes().enumerate().all(|(idx, byte)| match idx {
+            8 => byte == b'T',
+            15 => byte == b'Z',
+            _ => byte.is_ascii_digit(),
+        });
+    if !valid {
+        return Err("Invalid x-amz-date header");
+    }
+    Ok(&datetime[..8])
+}
+
+/// Return first value of header `name`, compared case-insensitively
+fn header<'a>(headers: &'a MultiDict, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|item| item.key.eq_ignore_ascii_case(name))
+        .map(|item| item.value.trim())
+}
+
+/// Return OAuth 1.0a signature base string (RFC 5849 section 3.4.1).
+///
+/// `base_uri` is the scheme, host and path of request without query,
+/// `parameters` are query, form body and `oauth_*` protocol parameters together.
+///
+/// # Examples
+/// ```
+/// use multidict::MultiDict;
+/// use multidict::signing::oauth1_base_string;
+///
+/// let parameters = MultiDict::from_query_string("b=2&a=1&oauth_signature=x").unwrap();
+/// assert_eq!(
+///     oauth1_base_string("post", "https://example.com/request", &parameters),
+///     "POST&https%3A%2F%2Fexample.com%2Frequest&a%3D1%26b%3D2"
+/// );
+/// ```
+pub fn oauth1_base_string(method: &str, base_uri: &str, parameters: &MultiDict) -> String {
+    format!(
+        "{}&{}&{}",
+        method.to_uppercase(),
+        encode(base_uri),
+        encode(&parameters.canonical_query_string(SigningProfile::OAuth1))
+    )
+}
+
+/// Return base64 encoded `HMAC-SHA1` OAuth 1.0a signature of `base_string`
+///
+/// # Examples
+/// ```
+/// use multidict::{MultiDict, MultiElement};
+/// use multidict::signing::{oauth1_base_string, oauth1_hmac_sha1};
+///
+/// let mut parameters = MultiDict::new();
+/// for (key, value) in [
+///     ("status", "Hello Ladies + Gentlemen, a signed OAuth request!"),
+///     ("include_entities", "true"),
+///     ("oauth_consumer_key", "xvz1evFS4wEEPTGEFPHBog"),
+///     ("oauth_nonce", "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg"),
+///     ("oauth_signature_method", "HMAC-SHA1"),
+///     ("oauth_timestamp", "1318622958"),
+///     ("oauth_token", "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb"),
+///     ("oauth_version", "1.0"),
+/// ] {
+///     parameters.add(MultiElement::new([key.to_string(), value.to_string()]));
+/// }
+/// let base_string = oauth1_base_string("POST", "https://api.twitter.com/1.1/statuses/update.json",
+///                                      &parameters);
+/// assert_eq!(
+///     oauth1_hmac_sha1(&base_string, "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+///                      "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE"),
+///     "hCtSmYh+iHYCEqBWrE7C7hYmtUk="
+/// );
+/// ```
+pub fn oauth1_hmac_sha1(base_string: &str, consumer_secret: &str, token_secret: &str) -> String {
+    let key = format!("{}&{}", encode(consumer_secret), encode(token_secret));
+    digest::base64_encode(&digest::hmac_sha1(key.as_bytes(), base_string.as_bytes()))
+}
@@ -0,0 +1,411 @@
+//! SIP message headers (RFC 3261) on top of `MultiDict`.
+//!
+//! Header names are case-insensitive and have one-letter compact forms such as `v` for
+//! `Via`, so names are stored in their long canonical form and lookups accept either.
+//! Headers of [`LIST_HEADERS`] may combine several values with commas, these are split
+//! into one element per value. Commas inside quoted strings and angle brackets do not
+//! split.
+use std::fmt;
+
+use crate::metadata::{ElementMeta, Span};
+use crate::{MultiDict, MultiElement};
+
+/// Compact form and long form of header names
+pub const COMPACT_FORMS: [(&str, &str); 20] = [
+    ("a", "Accept-Contact"),
+    ("b", "Referred-By"),
+    ("c", "Content-Type"),
+    ("d", "Request-Disposition"),
+    ("e", "Content-Encoding"),
+    ("f", "From"),
+    ("i", "Call-ID"),
+    ("j", "Reject-Contact"),
+    ("k", "Supported"),
+    ("l", "Content-Length"),
+    ("m", "Contact"),
+    ("n", "Identity-Info"),
+    ("o", "Event"),
+    ("r", "Refer-To"),
+    ("s", "Subject"),
+    ("t", "To"),
+    ("u", "Allow-Events"),
+    ("v", "Via"),
+    ("x", "Session-Expires"),
+    ("y", "Identity"),
+];
+
+/// Headers whose comma separated values are split into separate elements
+pub const LIST_HEADERS: [&str; 21] = [
+    "Accept",
+    "Accept-Contact",
+    "Accept-Encoding",
+    "Accept-Language",
+    "Alert-Info",
+    "Allow",
+    "Allow-Events",
+    "Call-Info",
+    "Contact",
+    "Content-Encoding",
+    "Content-Language",
+    "Error-Info",
+    "In-Reply-To",
+    "Proxy-Require",
+    "Record-Route",
+    "Reject-Contact",
+    "Require",
+    "Route",
+    "Supported",
+    "Unsupported",
+    "Via",
+];
+
+/// Other header names written in their usual capitalization
+const KNOWN_HEADERS: [&str; 19] = [
+    "Authentication-Info",
+    "Authorization",
+    "CSeq",
+    "Date",
+    "Expires",
+    "Max-Forwards",
+    "MIME-Version",
+    "Min-Expires",
+    "Organization",
+    "Priority",
+    "Proxy-Authenticate",
+    "Proxy-Authorization",
+    "Reply-To",
+    "Retry-After",
+    "Server",
+    "Timestamp",
+    "User-Agent",
+    "Warning",
+    "WWW-Authenticate",
+];
+
+/// Header name form used when serializing
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum SipForm {
+    /// Long names such as `Via`
+    #[default]
+    Long,
+    /// Compact names such as `v`, where one exists
+    Compact,
+}
+
+/// Return the long canonical name of a header name or compact form,
+/// unknown names get every `-` separated part capitalized
+///
+/// # Examples
+/// ```
+/// use multidict::sip::canonical_name;
+///
+/// assert_eq!(canonical_name("v"), "Via");
+/// assert_eq!(canonical_name("call-id"), "Call-ID");
+/// assert_eq!(canonical_name("x-CUSTOM"), "X-Custom");
+/// ```
+pub fn canonical_name(name: &str) -> String {
+    if let Some((_, long)) = COMPACT_FORMS.iter().find(|(compact, long)| {
+        compact.eq_ignore_ascii_case(name) || long.eq_ignore_ascii_case(name)
+    }) {
+        return long.to_string();
+    }
+    LIST_HEADERS
+        .iter()
+        .chain(KNOWN_HEADERS.iter())
+        .find(|known| known.eq_ignore_ascii_case(name))
+        .map_or_else(
+            || {
+                name.split('-')
+                    .map(|part| {
+                        let mut chars = part.chars();
+                        chars.next().map_or_else(String::new, |first| {
+                            first.to_ascii_uppercase().to_string()
+                                + &chars.as_str().to_ascii_lowercase()
+                        })
+                    })
+                    .collect::<Vec<_>>()
+                    .join("-")
+            },
+            |known| known.to_string(),
+        )
+}
+
+/// Return the compact form of a header name, if it has one
+///
+/// # Examples
+/// ```
+/// use multidict::sip::compact_name;
+///
+/// assert_eq!(compact_name("Contact"), Some("m"));
+/// assert_eq!(compact_name("CSeq"), None);
+/// ```
+pub fn compact_name(name: &str) -> Option<&'static str> {
+    let name = canonical_name(name);
+    COMPACT_FORMS
+        .iter()
+        .find(|(_, long)| *long == name)
+        .map(|(compact, _)| *compact)
+}
+
+/// Return true if `name` is a header with comma separated values
+pub fn is_list_header(name: &str) -> bool {
+    LIST_HEADERS.contains(&canonical_name(name).as_str())
+}
+
+/// Split header value on commas outside quoted strings and `<...>`
+fn split_values(value: &str) -> Vec<&str> {
+    let mut values = Vec::new();
+    let (mut quoted, mut escaped, mut angle, mut start) = (false, false, false, 0);
+    for (index, char) in value.char_indices() {
+        match char {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' if !quoted && !angle => {
+                values.push(value[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    values.push(value[start..].trim());
+    values.retain(|value| !value.is_empty());
+    values
+}
+
+/// Headers of a SIP message
+#[derive(Clone)]
+pub struct SipHeaders<M = ()> {
+    headers: MultiDict<M>,
+}
+
+impl<M> Default for SipHeaders<M> {
+    fn default() -> Self {
+        SipHeaders {
+            headers: MultiDict::default(),
+        }
+    }
+}
+
+impl<M: ElementMeta> fmt::Display for SipHeaders<M> {
+    /// Headers in `MultiDict` format with long names
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.headers)
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for SipHeaders<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SipHeaders")
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+impl SipHeaders {
+    /// Return empty headers
+    pub fn new() -> Self {
+        SipHeaders::default()
+    }
+
+    /// Parse header section of a SIP message, ends at the first empty line
+    ///
+    /// Lines may end with CRLF or LF, lines starting with whitespace continue the
+    /// previous header.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::sip::SipHeaders;
+    ///
+    /// let message = "v: SIP/2.0/UDP pc33.example.com;branch=z9hG4bK776asdhds\r\n\
+    ///                m: <sip:alice@pc33.example.com>,\r\n \
+    ///                <sip:alice@192.0.2.4>\r\n\
+    ///                CSeq: 314159 INVITE\r\n\r\nv=0\r\n";
+    /// let headers = SipHeaders::from_sip(message).unwrap();
+    /// assert_eq!(headers.getall("Contact"), vec!["<sip:alice@pc33.example.com>",
+    ///                                            "<sip:alice@192.0.2.4>"]);
+    /// assert_eq!(headers.get("cseq"), Some("314159 INVITE"));
+    /// ```
+    pub fn from_sip(input: &str) -> Result<SipHeaders, &'static str> {
+        parse_sip(input)
+    }
+}
+
+impl<M: ElementMeta> SipHeaders<M> {
+    /// Parse header section like [`SipHeaders::from_sip`], every value gets metadata for
+    /// the byte range of its header line including continuation lines
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::metadata::Span;
+    /// use multidict::sip::SipHeaders;
+    ///
+    /// let message = "Max-Forwards: 70\r\nExpires: soon\r\n\r\n";
+    /// let headers: SipHeaders<Span> = SipHeaders::from_sip_spanned(message).unwrap();
+    /// let expires = headers.headers().get("Expires").unwrap();
+    /// assert_eq!(expires.meta.unwrap().slice(message), "Expires: soon");
+    /// ```
+    pub fn from_sip_spanned(input: &str) -> Result<SipHeaders<M>, &'static str> {
+        parse_sip(input)
+    }
+}
+
+impl<M> SipHeaders<M> {
+    /// Return the number of header values
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Return true if there are no headers
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Return header values with long canonical names in order
+    pub fn headers(&self) -> &MultiDict<M> {
+        &self.headers
+    }
+
+    /// Append header under its long name, values of list headers are split on commas
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::sip::SipHeaders;
+    ///
+    /// let mut headers = SipHeaders::new();
+    /// headers.add("v", "SIP/2.0/UDP a.example.com;branch=z9hG4bK1, SIP/2.0/TCP b.example.com");
+    /// headers.add("f", r#""Doe, John" <sip:john@example.com>;tag=42"#);
+    /// println!("{headers}");
+    /// // MultiDict < "Via":"SIP/2.0/UDP a.example.com;branch=z9hG4bK1", "Via":"SIP/2.0/TCP b.example.com", "From":""Doe, John" <sip:john@example.com>;tag=42" >
+    /// assert_eq!(headers.getall("via").len(), 2);
+    /// ```
+    pub fn add(&mut self, name: &str, value: &str) {
+        self.add_with_meta(name, value, || None);
+    }
+
+    /// Append header like [`SipHeaders::add`], `meta` is called once per element
+    fn add_with_meta(&mut self, name: &str, value: &str, meta: impl Fn() -> Option<M>) {
+        let key = canonical_name(name);
+        let values = if is_list_header(&key) {
+            split_values(value)
+        } else {
+            vec![value.trim()]
+        };
+        for value in values {
+            self.headers.add(MultiElement {
+                key: key.clone(),
+                value: value.to_string(),
+                meta: meta(),
+            });
+        }
+    }
+
+    /// Return the **first** value of header by long name or compact form
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::sip::SipHeaders;
+    ///
+    /// let mut headers = SipHeaders::new();
+    /// headers.add("Call-ID", "a84b4c76e66710@pc33.example.com");
+    /// assert_eq!(headers.get("i"), Some("a84b4c76e66710@pc33.example.com"));
+    /// assert_eq!(headers.get("CALL-ID"), Some("a84b4c76e66710@pc33.example.com"));
+    /// ```
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&canonical_name(name))
+            .ok()
+            .map(|item| item.value.as_str())
+    }
+
+    /// Return all values of header by long name or compact form
+    pub fn getall(&self, name: &str) -> Vec<&str> {
+        let key = canonical_name(name);
+        self.headers
+            .iter()
+            .filter(|item| item.key == key)
+            .map(|item| item.value.as_str())
+            .collect()
+    }
+
+    /// Return true if header is present
+    pub fn contains(&self, name: &str) -> bool {
+        self.headers.contains(&canonical_name(name))
+    }
+
+    /// Remove all values of header, return true if any was present
+    pub fn remove(&mut self, name: &str) -> bool {
+        let key = canonical_name(name);
+        let count = self.headers.len();
+        self.headers.elements.retain(|item| item.key != key);
+        self.headers.len() != count
+    }
+
+    /// Return header section with one line per value, each ending with CRLF
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::sip::{SipForm, SipHeaders};
+    ///
+    /// let mut headers = SipHeaders::new();
+    /// headers.add("To", "<sip:bob@example.com>");
+    /// headers.add("CSeq", "1 INVITE");
+    /// assert_eq!(headers.to_sip(SipForm::Long), "To: <sip:bob@example.com>\r\nCSeq: 1 INVITE\r\n");
+    /// assert_eq!(headers.to_sip(SipForm::Compact), "t: <sip:bob@example.com>\r\nCSeq: 1 INVITE\r\n");
+    /// ```
+    pub fn to_sip(&self, form: SipForm) -> String {
+        let mut out = String::new();
+        for item in self.headers.iter() {
+            let name = match form {
+                SipForm::Long => None,
+                SipForm::Compact => compact_name(&item.key),
+            };
+            out.push_str(name.unwrap_or(&item.key));
+            out.push_str(": ");
+            out.push_str(&item.value);
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
+
+/// Parse header lines, metadata is built from the span of each header line
+fn parse_sip<M: ElementMeta>(input: &str) -> Result<SipHeaders<M>, &'static str> {
+    let mut lines: Vec<(String, Span)> = Vec::new();
+    let mut start = 0;
+    for line in input.split('\n') {
+        let line_start = start;
+        start += line.len() + 1;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() {
+            break;
+        }
+        let end = line_start + line.len();
+        if line.starts_with([' ', '\t']) {
+            let (previous, span) = lines.last_mut().ok_or("SIP header continues nothing")?;
+            previous.push(' ');
+            previous.push_str(line.trim());
+            span.end = end;
+        } else {
+            lines.push((
+                line.to_string(),
+                Span {
+                    start: line_start,
+                    end,
+                },
+            ));
+        }
+    }
+    let mut results = SipHeaders::default();
+    for (line, span) in lines {
+        let (name, value) = line.split_once(':').ok_or("SIP header has no colon")?;
+        let name = name.trim_end();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err("Invalid SIP header name");
+        }
+        results.add_with_meta(name, value, || M::from_span(span));
+    }
+    Ok(results)
+}
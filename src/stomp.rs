@@ -0,0 +1,276 @@
+//! STOMP 1.2 frames with `MultiDict` headers.
+//!
+//! A frame is a command line, header lines, an empty line and a body ended by NUL.
+//! Headers may repeat and only the first occurrence counts, which is what
+//! [`MultiDict::get`] returns; later duplicates are kept so frames round-trip exactly.
+//! Header names and values escape CR, LF, `:` and `\`, except in `CONNECT` and
+//! `CONNECTED` frames.
+use std::fmt;
+
+use crate::metadata::{ElementMeta, Span};
+use crate::{MultiDict, MultiElement};
+
+/// One STOMP frame
+#[derive(Clone)]
+pub struct StompFrame<M = ()> {
+    pub command: String,
+    pub headers: MultiDict<M>,
+    pub body: Vec<u8>,
+}
+
+impl<M> Default for StompFrame<M> {
+    fn default() -> Self {
+        StompFrame {
+            command: String::new(),
+            headers: MultiDict::default(),
+            body: Vec::new(),
+        }
+    }
+}
+
+impl<M: ElementMeta + fmt::Debug> fmt::Debug for StompFrame<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StompFrame")
+            .field("command", &self.command)
+            .field("headers", &self.headers)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+/// Return true if headers of `command` frames are written without escapes
+fn is_unescaped(command: &str) -> bool {
+    command == "CONNECT" || command == "CONNECTED"
+}
+
+/// Return `value` with STOMP header escapes
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for char in value.chars() {
+        match char {
+            '\r' => out.push_str(r"\r"),
+            '\n' => out.push_str(r"\n"),
+            ':' => out.push_str(r"\c"),
+            '\\' => out.push_str(r"\\"),
+            _ => out.push(char),
+        }
+    }
+    out
+}
+
+/// Return `value` with STOMP header escapes resolved, undefined escapes are errors
+fn unescape(value: &str) -> Result<String, &'static str> {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            out.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('r') => out.push('\r'),
+            Some('n') => out.push('\n'),
+            Some('c') => out.push(':'),
+            Some('\\') => out.push('\\'),
+            _ => return Err("Undefined STOMP header escape"),
+        }
+    }
+    Ok(out)
+}
+
+/// Return line at `position` without its CRLF or LF and move past it
+fn read_line<'a>(input: &'a [u8], position: &mut usize) -> Result<&'a str, &'static str> {
+    let rest = &input[*position..];
+    let end = rest
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .ok_or("Truncated STOMP frame")?;
+    *position += end + 1;
+    let line = rest[..end].strip_suffix(b"\r").unwrap_or(&rest[..end]);
+    std::str::from_utf8(line).map_err(|_| "STOMP frame header is not valid UTF-8")
+}
+
+impl StompFrame {
+    /// Return frame of `command` without headers and body
+    pub fn new(command: &str) -> Self {
+        StompFrame {
+            command: command.to_string(),
+            ..StompFrame::default()
+        }
+    }
+
+    /// Decode the frame at the start of `input`, return it and the number of bytes read
+    ///
+    /// Heart-beat EOLs before the frame are skipped. With a `content-length` header the
+    /// body is that many bytes and may contain NUL, otherwise it ends at the first NUL.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::stomp::StompFrame;
+    ///
+    /// let input = b"\nMESSAGE\nsubscription:0\nmessage-id:007\ndestination:/queue/a\\cb\n\
+    ///               content-length:5\n\nhe\0lo\0\n";
+    /// let (frame, length) = StompFrame::from_bytes(input).unwrap();
+    /// assert_eq!(frame.command, "MESSAGE");
+    /// assert_eq!(frame.get("destination"), Some("/queue/a:b"));
+    /// assert_eq!(frame.body, b"he\0lo");
+    /// assert_eq!(length, input.len() - 1);
+    /// ```
+    pub fn from_bytes(input: &[u8]) -> Result<(StompFrame, usize), &'static str> {
+        parse_frame(input)
+    }
+}
+
+impl<M: ElementMeta> StompFrame<M> {
+    /// Decode frame like [`StompFrame::from_bytes`], every header gets metadata for the
+    /// byte range of its line in `input`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::metadata::Span;
+    /// use multidict::stomp::StompFrame;
+    ///
+    /// let input = b"SEND\ndestination:/queue/a\nreceipt:77\n\nhi\0";
+    /// let (frame, _): (StompFrame<Span>, _) = StompFrame::from_bytes_spanned(input).unwrap();
+    /// assert_eq!(frame.headers.get("receipt").unwrap().meta, Some(Span { start: 26, end: 36 }));
+    /// ```
+    pub fn from_bytes_spanned(input: &[u8]) -> Result<(StompFrame<M>, usize), &'static str> {
+        parse_frame(input)
+    }
+}
+
+impl<M> StompFrame<M> {
+    /// Append header, duplicates are kept
+    pub fn add_header(&mut self, name: &str, value: &str) {
+        self.headers.add(MultiElement {
+            key: name.to_string(),
+            value: value.to_string(),
+            meta: None,
+        });
+    }
+
+    /// Return value of the **first** occurrence of header, later ones are ignored
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::stomp::StompFrame;
+    ///
+    /// let mut frame = StompFrame::new("MESSAGE");
+    /// frame.add_header("foo", "World");
+    /// frame.add_header("foo", "Hello");
+    /// assert_eq!(frame.get("foo"), Some("World"));
+    /// ```
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).ok().map(|item| item.value.as_str())
+    }
+
+    /// Return value of the first `content-length` header
+    pub fn content_length(&self) -> Result<Option<usize>, &'static str> {
+        self.get("content-length")
+            .map(|value| value.parse().map_err(|_| "Invalid STOMP content-length"))
+            .transpose()
+    }
+
+    /// Encode frame with headers in order, including duplicates
+    ///
+    /// A body containing NUL needs a `content-length` header, which must match the body.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::stomp::StompFrame;
+    ///
+    /// let mut frame = StompFrame::new("SEND");
+    /// frame.add_header("destination", "/queue/a:b");
+    /// frame.add_header("note", "line\nbreak");
+    /// frame.body = b"hello".to_vec();
+    /// assert_eq!(frame.to_bytes().unwrap(),
+    ///            b"SEND\ndestination:/queue/a\\cb\nnote:line\\nbreak\n\nhello\0");
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>, &'static str> {
+        if self.command.is_empty() || self.command.contains(['\r', '\n']) {
+            return Err("Invalid STOMP command");
+        }
+        match self.content_length()? {
+            Some(length) if length != self.body.len() => {
+                return Err("STOMP content-length does not match body")
+            }
+            None if self.body.contains(&0) => {
+                return Err("STOMP body with NUL needs content-length")
+            }
+            _ => {}
+        }
+        let mut out = Vec::with_capacity(self.body.len() + 64);
+        out.extend_from_slice(self.command.as_bytes());
+        out.push(b'\n');
+        for item in self.headers.iter() {
+            if is_unescaped(&self.command) {
+                if item.key.contains([':', '\r', '\n']) || item.value.contains(['\r', '\n']) {
+                    return Err("Invalid header in unescaped STOMP frame");
+                }
+                out.extend_from_slice(item.key.as_bytes());
+                out.push(b':');
+                out.extend_from_slice(item.value.as_bytes());
+            } else {
+                out.extend_from_slice(escape(&item.key).as_bytes());
+                out.push(b':');
+                out.extend_from_slice(escape(&item.value).as_bytes());
+            }
+            out.push(b'\n');
+        }
+        out.push(b'\n');
+        out.extend_from_slice(&self.body);
+        out.push(0);
+        Ok(out)
+    }
+}
+
+/// Decode frame, metadata is built from the span of each header line
+fn parse_frame<M: ElementMeta>(input: &[u8]) -> Result<(StompFrame<M>, usize), &'static str> {
+    let mut position = 0;
+    let command = loop {
+        let line = read_line(input, &mut position)?;
+        if !line.is_empty() {
+            break line;
+        }
+    };
+    let mut frame = StompFrame {
+        command: command.to_string(),
+        ..StompFrame::default()
+    };
+    loop {
+        let start = position;
+        let line = read_line(input, &mut position)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').ok_or("STOMP frame header has no colon")?;
+        let (key, value) = if is_unescaped(command) {
+            (name.to_string(), value.to_string())
+        } else {
+            (unescape(name)?, unescape(value)?)
+        };
+        frame.headers.add(MultiElement {
+            key,
+            value,
+            meta: M::from_span(Span {
+                start,
+                end: start + line.len(),
+            }),
+        });
+    }
+    let rest = &input[position..];
+    let length = match frame.content_length()? {
+        Some(length) => length,
+        None => rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or("Truncated STOMP frame")?,
+    };
+    match rest.get(length) {
+        Some(0) => {}
+        Some(_) => return Err("STOMP frame body is not followed by NUL"),
+        None => return Err("Truncated STOMP frame"),
+    }
+    frame.body = rest[..length].to_vec();
+    Ok((frame, position + length + 1))
+}
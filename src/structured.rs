@@ -0,0 +1,434 @@
+//! RFC 8941 Structured Field Values parsing and serialization.
+//!
+//! Used to re-serialize header values strictly (`;sf` and `;key` component
+//! parameters of HTTP Message Signatures) and to read `Signature-Input` and
+//! `Signature` dictionaries.
+use std::fmt;
+
+use crate::digest;
+
+/// Bare item of a structured field
+#[derive(Debug, Clone, PartialEq)]
+pub enum BareItem {
+    Integer(i64),
+    Decimal(f64),
+    String(String),
+    Token(String),
+    ByteSequence(Vec<u8>),
+    Boolean(bool),
+}
+
+/// Ordered parameters of an item or an inner list
+pub type Parameters = Vec<(String, BareItem)>;
+
+/// Bare item with its parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub value: BareItem,
+    pub parameters: Parameters,
+}
+
+/// Member of a list or a dictionary
+#[derive(Debug, Clone, PartialEq)]
+pub enum Member {
+    Item(Item),
+    InnerList(Vec<Item>, Parameters),
+}
+
+impl fmt::Display for BareItem {
+    /// Serialize bare item according to RFC 8941 section 4.1.3
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::structured::BareItem;
+    ///
+    /// assert_eq!(BareItem::Decimal(1.50).to_string(), "1.5");
+    /// assert_eq!(BareItem::String(r#"say "hi""#.to_string()).to_string(), r#""say \"hi\"""#);
+    /// assert_eq!(BareItem::ByteSequence(b"hello".to_vec()).to_string(), ":aGVsbG8=:");
+    /// assert_eq!(BareItem::Boolean(true).to_string(), "?1");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BareItem::Integer(value) => write!(f, "{value}"),
+            BareItem::Decimal(value) => {
+                let value = format!("{value:.3}");
+                let value = value.trim_end_matches('0');
+                if value.ends_with('.') {
+                    write!(f, "{value}0")
+                } else {
+                    write!(f, "{value}")
+                }
+            }
+            BareItem::String(value) => {
+                write!(
+                    f,
+                    "\"{}\"",
+                    value.replace('\\', "\\\\").replace('"', "\\\"")
+                )
+            }
+            BareItem::Token(value) => write!(f, "{value}"),
+            BareItem::ByteSequence(value) => write!(f, ":{}:", digest::base64_encode(value)),
+            BareItem::Boolean(value) => write!(f, "?{}", u8::from(*value)),
+        }
+    }
+}
+
+/// Return `;key` or `;key=value` for every parameter
+fn serialize_parameters(parameters: &Parameters) -> String {
+    parameters
+        .iter()
+        .map(|(key, value)| match value {
+            BareItem::Boolean(true) => format!(";{key}"),
+            value => format!(";{key}={value}"),
+        })
+        .collect()
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.value,
+            serialize_parameters(&self.parameters)
+        )
+    }
+}
+
+impl fmt::Display for Member {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Member::Item(item) => write!(f, "{item}"),
+            Member::InnerList(items, parameters) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(
+                    f,
+                    "({}){}",
+                    items.join(" "),
+                    serialize_parameters(parameters)
+                )
+            }
+        }
+    }
+}
+
+/// Return serialized list
+///
+/// # Examples
+/// ```
+/// use multidict::structured::{parse_list, serialize_list};
+///
+/// let list = parse_list("sugar,   tea;hot,(rum   \"milk\");x=1").unwrap();
+/// assert_eq!(serialize_list(&list), "sugar, tea;hot, (rum \"milk\");x=1");
+/// ```
+pub fn serialize_list(members: &[Member]) -> String {
+    members
+        .iter()
+        .map(|member| member.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Return serialized dictionary
+///
+/// # Examples
+/// ```
+/// use multidict::structured::{parse_dictionary, serialize_dictionary};
+///
+/// let dictionary = parse_dictionary("a=1,    b=2;x=1;y=2,   c=(a   b   c), d").unwrap();
+/// assert_eq!(serialize_dictionary(&dictionary), "a=1, b=2;x=1;y=2, c=(a b c), d");
+/// ```
+pub fn serialize_dictionary(members: &[(String, Member)]) -> String {
+    members
+        .iter()
+        .map(|(key, member)| match member {
+            Member::Item(Item {
+                value: BareItem::Boolean(true),
+                parameters,
+            }) => format!("{key}{}", serialize_parameters(parameters)),
+            member => format!("{key}={member}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse single item field value
+///
+/// # Examples
+/// ```
+/// use multidict::structured::{parse_item, BareItem};
+///
+/// let item = parse_item("  \"value\";a=1;b  ").unwrap();
+/// assert_eq!(item.value, BareItem::String("value".to_string()));
+/// assert_eq!(item.parameters[1], ("b".to_string(), BareItem::Boolean(true)));
+/// assert_eq!(item.to_string(), "\"value\";a=1;b");
+/// ```
+pub fn parse_item(input: &str) -> Result<Item, &'static str> {
+    let mut parser = Parser::new(input);
+    let item = parser.item()?;
+    parser.finish()?;
+    Ok(item)
+}
+
+/// Parse list field value
+pub fn parse_list(input: &str) -> Result<Vec<Member>, &'static str> {
+    let mut parser = Parser::new(input);
+    let mut members = Vec::new();
+    while !parser.at_end() {
+        members.push(parser.member()?);
+        if !parser.separator()? {
+            break;
+        }
+    }
+    parser.finish()?;
+    Ok(members)
+}
+
+/// Parse dictionary field value, a later duplicate key replaces an earlier one
+///
+/// # Examples
+/// ```
+/// use multidict::structured::{parse_dictionary, BareItem, Item, Member};
+///
+/// let dictionary = parse_dictionary("a=1, b, a=3").unwrap();
+/// assert_eq!(dictionary.len(), 2);
+/// assert_eq!(dictionary[0].1, Member::Item(Item { value: BareItem::Integer(3), parameters: vec![] }));
+/// assert!(parse_dictionary("A=1").is_err());
+/// ```
+pub fn parse_dictionary(input: &str) -> Result<Vec<(String, Member)>, &'static str> {
+    let mut parser = Parser::new(input);
+    let mut members: Vec<(String, Member)> = Vec::new();
+    while !parser.at_end() {
+        let key = parser.key()?;
+        let member = if parser.eat(b'=') {
+            parser.member()?
+        } else {
+            Member::Item(Item {
+                value: BareItem::Boolean(true),
+                parameters: parser.parameters()?,
+            })
+        };
+        match members.iter_mut().find(|(name, _)| name.eq(&key)) {
+            Some((_, existing)) => *existing = member,
+            None => members.push((key, member)),
+        }
+        if !parser.separator()? {
+            break;
+        }
+    }
+    parser.finish()?;
+    Ok(members)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        let input = input.trim_matches(' ').as_bytes();
+        Parser { input, position: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip(&mut self, bytes: &[u8]) {
+        while self.peek().is_some_and(|byte| bytes.contains(&byte)) {
+            self.position += 1;
+        }
+    }
+
+    fn finish(&self) -> Result<(), &'static str> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err("Unexpected trailing characters in structured field")
+        }
+    }
+
+    /// Consume `OWS "," OWS` between members, return false at end of input
+    fn separator(&mut self) -> Result<bool, &'static str> {
+        self.skip(b" \t");
+        if self.at_end() {
+            return Ok(false);
+        }
+        if !self.eat(b',') {
+            return Err("Expected ',' between structured field members");
+        }
+        self.skip(b" \t");
+        if self.at_end() {
+            return Err("Trailing ',' in structured field");
+        }
+        Ok(true)
+    }
+
+    fn member(&mut self) -> Result<Member, &'static str> {
+        if !self.eat(b'(') {
+            return Ok(Member::Item(self.item()?));
+        }
+        let mut items = Vec::new();
+        loop {
+            self.skip(b" ");
+            if self.eat(b')') {
+                return Ok(Member::InnerList(items, self.parameters()?));
+            }
+            items.push(self.item()?);
+            if !matches!(self.peek(), Some(b' ') | Some(b')')) {
+                return Err("Malformed inner list");
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Item, &'static str> {
+        Ok(Item {
+            value: self.bare_item()?,
+            parameters: self.parameters()?,
+        })
+    }
+
+    fn parameters(&mut self) -> Result<Parameters, &'static str> {
+        let mut parameters: Parameters = Vec::new();
+        while self.eat(b';') {
+            self.skip(b" ");
+            let key = self.key()?;
+            let value = if self.eat(b'=') {
+                self.bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            match parameters.iter_mut().find(|(name, _)| name.eq(&key)) {
+                Some((_, existing)) => *existing = value,
+                None => parameters.push((key, value)),
+            }
+        }
+        Ok(parameters)
+    }
+
+    fn key(&mut self) -> Result<String, &'static str> {
+        let start = self.position;
+        if !self
+            .peek()
+            .is_some_and(|byte| byte.is_ascii_lowercase() || byte == b'*')
+        {
+            return Err("Invalid structured field key");
+        }
+        self.skip(b"abcdefghijklmnopqrstuvwxyz0123456789_-.*");
+        Ok(String::from_utf8_lossy(&self.input[start..self.position]).into_owned())
+    }
+
+    fn bare_item(&mut self) -> Result<BareItem, &'static str> {
+        match self.peek() {
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b'"') => self.string(),
+            Some(b':') => self.byte_sequence(),
+            Some(b'?') => {
+                self.position += 1;
+                match self.peek() {
+                    Some(b'0') => {
+                        self.position += 1;
+                        Ok(BareItem::Boolean(false))
+                    }
+                    Some(b'1') => {
+                        self.position += 1;
+                        Ok(BareItem::Boolean(true))
+                    }
+                    _ => Err("Invalid structured field boolean"),
+                }
+            }
+            Some(byte) if byte.is_ascii_alphabetic() || byte == b'*' => {
+                let start = self.position;
+                while self.peek().is_some_and(|byte| {
+                    byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~:/".contains(&byte)
+                }) {
+                    self.position += 1;
+                }
+                let token = String::from_utf8_lossy(&self.input[start..self.position]);
+                Ok(BareItem::Token(token.into_owned()))
+            }
+            _ => Err("Invalid structured field item"),
+        }
+    }
+
+    fn number(&mut self) -> Result<BareItem, &'static str> {
+        let start = self.position;
+        self.eat(b'-');
+        let digits_start = self.position;
+        self.skip(b"0123456789");
+        let integer_digits = self.position - digits_start;
+        if integer_digits == 0 {
+            return Err("Invalid structured field number");
+        }
+        if !self.eat(b'.') {
+            if integer_digits > 15 {
+                return Err("Structured field integer is too long");
+            }
+            let text = String::from_utf8_lossy(&self.input[start..self.position]);
+            return text
+                .parse()
+                .map(BareItem::Integer)
+                .map_err(|_| "Invalid structured field number");
+        }
+        let fraction_start = self.position;
+        self.skip(b"0123456789");
+        let fraction_digits = self.position - fraction_start;
+        if integer_digits > 12 || fraction_digits == 0 || fraction_digits > 3 {
+            return Err("Invalid structured field decimal");
+        }
+        let text = String::from_utf8_lossy(&self.input[start..self.position]);
+        text.parse()
+            .map(BareItem::Decimal)
+            .map_err(|_| "Invalid structured field number")
+    }
+
+    fn string(&mut self) -> Result<BareItem, &'static str> {
+        self.position += 1;
+        let mut result = String::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(BareItem::String(result));
+                }
+                Some(b'\\') => {
+                    self.position += 1;
+                    match self.peek() {
+                        Some(byte @ (b'"' | b'\\')) => result.push(byte as char),
+                        _ => return Err("Invalid escape in structured field string"),
+                    }
+                }
+                Some(byte @ 0x20..=0x7e) => result.push(byte as char),
+                _ => return Err("Invalid structured field string"),
+            }
+            self.position += 1;
+        }
+    }
+
+    fn byte_sequence(&mut self) -> Result<BareItem, &'static str> {
+        self.position += 1;
+        let start = self.position;
+        while self.peek().is_some_and(|byte| byte != b':') {
+            self.position += 1;
+        }
+        if !self.eat(b':') {
+            return Err("Unterminated structured field byte sequence");
+        }
+        let data = String::from_utf8_lossy(&self.input[start..self.position - 1]);
+        digest::base64_decode(&data).map(BareItem::ByteSequence)
+    }
+}
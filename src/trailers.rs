@@ -0,0 +1,168 @@
+//! Git commit message trailers such as `Signed-off-by:`.
+//!
+//! The trailer block is the last paragraph of a message when every line of it is a
+//! `Token: value` trailer or a continuation line starting with whitespace, and it is not
+//! the title paragraph. Trailers repeat freely and map onto `MultiDict` in order;
+//! continuation lines are unfolded with single spaces.
+use crate::metadata::{ElementMeta, Span};
+use crate::{MultiDict, MultiElement};
+
+/// Return true if `token` is a valid trailer token
+fn is_token(token: &str) -> bool {
+    !token.is_empty()
+        && token
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-')
+}
+
+/// Return trailers of `paragraph` lines with their spans, `None` if any line is not part
+/// of a trailer
+fn parse_block<M: ElementMeta>(paragraph: &[(&str, Span)]) -> Option<MultiDict<M>> {
+    let mut trailers: Vec<(String, String, Span)> = Vec::new();
+    for (line, line_span) in paragraph {
+        if line.starts_with([' ', '\t']) {
+            let (_, value, span) = trailers.last_mut()?;
+            value.push(' ');
+            value.push_str(line.trim());
+            span.end = line_span.end;
+            continue;
+        }
+        let (token, value) = line.split_once(':')?;
+        if !is_token(token) {
+            return None;
+        }
+        trailers.push((token.to_string(), value.trim().to_string(), *line_span));
+    }
+    let mut results = MultiDict::default();
+    for (key, value, span) in trailers {
+        results.add(MultiElement {
+            key,
+            value,
+            meta: M::from_span(span),
+        });
+    }
+    Some(results)
+}
+
+/// Return message without its trailer block and the trailers, trailing blank lines are
+/// removed from the message
+///
+/// # Examples
+/// ```
+/// use multidict::trailers::split_trailers;
+///
+/// let message = "Fix parser\n\nHandle empty input.\n\n\
+///                Signed-off-by: Alice <alice@example.com>\n\
+///                Reviewed-by: Bob <bob@example.com>\n\
+///                Signed-off-by: Carol <carol@example.com>\n";
+/// let (body, trailers) = split_trailers(message);
+/// assert_eq!(body, "Fix parser\n\nHandle empty input.");
+/// assert_eq!(trailers.getall("Signed-off-by").unwrap().len(), 2);
+/// ```
+pub fn split_trailers(message: &str) -> (&str, MultiDict) {
+    split(message)
+}
+
+/// Split trailers off `message`, metadata is built from the span of each trailer
+fn split<M: ElementMeta>(message: &str) -> (&str, MultiDict<M>) {
+    let trimmed = message.trim_end();
+    let mut lines = Vec::new();
+    let (mut offset, mut blank) = (0, None);
+    for line in trimmed.split('\n') {
+        if line.trim().is_empty() {
+            blank = Some((offset, lines.len()));
+        }
+        let text = line.trim_end_matches('\r');
+        lines.push((
+            text,
+            Span {
+                start: offset,
+                end: offset + text.len(),
+            },
+        ));
+        offset += line.len() + 1;
+    }
+    let Some((offset, index)) = blank else {
+        return (trimmed, MultiDict::default());
+    };
+    match parse_block(&lines[index + 1..]) {
+        Some(trailers) => (trimmed[..offset].trim_end(), trailers),
+        None => (trimmed, MultiDict::default()),
+    }
+}
+
+impl<M: ElementMeta> MultiDict<M> {
+    /// Return trailers like [`MultiDict::from_git_trailers`], every element gets metadata
+    /// for the byte range of its trailer lines in `message`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::metadata::Span;
+    ///
+    /// let message = "Title\n\nFixes: 12\nAcked-by: A\n";
+    /// let trailers: MultiDict<Span> = MultiDict::from_git_trailers_spanned(message);
+    /// assert_eq!(trailers.get("Fixes").unwrap().meta.unwrap().slice(message), "Fixes: 12");
+    /// ```
+    pub fn from_git_trailers_spanned(message: &str) -> MultiDict<M> {
+        split(message).1
+    }
+}
+
+impl MultiDict {
+    /// Return trailers of a commit message, empty if it has no trailer block
+    pub fn from_git_trailers(message: &str) -> MultiDict {
+        split_trailers(message).1
+    }
+
+    /// Return trailer lines of the elements, each ending with a line feed
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let trailers = MultiDict::from_git_trailers("Title\n\nAcked-by: A\nFixes: 1234abcd\n");
+    /// assert_eq!(trailers.to_git_trailers().unwrap(), "Acked-by: A\nFixes: 1234abcd\n");
+    /// ```
+    pub fn to_git_trailers(&self) -> Result<String, &'static str> {
+        let mut out = String::new();
+        for item in self.iter() {
+            if !is_token(&item.key) {
+                return Err("Invalid trailer token");
+            }
+            if item.value.contains(['\r', '\n']) {
+                return Err("Trailer value contains a line break");
+            }
+            out.push_str(&item.key);
+            out.push_str(": ");
+            out.push_str(&item.value);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// Return `message` with the elements appended to its trailer block, which is
+    /// created after a blank line if the message has none. Existing text is kept as is.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let trailers = MultiDict::from_query_string("Signed-off-by=Bob").unwrap();
+    /// assert_eq!(trailers.append_git_trailers("Title\n\nBody\n").unwrap(),
+    ///            "Title\n\nBody\n\nSigned-off-by: Bob\n");
+    /// assert_eq!(trailers.append_git_trailers("Title\n\nAcked-by: A\n").unwrap(),
+    ///            "Title\n\nAcked-by: A\nSigned-off-by: Bob\n");
+    /// ```
+    pub fn append_git_trailers(&self, message: &str) -> Result<String, &'static str> {
+        let message = message.trim_end();
+        let trailers = self.to_git_trailers()?;
+        if trailers.is_empty() {
+            Ok(format!("{message}\n"))
+        } else if split_trailers(message).1.is_empty() {
+            Ok(format!("{message}\n\n{trailers}"))
+        } else {
+            Ok(format!("{message}\n{trailers}"))
+        }
+    }
+}
@@ -0,0 +1,503 @@
+//! RFC 6570 URI Template expansion (levels 1–4) with `MultiDict` as the variable source.
+//!
+//! A key which occurs once in the `MultiDict` is a string variable, a key which
+//! occurs several times is a list variable with values in element order.
+//! Keys `name[field]` form the associative array variable `name`, with pairs in
+//! element order, when `name` itself is not a key.
+//! Keys which are not in the `MultiDict` are undefined variables.
+use std::collections::HashSet;
+
+use crate::{percent, MultiDict, MultiElement};
+
+/// Return true for RFC 3986 `reserved` characters
+fn is_reserved(byte: u8) -> bool {
+    b":/?#[]@!$&'()*+,;=".contains(&byte)
+}
+
+/// Reserved expansion, also used for template literals
+const RESERVED: Operator = Operator {
+    first: "",
+    sep: ",",
+    named: false,
+    ifemp: "",
+    allow_reserved: true,
+};
+
+/// Expression operator as described in RFC 6570 Appendix A
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Operator {
+    first: &'static str,
+    sep: &'static str,
+    named: bool,
+    ifemp: &'static str,
+    allow_reserved: bool,
+}
+
+impl Operator {
+    fn from_char(operator: Option<char>) -> Result<Operator, &'static str> {
+        let (first, sep, named, ifemp, allow_reserved) = match operator {
+            None => ("", ",", false, "", false),
+            Some('+') => return Ok(RESERVED),
+            Some('.') => (".", ".", false, "", false),
+            Some('/') => ("/", "/", false, "", false),
+            Some(';') => (";", ";", true, "", false),
+            Some('?') => ("?", "&", true, "=", false),
+            Some('&') => ("&", "&", true, "=", false),
+            Some('#') => ("#", ",", false, "", true),
+            Some(_) => return Err("Unsupported URI template operator"),
+        };
+        Ok(Operator {
+            first,
+            sep,
+            named,
+            ifemp,
+            allow_reserved,
+        })
+    }
+
+    fn encode(&self, value: &str) -> String {
+        if !self.allow_reserved {
+            return percent::encode(value, percent::is_unreserved, false);
+        }
+        // Reserved expansion keeps already pct-encoded triplets as they are
+        let bytes = value.as_bytes();
+        let mut result = String::with_capacity(value.len());
+        let mut idx = 0;
+        while idx < value.len() {
+            let triplet = bytes[idx] == b'%'
+                && bytes.get(idx + 1).is_some_and(u8::is_ascii_hexdigit)
+                && bytes.get(idx + 2).is_some_and(u8::is_ascii_hexdigit);
+            let end = if triplet {
+                idx + 3
+            } else {
+                idx + value[idx..].chars().next().map_or(1, char::len_utf8)
+            };
+            result.push_str(&percent::encode(
+                &value[idx..end],
+                |byte| triplet || percent::is_unreserved(byte) || is_reserved(byte),
+                false,
+            ));
+            idx = end;
+        }
+        result
+    }
+
+    /// Return true if `byte` may appear in an expansion of this operator
+    fn allows(&self, byte: u8) -> bool {
+        percent::is_unreserved(byte)
+            || byte == b'%'
+            || byte == b','
+            || (self.allow_reserved && is_reserved(byte))
+            || (self.named && byte == b'=')
+            || self.first.as_bytes().contains(&byte)
+            || self.sep.as_bytes().contains(&byte)
+    }
+}
+
+/// Variable of an expression: name with optional prefix or explode modifier
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct VarSpec {
+    name: String,
+    prefix: Option<usize>,
+    explode: bool,
+}
+
+impl VarSpec {
+    fn parse(spec: &str) -> Result<VarSpec, &'static str> {
+        let (name, prefix, explode) = if let Some(name) = spec.strip_suffix('*') {
+            (name, None, true)
+        } else if let Some((name, length)) = spec.split_once(':') {
+            let valid = (1..=4).contains(&length.len())
+                && !length.starts_with('0')
+                && length.bytes().all(|byte| byte.is_ascii_digit());
+            match length.parse::<usize>() {
+                Ok(length) if valid => (name, Some(length), false),
+                _ => return Err("Invalid URI template prefix modifier"),
+            }
+        } else {
+            (spec, None, false)
+        };
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && !name.ends_with('.')
+            && !name.contains("..")
+            && percent::decode(name, false).is_ok()
+            && name
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.' | b'%'));
+        if !valid {
+            return Err("Invalid URI template variable name");
+        }
+        Ok(VarSpec {
+            name: name.to_string(),
+            prefix,
+            explode,
+        })
+    }
+}
+
+/// Value of a variable looked up in a `MultiDict`
+enum Variable<'a> {
+    List(Vec<&'a str>),
+    Associative(Vec<(&'a str, &'a str)>),
+}
+
+impl<'a> Variable<'a> {
+    fn lookup(variables: &'a MultiDict, name: &str) -> Variable<'a> {
+        let values: Vec<&str> = variables
+            .iter()
+            .filter(|item| item.key.eq(name))
+            .map(|item| item.value.as_str())
+            .collect();
+        if !values.is_empty() {
+            return Variable::List(values);
+        }
+        let field = |key: &'a str| {
+            key.strip_prefix(name)?
+                .strip_prefix('[')?
+                .strip_suffix(']')
+                .filter(|field| !field.contains(['[', ']']))
+        };
+        Variable::Associative(
+            variables
+                .iter()
+                .filter_map(|item| Some((field(&item.key)?, item.value.as_str())))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Part {
+    Literal(String),
+    Expression(Operator, Vec<VarSpec>),
+}
+
+/// Parsed RFC 6570 URI Template
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UriTemplate {
+    parts: Vec<Part>,
+}
+
+impl UriTemplate {
+    /// Parse URI template, return error if template is malformed
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::uri_template::UriTemplate;
+    ///
+    /// assert!(UriTemplate::new("/search{?q,lang*}").is_ok());
+    /// assert_eq!(UriTemplate::new("/search{?q").unwrap_err(), "Unclosed URI template expression");
+    /// assert_eq!(UriTemplate::new("{=x}").unwrap_err(), "Unsupported URI template operator");
+    /// ```
+    pub fn new(template: &str) -> Result<UriTemplate, &'static str> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while !rest.is_empty() {
+            match rest.find(['{', '}']) {
+                Some(idx) if rest[idx..].starts_with('}') => {
+                    return Err("Unexpected '}' in URI template");
+                }
+                Some(idx) => {
+                    if idx > 0 {
+                        parts.push(Part::Literal(rest[..idx].to_string()));
+                    }
+                    let Some(close) = rest[idx..].find('}') else {
+                        return Err("Unclosed URI template expression");
+                    };
+                    parts.push(Self::parse_expression(&rest[idx + 1..idx + close])?);
+                    rest = &rest[idx + close + 1..];
+                }
+                None => {
+                    parts.push(Part::Literal(rest.to_string()));
+                    rest = "";
+                }
+            }
+        }
+        Ok(UriTemplate { parts })
+    }
+
+    fn parse_expression(expression: &str) -> Result<Part, &'static str> {
+        let operator = expression
+            .chars()
+            .next()
+            .filter(|c| !c.is_ascii_alphanumeric() && *c != '_' && *c != '%');
+        let variables = &expression[operator.map_or(0, char::len_utf8)..];
+        let operator = Operator::from_char(operator)?;
+        let specs = variables
+            .split(',')
+            .map(VarSpec::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Part::Expression(operator, specs))
+    }
+
+    /// Expand template using `variables`.
+    /// Prefix modifier applied to a list or associative variable is an error.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::uri_template::UriTemplate;
+    ///
+    /// let variables = MultiDict::from_query_string("q=rust lang&lang=en&lang=fr").unwrap();
+    /// let template = UriTemplate::new("/search{?q,lang*}").unwrap();
+    /// assert_eq!(template.expand(&variables).unwrap(), "/search?q=rust%20lang&lang=en&lang=fr");
+    ///
+    /// let template = UriTemplate::new("/search{?q,lang}").unwrap();
+    /// assert_eq!(template.expand(&variables).unwrap(), "/search?q=rust%20lang&lang=en,fr");
+    ///
+    /// let template = UriTemplate::new("/search{?lang:1}").unwrap();
+    /// assert_eq!(template.expand(&variables).unwrap_err(), "Prefix modifier is not applicable to list");
+    ///
+    /// let variables = MultiDict::from_query_string("sort[name]=asc&sort[age]=desc").unwrap();
+    /// let template = UriTemplate::new("/users{?sort*}").unwrap();
+    /// assert_eq!(template.expand(&variables).unwrap(), "/users?name=asc&age=desc");
+    /// let template = UriTemplate::new("/users{?sort}").unwrap();
+    /// assert_eq!(template.expand(&variables).unwrap(), "/users?sort=name,asc,age,desc");
+    /// ```
+    pub fn expand(&self, variables: &MultiDict) -> Result<String, &'static str> {
+        let mut result = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => result.push_str(&RESERVED.encode(literal)),
+                Part::Expression(operator, specs) => {
+                    let mut expanded = Vec::new();
+                    for spec in specs {
+                        let variable = Variable::lookup(variables, &spec.name);
+                        if let Some(value) = Self::expand_variable(operator, spec, &variable)? {
+                            expanded.push(value);
+                        }
+                    }
+                    if !expanded.is_empty() {
+                        result.push_str(operator.first);
+                        result.push_str(&expanded.join(operator.sep));
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn expand_variable(
+        operator: &Operator,
+        spec: &VarSpec,
+        variable: &Variable,
+    ) -> Result<Option<String>, &'static str> {
+        let named = |name: &str, value: &str| {
+            if !operator.named {
+                value.to_string()
+            } else if value.is_empty() {
+                format!("{name}{}", operator.ifemp)
+            } else {
+                format!("{name}={value}")
+            }
+        };
+        let join = |items: Vec<String>, sep: &str| items.join(sep);
+        match variable {
+            Variable::List(values) if values.is_empty() => Ok(None),
+            Variable::Associative(pairs) if pairs.is_empty() => Ok(None),
+            Variable::List(values) if values.len() == 1 => {
+                let value = match spec.prefix {
+                    Some(length) => values[0].chars().take(length).collect(),
+                    None => values[0].to_string(),
+                };
+                Ok(Some(named(&spec.name, &operator.encode(&value))))
+            }
+            Variable::List(_) if spec.prefix.is_some() => {
+                Err("Prefix modifier is not applicable to list")
+            }
+            Variable::Associative(_) if spec.prefix.is_some() => {
+                Err("Prefix modifier is not applicable to associative array")
+            }
+            Variable::List(values) if spec.explode => Ok(Some(join(
+                values
+                    .iter()
+                    .map(|value| named(&spec.name, &operator.encode(value)))
+                    .collect(),
+                operator.sep,
+            ))),
+            Variable::List(values) => Ok(Some(named(
+                &spec.name,
+                &join(
+                    values.iter().map(|value| operator.encode(value)).collect(),
+                    ",",
+                ),
+            ))),
+            Variable::Associative(pairs) if spec.explode => Ok(Some(join(
+                pairs
+                    .iter()
+                    .map(|(key, value)| {
+                        let (key, value) = (operator.encode(key), operator.encode(value));
+                        if operator.named {
+                            named(&key, &value)
+                        } else {
+                            format!("{key}={value}")
+                        }
+                    })
+                    .collect(),
+                operator.sep,
+            ))),
+            Variable::Associative(pairs) => Ok(Some(named(
+                &spec.name,
+                &join(
+                    pairs
+                        .iter()
+                        .flat_map(|(key, value)| [operator.encode(key), operator.encode(value)])
+                        .collect(),
+                    ",",
+                ),
+            ))),
+        }
+    }
+
+    /// Match `uri` against template and return extracted variables,
+    /// `None` if `uri` can not be produced by this template.
+    ///
+    /// Matching is the best-effort inverse of expansion: comma separated
+    /// and exploded values become repeated keys, prefix variables get the
+    /// truncated value.
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::uri_template::UriTemplate;
+    ///
+    /// let template = UriTemplate::new("/users/{id}/posts{?page,tag*}").unwrap();
+    /// let variables = template.match_uri("/users/42/posts?page=2&tag=a&tag=b%20c").unwrap();
+    /// println!("{variables}");
+    /// // MultiDict < "id":"42", "page":"2", "tag":"a", "tag":"b c" >
+    /// assert_eq!(variables.keys(), vec!["id", "page", "tag", "tag"]);
+    /// assert_eq!(variables.values(), vec!["42", "2", "a", "b c"]);
+    ///
+    /// assert!(template.match_uri("/users/42/comments").is_none());
+    /// ```
+    ///
+    /// Round trip with [`UriTemplate::expand`]
+    /// ```
+    /// use multidict::MultiDict;
+    /// use multidict::uri_template::UriTemplate;
+    ///
+    /// let template = UriTemplate::new("{/path*}{;x,y}").unwrap();
+    /// let variables = MultiDict::from_query_string("path=a&path=b&x=1&y=2").unwrap();
+    /// let uri = template.expand(&variables).unwrap();
+    /// assert_eq!(uri, "/a/b;x=1;y=2");
+    /// assert_eq!(template.match_uri(&uri).unwrap().values(), variables.values());
+    /// ```
+    pub fn match_uri(&self, uri: &str) -> Option<MultiDict> {
+        let mut results = MultiDict::new();
+        let mut failed = HashSet::new();
+        if self.match_parts(0, uri, &mut results, &mut failed) {
+            Some(results)
+        } else {
+            None
+        }
+    }
+
+    /// Match parts from `index` on against the rest of the URI. Whether a suffix matches
+    /// does not depend on earlier variables, so `failed` remembers every
+    /// `(index, rest length)` that did not match and each is tried once.
+    fn match_parts(
+        &self,
+        index: usize,
+        uri: &str,
+        results: &mut MultiDict,
+        failed: &mut HashSet<(usize, usize)>,
+    ) -> bool {
+        if failed.contains(&(index, uri.len())) {
+            return false;
+        }
+        let matched = match &self.parts[index..] {
+            [] => uri.is_empty(),
+            [Part::Literal(literal), ..] => {
+                match uri.strip_prefix(RESERVED.encode(literal).as_str()) {
+                    Some(uri) => self.match_parts(index + 1, uri, results, failed),
+                    None => false,
+                }
+            }
+            [Part::Expression(operator, specs), ..] => {
+                // Expansion is the longest allowed prefix or any shorter one, try shortest first
+                let limit = uri
+                    .bytes()
+                    .position(|byte| !operator.allows(byte))
+                    .unwrap_or(uri.len());
+                let length = results.len();
+                (0..=limit).filter(|end| uri.is_char_boundary(*end)).any(|end| {
+                    let matched = Self::match_expression(operator, specs, &uri[..end], results)
+                        && self.match_parts(index + 1, &uri[end..], results, failed);
+                    if !matched {
+                        results.elements.truncate(length);
+                    }
+                    matched
+                })
+            }
+        };
+        if !matched {
+            failed.insert((index, uri.len()));
+        }
+        matched
+    }
+
+    fn match_expression(
+        operator: &Operator,
+        specs: &[VarSpec],
+        expansion: &str,
+        results: &mut MultiDict,
+    ) -> bool {
+        if expansion.is_empty() {
+            return true;
+        }
+        let Some(expansion) = expansion.strip_prefix(operator.first) else {
+            return false;
+        };
+        let pieces: Vec<&str> = expansion.split(operator.sep).collect();
+        let mut add = |name: &str, value: &str| match percent::decode(value, false) {
+            Ok(value) => {
+                results.add(MultiElement {
+                    key: name.to_string(),
+                    value,
+                    meta: None,
+                });
+                true
+            }
+            Err(_) => false,
+        };
+
+        if operator.named {
+            for piece in pieces {
+                let (name, value) = piece.split_once('=').unwrap_or((piece, ""));
+                let Some(spec) = specs.iter().find(|spec| spec.name.eq(name)) else {
+                    return false;
+                };
+                let values: Vec<&str> = if spec.explode {
+                    vec![value]
+                } else {
+                    value.split(',').collect()
+                };
+                if !values.into_iter().all(|value| add(name, value)) {
+                    return false;
+                }
+            }
+            return true;
+        }
+
+        let mut pieces = pieces.as_slice();
+        for (idx, spec) in specs.iter().enumerate() {
+            if pieces.is_empty() {
+                break;
+            }
+            let remaining_specs = specs.len() - idx - 1;
+            let take = if spec.explode || (remaining_specs == 0 && operator.sep == ",") {
+                pieces.len().saturating_sub(remaining_specs).max(1)
+            } else {
+                1
+            };
+            let (taken, rest) = pieces.split_at(take);
+            for value in taken.iter().flat_map(|piece| piece.split(',')) {
+                if !add(&spec.name, value) {
+                    return false;
+                }
+            }
+            pieces = rest;
+        }
+        pieces.is_empty()
+    }
+}
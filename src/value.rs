@@ -0,0 +1,121 @@
+//! Value types of `MultiElement` besides `String`.
+//!
+//! `MultiDict<M, V>` stores values of any type `V`. Printing a map needs
+//! [`ElementValue`], which is implemented for strings, byte vectors and optional
+//! values, so binary protocols can keep raw bytes and null values.
+use std::fmt;
+
+use crate::{MultiDict, MultiElement};
+
+/// Value printed by `Display` of `MultiElement` and `MultiDict`
+pub trait ElementValue {
+    /// Write the value as it appears after `"key":`
+    fn fmt_value(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+impl ElementValue for String {
+    /// Quoted string, `"value"`
+    fn fmt_value(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{self}""#)
+    }
+}
+
+impl ElementValue for Vec<u8> {
+    /// Byte string literal, printable ASCII is kept and other bytes are escaped
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    ///
+    /// let mut map: MultiDict<(), Vec<u8>> = MultiDict::default();
+    /// map.add(MultiElement {
+    ///             key: "trace".to_string(),
+    ///             value: vec![b'i', b'd', 0x00, 0xff, b'"'],
+    ///             meta: None,
+    ///         });
+    /// println!("{map}");
+    /// // MultiDict < "trace":b"id\x00\xff\"" >
+    /// assert_eq!(map.to_string(), r#"MultiDict < "trace":b"id\x00\xff\"" >"#);
+    /// ```
+    fn fmt_value(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "b\"")?;
+        for byte in self {
+            match byte {
+                b'"' | b'\\' => write!(f, "\\{}", *byte as char)?,
+                0x20..=0x7e => write!(f, "{}", *byte as char)?,
+                _ => write!(f, "\\x{byte:02x}")?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+impl<V: ElementValue> ElementValue for Option<V> {
+    /// Inner value, `null` for `None`
+    fn fmt_value(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Some(value) => value.fmt_value(f),
+            None => write!(f, "null"),
+        }
+    }
+}
+
+impl<M, V> MultiDict<M, V> {
+    /// Return map with every value converted by `convert`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let map = MultiDict::from_query_string("a=1&b=").unwrap();
+    /// let bytes = map.map_values(|value| (!value.is_empty()).then(|| value.into_bytes()));
+    /// println!("{bytes}");
+    /// // MultiDict < "a":b"1", "b":null >
+    /// ```
+    pub fn map_values<W>(self, mut convert: impl FnMut(V) -> W) -> MultiDict<M, W> {
+        let mut results = MultiDict::default();
+        results.set_redaction(self.redaction().clone());
+        for item in self.elements {
+            results.add(MultiElement {
+                key: item.key,
+                value: convert(item.value),
+                meta: item.meta,
+            });
+        }
+        results
+    }
+
+    /// Return map with every value converted by `convert`, or its first error
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::{MultiDict, MultiElement};
+    ///
+    /// let mut map: MultiDict<(), Vec<u8>> = MultiDict::default();
+    /// map.add(MultiElement {
+    ///             key: "a".to_string(),
+    ///             value: b"text".to_vec(),
+    ///             meta: None,
+    ///         });
+    /// let strings = map.clone().try_map_values(String::from_utf8).unwrap();
+    /// assert_eq!(strings.get("a").unwrap().value, "text");
+    ///
+    /// map.elements[0].value.push(0xff);
+    /// assert!(map.try_map_values(String::from_utf8).is_err());
+    /// ```
+    pub fn try_map_values<W, E>(
+        self,
+        mut convert: impl FnMut(V) -> Result<W, E>,
+    ) -> Result<MultiDict<M, W>, E> {
+        let mut results = MultiDict::default();
+        results.set_redaction(self.redaction().clone());
+        for item in self.elements {
+            results.add(MultiElement {
+                key: item.key,
+                value: convert(item.value)?,
+                meta: item.meta,
+            });
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,287 @@
+//! Set views of keys and items, and multiset operations on `MultiDict`.
+//!
+//! [`KeysView`] and [`ItemsView`] hold every distinct key or `(key, value)` pair once,
+//! in order of first occurrence, and support `&`, `|`, `-` and `^` like Python
+//! dictionary views. Results list elements of the left operand first.
+//!
+//! The same operators on `&MultiDict` work on multisets of `(key, value)` pairs and
+//! respect how many times each pair appears, like Python `Counter`.
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+use crate::MultiDict;
+
+/// Distinct keys of a `MultiDict`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct KeysView<'a> {
+    keys: Vec<&'a str>,
+}
+
+/// Distinct `(key, value)` pairs of a `MultiDict`
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ItemsView<'a> {
+    items: Vec<(&'a str, &'a str)>,
+}
+
+/// Implement set operations and accessors of a view over `Vec<$item>`
+macro_rules! set_view {
+    ($view:ident, $field:ident, $item:ty) => {
+        impl<'a> $view<'a> {
+            /// Return the number of distinct entries
+            pub fn len(&self) -> usize {
+                self.$field.len()
+            }
+
+            /// Return true if the view has no entries
+            pub fn is_empty(&self) -> bool {
+                self.$field.is_empty()
+            }
+
+            /// Return an iterator over entries in order of first occurrence
+            pub fn iter(&self) -> std::slice::Iter<'_, $item> {
+                self.$field.iter()
+            }
+
+            /// Return true if `entry` is in the view
+            pub fn contains(&self, entry: &$item) -> bool {
+                self.$field.contains(entry)
+            }
+
+            /// Return true if every entry is also in `other`
+            pub fn is_subset(&self, other: &$view<'_>) -> bool {
+                self.$field.iter().all(|entry| other.$field.contains(entry))
+            }
+
+            /// Return true if no entry is in `other`
+            pub fn is_disjoint(&self, other: &$view<'_>) -> bool {
+                !self.$field.iter().any(|entry| other.$field.contains(entry))
+            }
+
+            fn from_entries(entries: impl Iterator<Item = $item>) -> Self {
+                let mut $field: Vec<$item> = Vec::new();
+                for entry in entries {
+                    if !$field.contains(&entry) {
+                        $field.push(entry);
+                    }
+                }
+                $view { $field }
+            }
+        }
+
+        impl<'a> BitAnd for &$view<'a> {
+            type Output = $view<'a>;
+
+            /// Entries in both views
+            fn bitand(self, other: Self) -> $view<'a> {
+                $view::from_entries(
+                    self.$field.iter().copied().filter(|entry| other.contains(entry)),
+                )
+            }
+        }
+
+        impl<'a> BitOr for &$view<'a> {
+            type Output = $view<'a>;
+
+            /// Entries in either view
+            fn bitor(self, other: Self) -> $view<'a> {
+                $view::from_entries(self.$field.iter().chain(other.$field.iter()).copied())
+            }
+        }
+
+        impl<'a> Sub for &$view<'a> {
+            type Output = $view<'a>;
+
+            /// Entries in the left view only
+            fn sub(self, other: Self) -> $view<'a> {
+                $view::from_entries(
+                    self.$field
+                        .iter()
+                        .copied()
+                        .filter(|entry| !other.contains(entry)),
+                )
+            }
+        }
+
+        impl<'a> BitXor for &$view<'a> {
+            type Output = $view<'a>;
+
+            /// Entries in exactly one of the views
+            fn bitxor(self, other: Self) -> $view<'a> {
+                $view::from_entries((self - other).$field.into_iter().chain((other - self).$field))
+            }
+        }
+
+        impl<'a> IntoIterator for $view<'a> {
+            type Item = $item;
+            type IntoIter = std::vec::IntoIter<$item>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.$field.into_iter()
+            }
+        }
+    };
+}
+
+set_view!(KeysView, keys, &'a str);
+set_view!(ItemsView, items, (&'a str, &'a str));
+
+impl<M> MultiDict<M> {
+    /// Return set view of distinct keys
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let first = MultiDict::from_query_string("q=rust&page=2&sort=new").unwrap();
+    /// let second = MultiDict::from_query_string("q=go&page=2&lang=en").unwrap();
+    /// let (first, second) = (first.keys_view(), second.keys_view());
+    /// assert_eq!((&first & &second).into_iter().collect::<Vec<_>>(), vec!["q", "page"]);
+    /// assert_eq!((&first | &second).len(), 4);
+    /// assert_eq!((&first - &second).into_iter().collect::<Vec<_>>(), vec!["sort"]);
+    /// assert_eq!((&first ^ &second).into_iter().collect::<Vec<_>>(), vec!["sort", "lang"]);
+    /// ```
+    pub fn keys_view(&self) -> KeysView<'_> {
+        KeysView::from_entries(self.iter().map(|item| item.key.as_str()))
+    }
+
+    /// Return set view of distinct `(key, value)` pairs
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let first = MultiDict::from_query_string("q=rust&page=2&page=2&sort=new").unwrap();
+    /// let second = MultiDict::from_query_string("q=go&page=2&lang=en").unwrap();
+    /// let shared = &first.items_view() & &second.items_view();
+    /// assert_eq!(shared.into_iter().collect::<Vec<_>>(), vec![("page", "2")]);
+    /// assert_eq!(first.items_view().len(), 3);
+    /// ```
+    pub fn items_view(&self) -> ItemsView<'_> {
+        ItemsView::from_entries(self.iter().map(|item| (item.key.as_str(), item.value.as_str())))
+    }
+}
+
+impl<M: Clone> MultiDict<M> {
+    /// Return elements whose `(key, value)` pair appears in `other`, each pair at most
+    /// as many times as in `other`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let first = MultiDict::from_query_string("tag=a&tag=a&tag=b&q=1").unwrap();
+    /// let second = MultiDict::from_query_string("tag=a&tag=b&tag=b&q=2").unwrap();
+    /// assert_eq!(first.intersection(&second).to_query_string(), "tag=a&tag=b");
+    /// assert_eq!((&first & &second).to_query_string(), "tag=a&tag=b");
+    /// ```
+    pub fn intersection(&self, other: &MultiDict<M>) -> MultiDict<M> {
+        let mut remaining = Counts::new(other);
+        self.filtered(|key, value| remaining.take(key, value))
+    }
+
+    /// Return all elements and the elements of `other` whose pair appears there
+    /// more times than here
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let first = MultiDict::from_query_string("tag=a&tag=a&tag=b").unwrap();
+    /// let second = MultiDict::from_query_string("tag=b&tag=b&tag=c").unwrap();
+    /// assert_eq!(first.union(&second).to_query_string(), "tag=a&tag=a&tag=b&tag=b&tag=c");
+    /// ```
+    pub fn union(&self, other: &MultiDict<M>) -> MultiDict<M> {
+        let mut results = self.clone();
+        results.elements.extend(other.difference(self).elements);
+        results
+    }
+
+    /// Return elements left after removing one occurrence of each pair of `other`
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let first = MultiDict::from_query_string("tag=a&tag=a&tag=b").unwrap();
+    /// let second = MultiDict::from_query_string("tag=a&tag=b&tag=c").unwrap();
+    /// assert_eq!(first.difference(&second).to_query_string(), "tag=a");
+    /// ```
+    pub fn difference(&self, other: &MultiDict<M>) -> MultiDict<M> {
+        let mut remaining = Counts::new(other);
+        self.filtered(|key, value| !remaining.take(key, value))
+    }
+
+    /// Return elements of pairs whose count differs, by the count difference
+    ///
+    /// # Examples
+    /// ```
+    /// use multidict::MultiDict;
+    ///
+    /// let first = MultiDict::from_query_string("tag=a&tag=a&tag=b").unwrap();
+    /// let second = MultiDict::from_query_string("tag=a&tag=b&tag=c").unwrap();
+    /// assert_eq!(first.symmetric_difference(&second).to_query_string(), "tag=a&tag=c");
+    /// ```
+    pub fn symmetric_difference(&self, other: &MultiDict<M>) -> MultiDict<M> {
+        let mut results = self.difference(other);
+        results.elements.extend(other.difference(self).elements);
+        results
+    }
+
+    /// Return copy with elements for which `keep` returns true
+    fn filtered(&self, mut keep: impl FnMut(&str, &str) -> bool) -> MultiDict<M> {
+        let mut results = self.clone();
+        results.elements.retain(|item| keep(&item.key, &item.value));
+        results
+    }
+}
+
+/// Remaining occurrences of every `(key, value)` pair of a `MultiDict`
+struct Counts<'a> {
+    counts: Vec<((&'a str, &'a str), usize)>,
+}
+
+impl<'a> Counts<'a> {
+    fn new<M>(map: &'a MultiDict<M>) -> Self {
+        let mut counts: Vec<((&str, &str), usize)> = Vec::new();
+        for item in map.iter() {
+            let pair = (item.key.as_str(), item.value.as_str());
+            match counts.iter_mut().find(|(known, _)| *known == pair) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((pair, 1)),
+            }
+        }
+        Counts { counts }
+    }
+
+    /// Use up one occurrence of the pair, return false if none is left
+    fn take(&mut self, key: &str, value: &str) -> bool {
+        match self
+            .counts
+            .iter_mut()
+            .find(|(pair, count)| *pair == (key, value) && *count > 0)
+        {
+            Some((_, count)) => {
+                *count -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Implement a multiset operator on `&MultiDict` by the named method
+macro_rules! multiset_operator {
+    ($trait:ident, $operator:ident, $method:ident) => {
+        impl<M: Clone> $trait for &MultiDict<M> {
+            type Output = MultiDict<M>;
+
+            fn $operator(self, other: Self) -> MultiDict<M> {
+                self.$method(other)
+            }
+        }
+    };
+}
+
+multiset_operator!(BitAnd, bitand, intersection);
+multiset_operator!(BitOr, bitor, union);
+multiset_operator!(Sub, sub, difference);
+multiset_operator!(BitXor, bitxor, symmetric_difference);
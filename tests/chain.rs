@@ -0,0 +1,106 @@
+//! Configuration overlay built from defaults, file config, environment and CLI flags.
+use multidict::chain::{ChainLookup, ChainMultiDict};
+use multidict::metadata::Metadata;
+use multidict::{MultiDict, MultiElement};
+
+fn layer(query: &str, origin: &str) -> MultiDict<Metadata> {
+    let mut map = MultiDict::default();
+    for item in MultiDict::from_query_string(query).unwrap().elements {
+        map.add(MultiElement {
+            key: item.key,
+            value: item.value,
+            meta: Some(Metadata {
+                origin: Some(origin.to_string()),
+                ..Default::default()
+            }),
+        });
+    }
+    map
+}
+
+fn overlay() -> ChainMultiDict<Metadata> {
+    let mut chain = ChainMultiDict::from_layers(vec![layer(
+        "host=localhost&port=80&include=base.conf",
+        "defaults",
+    )]);
+    chain.push_layer(layer(
+        "port=8080&include=site.conf&include=extra.conf",
+        "file",
+    ));
+    chain.push_layer(layer("log=debug", "environment"));
+    chain.push_layer(layer("port=9000", "cli"));
+    chain
+}
+
+fn origin(item: &MultiElement<Metadata>) -> &str {
+    item.meta.as_ref().unwrap().origin.as_deref().unwrap()
+}
+
+#[test]
+fn lookups_resolve_through_layers() {
+    let chain = overlay();
+    assert_eq!(chain.layers().len(), 4);
+    assert_eq!(chain.len(), 8);
+
+    let port = chain.get("port").unwrap();
+    assert_eq!((port.value.as_str(), origin(port)), ("9000", "cli"));
+    assert_eq!(origin(chain.get("host").unwrap()), "defaults");
+    assert!(chain.contains("log"));
+    assert!(!chain.contains("user"));
+
+    let merged = chain.getall("include", ChainLookup::Merge).unwrap();
+    assert_eq!(
+        merged.values(),
+        vec!["site.conf", "extra.conf", "base.conf"]
+    );
+    let top = chain.getall("include", ChainLookup::TopLayer).unwrap();
+    assert_eq!(top.values(), vec!["site.conf", "extra.conf"]);
+    assert!(top.iter().all(|item| origin(item) == "file"));
+}
+
+#[test]
+fn writes_go_to_top_layer() {
+    let mut chain = overlay();
+    chain.add(MultiElement {
+        key: "include".to_string(),
+        value: "cli.conf".to_string(),
+        meta: None,
+    });
+    chain.update(MultiElement {
+        key: "log".to_string(),
+        value: "trace".to_string(),
+        meta: None,
+    });
+    assert_eq!(chain.top().keys(), vec!["port", "include", "log"]);
+    assert_eq!(chain.get("log").unwrap().value, "trace");
+    assert_eq!(chain.layers()[1].get("log").unwrap().value, "debug");
+
+    assert_eq!(chain.popone("port").unwrap().value, "9000");
+    assert_eq!(chain.popone("port").unwrap_err(), "No matching key found");
+    assert_eq!(chain.get("port").unwrap().value, "8080");
+
+    let cli = chain.pop_layer().unwrap();
+    assert_eq!(cli.keys(), vec!["include", "log"]);
+    assert_eq!(chain.get("log").unwrap().value, "debug");
+}
+
+#[test]
+fn flatten_matches_lookups() {
+    let chain = overlay();
+    for lookup in [ChainLookup::Merge, ChainLookup::TopLayer] {
+        let flat = chain.flatten(lookup);
+        for key in ["host", "port", "include", "log"] {
+            assert_eq!(flat.get(key).unwrap(), chain.get(key).unwrap());
+            assert_eq!(
+                flat.getall(key).unwrap().to_string(),
+                chain.getall(key, lookup).unwrap().to_string()
+            );
+        }
+    }
+    let flat = chain.flatten(ChainLookup::TopLayer);
+    assert_eq!(
+        flat.without_meta().to_query_string(),
+        "port=9000&log=debug&include=site.conf&include=extra.conf&host=localhost"
+    );
+    assert_eq!(chain.flatten(ChainLookup::Merge).len(), chain.len());
+}
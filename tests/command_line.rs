@@ -0,0 +1,216 @@
+//! Parsing of browser "Copy as cURL" exports and curl round trips.
+use multidict::command_line::HttpCommand;
+use multidict::metadata::Span;
+use multidict::MultiDict;
+
+#[test]
+fn chrome_copy_as_curl_bash() {
+    let command = r#"curl 'https://example.com/api/search?q=caf%C3%A9&page=2' \
+  -H 'accept: application/json' \
+  -H 'accept-language: en-US,en;q=0.9' \
+  -H $'cookie: theme=dark; note=it\'s' \
+  -H 'content-type: application/x-www-form-urlencoded; charset=UTF-8' \
+  -H 'user-agent: Mozilla/5.0 (X11; Linux x86_64)' \
+  --data-raw 'tags=a&tags=b&msg=hello+world%21'"#;
+    let parsed = HttpCommand::from_curl(command).unwrap();
+    assert_eq!(parsed.method, "POST");
+    assert_eq!(parsed.url, "https://example.com/api/search");
+    assert_eq!(
+        parsed.query.unwrap().to_string(),
+        [("q", "caf\u{e9}"), ("page", "2")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(parsed.headers.len(), 5);
+    assert_eq!(
+        parsed.headers.get("cookie").unwrap().value,
+        "theme=dark; note=it's"
+    );
+    assert_eq!(
+        parsed.form.unwrap().to_string(),
+        [("tags", "a"), ("tags", "b"), ("msg", "hello world!")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+}
+
+#[test]
+fn firefox_copy_as_curl_posix() {
+    let command = "curl 'https://example.com/logout' -X DELETE \
+                   -H 'Accept: */*' -H 'Referer: https://example.com/' \
+                   -H 'Connection: keep-alive' --compressed";
+    let parsed = HttpCommand::from_curl(command).unwrap();
+    assert_eq!(parsed.method, "DELETE");
+    assert!(parsed.query.is_none());
+    assert!(parsed.form.is_none());
+    assert_eq!(
+        parsed.headers.keys(),
+        vec!["Accept", "Referer", "Connection"]
+    );
+}
+
+#[test]
+fn options_mapped_to_headers_and_query() {
+    let parsed = HttpCommand::from_curl(
+        "curl -G -u user:secret -A agent/1.0 -e https://ref.example -b 'a=1; b=2' \
+         --data-urlencode 'q=a b' -d lang=en \"https://example.com/find?x=1\"",
+    )
+    .unwrap();
+    assert_eq!(parsed.method, "GET");
+    assert!(parsed.form.is_none());
+    assert_eq!(
+        parsed.query.unwrap().to_string(),
+        [("x", "1"), ("q", "a b"), ("lang", "en")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(
+        parsed.headers.to_string(),
+        [
+            ("Authorization", "Basic dXNlcjpzZWNyZXQ="),
+            ("User-Agent", "agent/1.0"),
+            ("Referer", "https://ref.example"),
+            ("Cookie", "a=1; b=2"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+
+    let head = HttpCommand::from_curl("curl -sSI https://example.com").unwrap();
+    assert_eq!(head.method, "HEAD");
+    let joined =
+        HttpCommand::from_curl("curl -XPATCH -HAccept:text/plain https://example.com").unwrap();
+    assert_eq!(joined.method, "PATCH");
+    assert_eq!(joined.headers.get("Accept").unwrap().value, "text/plain");
+}
+
+#[test]
+fn curl_round_trip() {
+    let mut command = HttpCommand::new(
+        "PUT",
+        "https://example.com/items/1",
+        [
+            ("X-Trace", "a"),
+            ("X-Trace", "b"),
+            ("X-Empty", ""),
+            ("X-Quote", "'single' \"double\" $HOME `cmd`"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>(),
+    );
+    command.query = Some(
+        [("v", "1"), ("v", "2"), ("sp ace", "x&y")]
+            .into_iter()
+            .collect::<MultiDict>(),
+    );
+    command.form = Some(
+        [("name", "caf\u{e9}"), ("name", "line\nbreak")]
+            .into_iter()
+            .collect::<MultiDict>(),
+    );
+
+    let rendered = command.to_curl();
+    let parsed = HttpCommand::from_curl(&rendered).unwrap();
+    assert_eq!(parsed.method, "PUT");
+    assert_eq!(parsed.url, command.url);
+    assert_eq!(parsed.headers.to_string(), command.headers.to_string());
+    assert_eq!(
+        parsed.query.unwrap().to_string(),
+        command.query.unwrap().to_string()
+    );
+    assert_eq!(
+        parsed.form.unwrap().to_string(),
+        command.form.unwrap().to_string()
+    );
+}
+
+#[test]
+fn httpie_quoting() {
+    let mut command = HttpCommand::new(
+        "get",
+        "https://example.com/",
+        [("Accept", "=json"), ("X:Odd", "it's")]
+            .into_iter()
+            .collect::<MultiDict>(),
+    );
+    command.query = Some([("a=b", "c d")].into_iter().collect::<MultiDict>());
+    assert_eq!(
+        command.to_httpie(),
+        r#"http GET https://example.com/ 'Accept:\=json' 'X\:Odd:it'\''s' 'a\=b==c d'"#
+    );
+}
+
+#[test]
+fn invalid_commands() {
+    let cases = [
+        (
+            "wget https://example.com",
+            "Command line must start with curl",
+        ),
+        ("curl", "Missing URL in curl command"),
+        ("curl 'https://example.com", "Unterminated single quote"),
+        (
+            "curl https://a.example https://b.example",
+            "Multiple URLs are not supported",
+        ),
+        (
+            "curl -d @body.txt https://example.com",
+            "Reading data from files is not supported",
+        ),
+        (
+            "curl --data-urlencode a@file https://example.com",
+            "Reading data from files is not supported",
+        ),
+        (
+            "curl --tlsv1.3 https://example.com",
+            "Unsupported curl option",
+        ),
+        ("curl -H", "Missing curl option argument"),
+        (
+            "curl -H 'Content-Type: application/json' -d '{}' https://example.com",
+            "Only form encoded bodies are supported",
+        ),
+    ];
+    for (command, error) in cases {
+        assert_eq!(
+            HttpCommand::from_curl(command).unwrap_err(),
+            error,
+            "{command}"
+        );
+    }
+}
+
+#[test]
+fn spans_of_words() {
+    let command = "curl -H 'Accept: */*' -H $'Max-Forwards: \\'ten\\'' \\\n  \
+                   'https://example.com/?page=2&page=x' -G -d 'a=1&b=2' --data-urlencode c=d";
+    let parsed: HttpCommand<Span> = HttpCommand::from_curl_spanned(command).unwrap();
+    let header = parsed.headers.get("Max-Forwards").unwrap();
+    // the value is not a number, its span points at the word holding it
+    assert!(header.value.parse::<u32>().is_err());
+    assert_eq!(header.meta, Some(Span { start: 25, end: 49 }));
+    assert_eq!(
+        header.meta.unwrap().slice(command),
+        "$'Max-Forwards: \\'ten\\''"
+    );
+    let query = parsed.query.unwrap();
+    let spans: Vec<&str> = query
+        .iter()
+        .map(|item| item.meta.unwrap().slice(command))
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            "'https://example.com/?page=2&page=x'",
+            "'https://example.com/?page=2&page=x'",
+            "'a=1&b=2'",
+            "'a=1&b=2'",
+            "c=d",
+        ]
+    );
+    assert!(HttpCommand::<Span>::from_curl_spanned("curl -H").is_err());
+}
@@ -0,0 +1,216 @@
+//! Small JSON reader for the vendored test fixtures.
+//!
+//! Numbers are kept as written, object members in document order.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parse a whole JSON document, panics on malformed input
+    pub fn parse(input: &str) -> Json {
+        let mut parser = Parser {
+            input: input.as_bytes(),
+            position: 0,
+        };
+        let value = parser.value();
+        parser.whitespace();
+        assert_eq!(
+            parser.position,
+            input.len(),
+            "trailing data after JSON value"
+        );
+        value
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Return strings unquoted and other scalars as written
+    pub fn scalar(&self) -> String {
+        match self {
+            Json::String(value) => value.clone(),
+            Json::Number(text) => text.clone(),
+            Json::Bool(value) => value.to_string(),
+            Json::Null => "null".to_string(),
+            other => panic!("not a scalar: {other:?}"),
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn whitespace(&mut self) {
+        while self
+            .input
+            .get(self.position)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.position += 1;
+        }
+    }
+
+    fn next(&mut self) -> u8 {
+        let byte = *self.input.get(self.position).expect("truncated JSON");
+        self.position += 1;
+        byte
+    }
+
+    fn expect(&mut self, text: &str) {
+        assert!(
+            self.input[self.position..].starts_with(text.as_bytes()),
+            "expected {text} at {}",
+            self.position
+        );
+        self.position += text.len();
+    }
+
+    fn value(&mut self) -> Json {
+        self.whitespace();
+        match self.input.get(self.position).expect("truncated JSON") {
+            b'n' => {
+                self.expect("null");
+                Json::Null
+            }
+            b't' => {
+                self.expect("true");
+                Json::Bool(true)
+            }
+            b'f' => {
+                self.expect("false");
+                Json::Bool(false)
+            }
+            b'"' => Json::String(self.string()),
+            b'[' => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.input[self.position] == b']' {
+                    self.position += 1;
+                    return Json::Array(items);
+                }
+                loop {
+                    items.push(self.value());
+                    self.whitespace();
+                    match self.next() {
+                        b',' => continue,
+                        b']' => return Json::Array(items),
+                        other => panic!("unexpected {} in array", other as char),
+                    }
+                }
+            }
+            b'{' => {
+                self.position += 1;
+                let mut members = Vec::new();
+                self.whitespace();
+                if self.input[self.position] == b'}' {
+                    self.position += 1;
+                    return Json::Object(members);
+                }
+                loop {
+                    self.whitespace();
+                    let name = self.string();
+                    self.whitespace();
+                    self.expect(":");
+                    members.push((name, self.value()));
+                    self.whitespace();
+                    match self.next() {
+                        b',' => continue,
+                        b'}' => return Json::Object(members),
+                        other => panic!("unexpected {} in object", other as char),
+                    }
+                }
+            }
+            _ => {
+                let start = self.position;
+                while self
+                    .input
+                    .get(self.position)
+                    .is_some_and(|byte| b"+-.0123456789eE".contains(byte))
+                {
+                    self.position += 1;
+                }
+                assert!(self.position > start, "unexpected JSON at {start}");
+                let text = std::str::from_utf8(&self.input[start..self.position]).unwrap();
+                Json::Number(text.to_string())
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> u32 {
+        let digits = std::str::from_utf8(&self.input[self.position..self.position + 4]).unwrap();
+        self.position += 4;
+        u32::from_str_radix(digits, 16).expect("invalid \\u escape")
+    }
+
+    fn string(&mut self) -> String {
+        self.expect("\"");
+        let mut bytes = Vec::new();
+        loop {
+            match self.next() {
+                b'"' => return String::from_utf8(bytes).expect("JSON string is not UTF-8"),
+                b'\\' => {
+                    let escaped = match self.next() {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex4();
+                            if (0xd800..0xdc00).contains(&code) {
+                                self.expect("\\u");
+                                code = 0x10000 + ((code - 0xd800) << 10) + (self.hex4() - 0xdc00);
+                            }
+                            char::from_u32(code).expect("invalid \\u escape")
+                        }
+                        other => panic!("invalid escape \\{}", other as char),
+                    };
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+    }
+}
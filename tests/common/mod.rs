@@ -0,0 +1,32 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+pub mod json;
+
+/// Parse hex digits, whitespace is ignored
+pub fn bytes(hex: &str) -> Vec<u8> {
+    let digits: Vec<u8> = hex.bytes().filter(|byte| !byte.is_ascii_whitespace()).collect();
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+        .collect()
+}
+
+/// Decode standard base64 with padding
+pub fn base64(text: &str) -> Vec<u8> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let sextets: Vec<u32> = text
+        .bytes()
+        .filter(|byte| *byte != b'=')
+        .map(|byte| ALPHABET.iter().position(|known| *known == byte).unwrap() as u32)
+        .collect();
+    let mut decoded = Vec::new();
+    for chunk in sextets.chunks(4) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (idx, sextet)| bits | sextet << (18 - 6 * idx));
+        decoded.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+    decoded
+}
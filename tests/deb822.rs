@@ -0,0 +1,109 @@
+//! Debian control file stanzas with continuation lines.
+use multidict::deb822::{parse_stanzas, parse_stanzas_spanned, write_stanzas};
+use multidict::metadata::Span;
+use multidict::MultiDict;
+
+const CONTROL: &str = "# debian/control
+Source: hello
+Section: devel
+Priority: optional
+Maintainer: Jane Doe <jane@example.com>
+Build-Depends: debhelper-compat (= 13),
+               libgreeting-dev
+
+Package: hello
+Architecture: any
+Depends: ${shlibs:Depends}, ${misc:Depends}
+Description: example package
+ This package prints a greeting.
+ .
+ It is only an example.
+
+
+Package: hello-doc
+Architecture: all
+Description: documentation for hello
+";
+
+#[test]
+fn control_stanzas() {
+    let stanzas = parse_stanzas(CONTROL).unwrap();
+    assert_eq!(stanzas.len(), 3);
+    assert_eq!(
+        stanzas[0].get("Build-Depends").unwrap().value,
+        "debhelper-compat (= 13),\n              libgreeting-dev"
+    );
+    assert_eq!(
+        stanzas[1].to_string(),
+        [
+            ("Package", "hello"),
+            ("Architecture", "any"),
+            ("Depends", "${shlibs:Depends}, ${misc:Depends}"),
+            (
+                "Description",
+                "example package\nThis package prints a greeting.\n\nIt is only an example."
+            ),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    assert_eq!(stanzas[2].get("Package").unwrap().value, "hello-doc");
+
+    let written = write_stanzas(&stanzas).unwrap();
+    assert!(
+        written.contains("Description: example package\n This package prints a greeting.\n .\n")
+    );
+    let reparsed = parse_stanzas(&written).unwrap();
+    assert_eq!(reparsed.len(), stanzas.len());
+    for (reparsed, stanza) in reparsed.iter().zip(&stanzas) {
+        assert_eq!(reparsed.to_string(), stanza.to_string());
+    }
+}
+
+#[test]
+fn control_duplicates_and_errors() {
+    let stanzas = parse_stanzas("Package: a\r\nTag: x\r\nTag: y\r\n").unwrap();
+    assert_eq!(stanzas[0].getall("Tag").unwrap().len(), 2);
+    assert_eq!(
+        stanzas[0].to_deb822().unwrap(),
+        "Package: a\nTag: x\nTag: y\n"
+    );
+    for (input, error) in [
+        (" orphan\n", "Control continuation line outside a field"),
+        ("Package a\n", "Control field has no colon"),
+        ("Bad Name: x\n", "Invalid control field name"),
+        ("-Field: x\n", "Invalid control field name"),
+    ] {
+        assert_eq!(parse_stanzas(input).unwrap_err(), error);
+    }
+    assert!(parse_stanzas("\n# only a comment\n\n").unwrap().is_empty());
+}
+
+#[test]
+fn spans_of_fields() {
+    let control = "Source: hello\n\nPackage: hello\n# built for every arch\n\
+                   Architecture: any\nDepends: libc6,\n libgreeting1\nPriority: urgent\n";
+    let stanzas: Vec<MultiDict<Span>> = parse_stanzas_spanned(control).unwrap();
+    assert_eq!(stanzas.len(), 2);
+    let invalid = stanzas[1]
+        .iter()
+        .find(|item| {
+            item.key == "Priority"
+                && !["required", "important", "standard", "optional", "extra"]
+                    .contains(&item.value.as_str())
+        })
+        .unwrap();
+    assert_eq!(
+        invalid.meta,
+        Some(Span {
+            start: 101,
+            end: 117
+        })
+    );
+    assert_eq!(invalid.meta.unwrap().slice(control), "Priority: urgent");
+    assert_eq!(
+        stanzas[1].get("Depends").unwrap().meta.unwrap().slice(control),
+        "Depends: libc6,\n libgreeting1"
+    );
+}
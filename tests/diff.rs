@@ -0,0 +1,193 @@
+//! Diff, patch and unified diff of header rewrites.
+use multidict::diff::{diff, unified_diff, Change, Patch};
+use multidict::redaction::RedactionPolicy;
+use multidict::MultiDict;
+
+fn incoming() -> MultiDict {
+    [
+        ("Host", "internal:8080"),
+        ("Accept", "*/*"),
+        ("Cookie", "a=1"),
+        ("Cookie", "b=2"),
+        ("X-Debug", "1"),
+        ("Authorization", "Bearer abc"),
+        ("User-Agent", "curl/8.0"),
+    ]
+    .into_iter()
+    .collect::<MultiDict>()
+}
+
+fn outgoing() -> MultiDict {
+    [
+        ("Host", "api.example.com"),
+        ("Accept", "*/*"),
+        ("Cookie", "b=2"),
+        ("Authorization", "Bearer abc"),
+        ("User-Agent", "curl/8.0"),
+        ("Via", "1.1 proxy"),
+        ("X-Forwarded-For", "192.0.2.1"),
+    ]
+    .into_iter()
+    .collect::<MultiDict>()
+}
+
+#[test]
+fn proxy_rewrite() {
+    let (a, b) = (incoming(), outgoing());
+    let patch = diff(&a, &b);
+    let element =
+        |index: usize, key: &str, value: &str| (index, key.to_string(), value.to_string());
+    let mut summary = Vec::new();
+    for change in &patch.changes {
+        summary.push(match change {
+            Change::Insert { index, key, value } => ("insert", element(*index, key, value)),
+            Change::Remove { index, key, value } => ("remove", element(*index, key, value)),
+            Change::Modify {
+                index,
+                key,
+                new_value,
+                ..
+            } => ("modify", element(*index, key, new_value)),
+        });
+    }
+    assert_eq!(
+        summary,
+        vec![
+            ("modify", element(0, "Host", "api.example.com")),
+            ("remove", element(2, "Cookie", "a=1")),
+            ("remove", element(3, "X-Debug", "1")),
+            ("insert", element(5, "Via", "1.1 proxy")),
+            ("insert", element(6, "X-Forwarded-For", "192.0.2.1")),
+        ]
+    );
+
+    let mut map = a.clone();
+    map.apply(&patch).unwrap();
+    assert_eq!(map.to_string(), b.to_string());
+
+    let restored = Patch::from_json(&patch.to_json()).unwrap();
+    assert_eq!(restored, patch);
+    assert!(diff(&b, &b).changes.is_empty());
+}
+
+#[test]
+fn unified_output() {
+    let (mut a, mut b) = (incoming(), outgoing());
+    a.set_redaction(RedactionPolicy::sensitive_headers());
+    b.set_redaction(RedactionPolicy::sensitive_headers());
+    // changes two unchanged elements apart share a hunk
+    assert_eq!(
+        unified_diff(&a, &b, 1),
+        "--- a\n+++ b\n\
+         @@ -1,7 +1,7 @@\n\
+         -Host: internal:8080\n\
+         +Host: api.example.com\n \
+         Accept: */*\n\
+         -Cookie: ***\n \
+         Cookie: ***\n\
+         -X-Debug: 1\n \
+         Authorization: ***\n \
+         User-Agent: curl/8.0\n\
+         +Via: 1.1 proxy\n\
+         +X-Forwarded-For: 192.0.2.1\n"
+    );
+    assert_eq!(
+        unified_diff(&a, &b, 0),
+        "--- a\n+++ b\n\
+         @@ -1,1 +1,1 @@\n\
+         -Host: internal:8080\n\
+         +Host: api.example.com\n\
+         @@ -3,1 +2,0 @@\n\
+         -Cookie: ***\n\
+         @@ -5,1 +3,0 @@\n\
+         -X-Debug: 1\n\
+         @@ -7,0 +6,2 @@\n\
+         +Via: 1.1 proxy\n\
+         +X-Forwarded-For: 192.0.2.1\n"
+    );
+    assert_eq!(
+        unified_diff(
+            &MultiDict::new(),
+            &[("A", "1")].into_iter().collect::<MultiDict>(),
+            3
+        ),
+        "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+A: 1\n"
+    );
+}
+
+#[test]
+fn redacted_json() {
+    let (mut a, mut b) = (incoming(), outgoing());
+    a.set_redaction(RedactionPolicy::sensitive_headers());
+    b.set_redaction(RedactionPolicy::sensitive_headers());
+    let json = diff(&a, &b).to_json();
+    assert!(!json.contains("a=1"), "{json}");
+    assert!(json.contains(r#"{"op":"remove","path":"/2","old":{"key":"Cookie","value":"***"}}"#));
+    assert!(json.contains(r#"{"op":"remove","path":"/3","old":{"key":"X-Debug","value":"1"}}"#));
+    // without a policy the patch still carries real values
+    assert!(diff(&incoming(), &outgoing()).to_json().contains("a=1"));
+
+    let mut a = [("Authorization", "Bearer abc"), ("Accept", "*/*")]
+        .into_iter()
+        .collect::<MultiDict>();
+    let mut b = [("Authorization", "Bearer xyz"), ("Accept", "*/*")]
+        .into_iter()
+        .collect::<MultiDict>();
+    a.set_redaction(RedactionPolicy::sensitive_headers());
+    b.set_redaction(RedactionPolicy::sensitive_headers());
+    assert_eq!(
+        diff(&a, &b).to_json(),
+        r#"[{"op":"replace","path":"/0/value","value":"***","old":{"key":"Authorization","value":"***"}}]"#
+    );
+}
+
+#[test]
+fn apply_reproduces_target() {
+    // deterministic pseudo random edits of duplicate-heavy maps
+    let mut seed: u32 = 7;
+    let mut next = |bound: u32| {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        (seed >> 16) % bound
+    };
+    for _ in 0..200 {
+        let mut random = || {
+            (0..next(8))
+                .map(|_| (format!("k{}", next(3)), format!("v{}", next(3))))
+                .collect::<MultiDict>()
+        };
+        let (a, b) = (random(), random());
+        let patch = diff(&a, &b);
+        let mut map = a.clone();
+        map.apply(&Patch::from_json(&patch.to_json()).unwrap()).unwrap();
+        assert_eq!(map.to_string(), b.to_string());
+    }
+}
+
+#[test]
+fn invalid_patches() {
+    let mut map = [("A", "1")].into_iter().collect::<MultiDict>();
+    let stale = diff(
+        &[("A", "2")].into_iter().collect::<MultiDict>(),
+        &MultiDict::new(),
+    );
+    assert_eq!(
+        map.apply(&stale).unwrap_err(),
+        "Patch does not match MultiDict"
+    );
+    assert_eq!(
+        Patch::from_json(r#"[{"op":"add","path":"/01","value":{"key":"a","value":"1"}}]"#)
+            .unwrap_err(),
+        "Invalid patch path"
+    );
+    assert_eq!(
+        Patch::from_json(r#"[{"op":"move","from":"/0","path":"/1"}]"#).unwrap_err(),
+        "Unsupported patch operation"
+    );
+    let far =
+        Patch::from_json(r#"[{"op":"add","path":"/5","value":{"key":"a","value":"1"}}]"#).unwrap();
+    assert_eq!(map.apply(&far).unwrap_err(), "Patch index out of range");
+    assert_eq!(
+        map.to_string(),
+        [("A", "1")].into_iter().collect::<MultiDict>().to_string()
+    );
+}
@@ -0,0 +1,143 @@
+//! gRPC metadata against HTTP/2 header lists and HPACK blocks.
+use multidict::grpc::{is_binary_key, is_reserved, GrpcMetadata, MetadataValue};
+use multidict::hpack::{Decoder, Encoder};
+use multidict::MultiDict;
+
+#[test]
+fn reserved_keys() {
+    for key in [
+        ":authority",
+        ":path",
+        "grpc-timeout",
+        "Grpc-Encoding",
+        "content-type",
+        "TE",
+    ] {
+        assert!(is_reserved(key), "{key}");
+        let mut metadata = GrpcMetadata::new();
+        assert_eq!(
+            metadata.add_ascii(key, "x"),
+            Err("Reserved gRPC metadata key")
+        );
+    }
+    assert!(!is_reserved("grpcx"));
+    assert!(is_binary_key("Trace-BIN"));
+    assert!(!is_binary_key("binary"));
+}
+
+#[test]
+fn invalid_entries() {
+    let mut metadata = GrpcMetadata::new();
+    assert_eq!(
+        metadata.add_ascii("bad key", "x"),
+        Err("Invalid gRPC metadata key")
+    );
+    assert_eq!(
+        metadata.add_ascii("", "x"),
+        Err("Invalid gRPC metadata key")
+    );
+    assert_eq!(
+        metadata.add_ascii("note", "line\nbreak"),
+        Err("gRPC metadata value is not printable ASCII")
+    );
+    assert_eq!(
+        metadata.add_ascii("note", "caf\u{e9}"),
+        Err("gRPC metadata value is not printable ASCII")
+    );
+    assert_eq!(
+        metadata.add_binary("note", b"\x00"),
+        Err("Binary gRPC metadata value needs a -bin key")
+    );
+    assert!(metadata.is_empty());
+}
+
+#[test]
+fn case_insensitive_lookup() {
+    let mut metadata = GrpcMetadata::new();
+    metadata.add_ascii("X-Tenant", "a").unwrap();
+    metadata.add_binary("Trace-Bin", b"\xff").unwrap();
+    metadata.add_ascii("x-tenant", "b").unwrap();
+    assert_eq!(
+        metadata.to_string(),
+        r#"MultiDict < "x-tenant":"a", "trace-bin":b"\xff", "x-tenant":"b" >"#
+    );
+    assert_eq!(
+        metadata.getall("X-TENANT"),
+        vec![
+            &MetadataValue::Ascii("a".to_string()),
+            &MetadataValue::Ascii("b".to_string())
+        ]
+    );
+    assert_eq!(
+        metadata.get("TRACE-BIN"),
+        Some(&MetadataValue::Binary(vec![0xff]))
+    );
+    assert!(metadata.remove("X-Tenant"));
+    assert!(!metadata.remove("x-tenant"));
+    assert_eq!(metadata.len(), 1);
+}
+
+#[test]
+fn binary_values_are_unpadded_base64() {
+    let mut metadata = GrpcMetadata::new();
+    for value in [&b""[..], b"a", b"ab", b"abc", b"\x00\xff\xfe\xfd"] {
+        metadata.add_binary("data-bin", value).unwrap();
+    }
+    assert_eq!(
+        metadata.to_headers().to_string(),
+        [
+            ("data-bin", ""),
+            ("data-bin", "YQ"),
+            ("data-bin", "YWI"),
+            ("data-bin", "YWJj"),
+            ("data-bin", "AP/+/Q"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    let decoded = GrpcMetadata::from_headers(&metadata.to_headers()).unwrap();
+    assert_eq!(decoded.to_string(), metadata.to_string());
+}
+
+#[test]
+fn from_headers_errors() {
+    assert_eq!(
+        GrpcMetadata::from_headers(&[("data-bin", "a*b")].into_iter().collect::<MultiDict>())
+            .unwrap_err(),
+        "Invalid base64 in binary gRPC metadata"
+    );
+    assert_eq!(
+        GrpcMetadata::from_headers(&[("Bad Key", "x")].into_iter().collect::<MultiDict>())
+            .unwrap_err(),
+        "Invalid gRPC metadata key"
+    );
+}
+
+#[test]
+fn hpack_round_trip() {
+    let mut metadata = GrpcMetadata::new();
+    metadata.add_ascii("authorization", "Bearer token").unwrap();
+    metadata.add_binary("trace-bin", &[0, 1, 2, 3, 4]).unwrap();
+
+    let mut request = [
+        (":method", "POST"),
+        (":path", "/helloworld.Greeter/SayHello"),
+        ("content-type", "application/grpc"),
+        ("te", "trailers"),
+        ("grpc-timeout", "1S"),
+    ]
+    .into_iter()
+    .collect::<MultiDict>();
+    for item in metadata.to_headers().iter() {
+        request.add(item.clone());
+    }
+
+    let block = Encoder::default().encode(&request);
+    let decoded = Decoder::default().decode(&block).unwrap();
+    assert_eq!(decoded.to_string(), request.to_string());
+    assert_eq!(
+        GrpcMetadata::from_headers(&decoded).unwrap().to_string(),
+        metadata.to_string()
+    );
+}
@@ -0,0 +1,212 @@
+//! HAR import of a browser style export and export round trips.
+use multidict::har::{Har, HarEntry, HarPostData, HarRequest, HarResponse};
+use multidict::metadata::Span;
+use multidict::{MultiDict, MultiElement};
+
+const EXPORT: &str = r#"{
+  "log": {
+    "version": "1.2",
+    "creator": {"name": "WebInspector", "version": "537.36"},
+    "pages": [{"startedDateTime": "2024-05-01T10:00:00.000Z", "id": "page_1",
+               "title": "https://example.com/", "pageTimings": {}}],
+    "entries": [
+      {
+        "pageref": "page_1",
+        "startedDateTime": "2024-05-01T10:00:00.123Z",
+        "time": 85.25,
+        "request": {
+          "method": "GET",
+          "url": "https://example.com/search?q=caf%C3%A9&tag=a&tag=b",
+          "httpVersion": "http/2.0",
+          "headers": [
+            {"name": ":authority", "value": "example.com"},
+            {"name": "accept", "value": "text/html"},
+            {"name": "cookie", "value": "id=1"},
+            {"name": "cookie", "value": "theme=\"dark\""}
+          ],
+          "queryString": [
+            {"name": "q", "value": "caf\u00e9"},
+            {"name": "tag", "value": "a"},
+            {"name": "tag", "value": "b"}
+          ],
+          "cookies": [
+            {"name": "id", "value": "1", "path": "/", "expires": null, "httpOnly": false, "secure": true},
+            {"name": "theme", "value": "\"dark\""}
+          ],
+          "headersSize": -1,
+          "bodySize": 0
+        },
+        "response": {
+          "status": 200,
+          "statusText": "",
+          "httpVersion": "http/2.0",
+          "headers": [
+            {"name": "content-type", "value": "text/html; charset=utf-8"},
+            {"name": "set-cookie", "value": "a=1; Path=/"},
+            {"name": "set-cookie", "value": "b=2; Path=/"}
+          ],
+          "cookies": [{"name": "a", "value": "1"}, {"name": "b", "value": "2"}],
+          "content": {"size": 12, "mimeType": "text/html", "text": "<p>\ud83e\udd80</p>"},
+          "redirectURL": "",
+          "headersSize": -1,
+          "bodySize": -1,
+          "_transferSize": 321
+        },
+        "cache": {},
+        "timings": {"blocked": 1.5, "dns": -1, "connect": -1, "send": 0.2, "wait": 80, "receive": 3.55, "ssl": -1},
+        "_priority": "VeryHigh"
+      }
+    ]
+  }
+}"#;
+
+#[test]
+fn import_browser_export() {
+    let har = Har::from_json(EXPORT).unwrap();
+    assert_eq!(har.creator_name, "WebInspector");
+    assert_eq!(har.entries.len(), 1);
+    let entry = &har.entries[0];
+    assert_eq!(entry.time, 85.25);
+
+    let request = &entry.request;
+    assert_eq!(request.headers.getall("cookie").unwrap().len(), 2);
+    assert_eq!(request.query_string.get("q").unwrap().value, "caf\u{e9}");
+    assert_eq!(
+        request.query_string.getall("tag").unwrap().values(),
+        vec!["a", "b"]
+    );
+    assert_eq!(request.cookies.get("theme").unwrap().value, "\"dark\"");
+    assert!(request.post_data.is_none());
+
+    let response = &entry.response;
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.headers.getall("set-cookie").unwrap().values(),
+        vec!["a=1; Path=/", "b=2; Path=/"]
+    );
+    assert_eq!(response.text, "<p>\u{1f980}</p>");
+}
+
+#[test]
+fn export_round_trip() {
+    let har = Har::from_json(EXPORT).unwrap();
+    let output = har.to_json();
+    let imported = Har::from_json(&output).unwrap();
+    assert_eq!(imported.to_json(), output);
+    let (before, after) = (&har.entries[0].request, &imported.entries[0].request);
+    assert_eq!(before.headers.to_string(), after.headers.to_string());
+    assert_eq!(
+        before.query_string.to_string(),
+        after.query_string.to_string()
+    );
+    assert_eq!(before.cookies.to_string(), after.cookies.to_string());
+}
+
+#[test]
+fn form_post_data() {
+    let mut params = MultiDict::new();
+    for (key, value) in [("item", "1"), ("item", "2"), ("note", "line\nbreak")] {
+        params.add(MultiElement {
+            key: key.to_string(),
+            value: value.to_string(),
+            meta: None,
+        });
+    }
+    let mut har = Har::default();
+    har.entries.push(HarEntry {
+        request: HarRequest {
+            method: "POST".to_string(),
+            url: "https://example.com/cart".to_string(),
+            post_data: Some(HarPostData {
+                mime_type: "application/x-www-form-urlencoded".to_string(),
+                text: params.to_query_string(),
+                params,
+            }),
+            ..Default::default()
+        },
+        response: HarResponse {
+            status: 204,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let output = har.to_json();
+    assert!(output.contains(r#""value": "line\nbreak""#));
+    assert!(output.contains(r#""bodySize": 31"#));
+
+    let imported = Har::from_json(&output).unwrap();
+    let post_data = imported.entries[0].request.post_data.as_ref().unwrap();
+    assert_eq!(
+        post_data.params.getall("item").unwrap().values(),
+        vec!["1", "2"]
+    );
+    assert_eq!(
+        MultiDict::from_query_string(&post_data.text)
+            .unwrap()
+            .to_string(),
+        post_data.params.to_string()
+    );
+}
+
+#[test]
+fn invalid_documents() {
+    let cases = [
+        ("", "Unexpected end of JSON"),
+        ("{\"log\": {}}", "HAR log requires entries array"),
+        ("[]", "HAR document requires log object"),
+        ("{\"log\": {\"entries\": [{}]}}", "HAR entry requires request and response"),
+        (
+            "{\"log\": {\"entries\": [{\"request\": {\"headers\": {}}, \"response\": {\"status\": 200}}]}}",
+            "HAR name/value list must be an array",
+        ),
+        (
+            "{\"log\": {\"entries\": [{\"request\": {}, \"response\": {\"status\": \"200\"}}]}}",
+            "HAR response status must be an integer",
+        ),
+        ("{\"log\": {\"entries\": []}} x", "Trailing data after JSON value"),
+        ("{\"log\": \"\\ud800\"}", "Invalid JSON unicode escape"),
+    ];
+    for (input, error) in cases {
+        assert_eq!(Har::from_json(input).unwrap_err(), error, "{input}");
+    }
+}
+
+#[test]
+fn spans_of_pairs() {
+    let har: Har<Span> = Har::from_json_spanned(EXPORT).unwrap();
+    let entry = &har.entries[0];
+    let lists = [
+        &entry.request.headers,
+        &entry.request.query_string,
+        &entry.request.cookies,
+        &entry.response.headers,
+        &entry.response.cookies,
+    ];
+    for item in lists.into_iter().flat_map(MultiDict::iter) {
+        let text = item.meta.unwrap().slice(EXPORT);
+        assert!(text.starts_with(r#"{"name": ""#), "{text}");
+        assert!(text.ends_with('}'), "{text}");
+        let pair = MultiDict::from_har_pairs(&format!("[{text}]")).unwrap();
+        assert_eq!(pair.get(&item.key).unwrap().value, item.value);
+    }
+    assert_eq!(Har::from_json(EXPORT).unwrap().to_json(), har.to_json());
+
+    let input = r#"[{"name": "content-type", "value": "text/plain"},
+                    {"name": "content-length", "value": "ten"}]"#;
+    let headers: MultiDict<Span> = MultiDict::from_har_pairs_spanned(input).unwrap();
+    let invalid = headers
+        .iter()
+        .find(|item| item.key == "content-length" && item.value.parse::<u64>().is_err())
+        .unwrap();
+    assert_eq!(
+        invalid.meta,
+        Some(Span {
+            start: 70,
+            end: 112
+        })
+    );
+    assert_eq!(
+        invalid.meta.unwrap().slice(input),
+        r#"{"name": "content-length", "value": "ten"}"#
+    );
+}
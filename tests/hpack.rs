@@ -0,0 +1,488 @@
+//! Examples of RFC 7541 Appendix C and the stories of the hpack-test-case corpus
+//! when it is checked out in `tests/hpack-test-case`.
+//!
+//! Every header block is decoded and checked against the header list and the
+//! resulting dynamic table, then the encoder is expected to emit the exact same
+//! bytes from the header list.
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::bytes;
+use common::json::Json;
+use multidict::hpack::{Decoder, Encoder};
+use multidict::MultiDict;
+
+const STORIES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/hpack-test-case");
+
+struct Step<'a> {
+    block: &'a str,
+    headers: &'a [(&'a str, &'a str)],
+    table: &'a [(&'a str, &'a str)],
+    table_size: usize,
+}
+
+fn check_sequence(steps: &[Step], max_table_size: usize, huffman: bool) {
+    let mut encoder = Encoder::new(max_table_size);
+    encoder.set_huffman(huffman);
+    let mut decoder = Decoder::new(max_table_size);
+    for step in steps {
+        let block = bytes(step.block);
+        let decoded = decoder.decode(&block).unwrap();
+        assert_eq!(
+            decoded.to_string(),
+            step.headers.iter().copied().collect::<MultiDict>().to_string()
+        );
+        assert_eq!(
+            decoder.dynamic_table().to_string(),
+            step.table.iter().copied().collect::<MultiDict>().to_string()
+        );
+        assert_eq!(decoder.table_size(), step.table_size);
+        assert_eq!(
+            encoder.encode(&step.headers.iter().copied().collect::<MultiDict>()),
+            block
+        );
+        assert_eq!(encoder.table_size(), step.table_size);
+    }
+}
+
+#[test]
+fn literal_with_indexing() {
+    let mut decoder = Decoder::default();
+    let block = bytes("400a 6375 7374 6f6d 2d6b 6579 0d63 7573 746f 6d2d 6865 6164 6572");
+    let decoded = decoder.decode(&block).unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [("custom-key", "custom-header")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(decoder.table_size(), 55);
+
+    let mut encoder = Encoder::default();
+    encoder.set_huffman(false);
+    assert_eq!(encoder.encode(&decoded), block);
+}
+
+#[test]
+fn literal_without_indexing() {
+    let mut decoder = Decoder::default();
+    let block = bytes("040c 2f73 616d 706c 652f 7061 7468");
+    let (decoded, never_indexed) = decoder.decode_flagged(&block).unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [(":path", "/sample/path")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(never_indexed, vec![false]);
+    assert_eq!(decoder.table_size(), 0);
+}
+
+#[test]
+fn literal_never_indexed() {
+    let mut decoder = Decoder::default();
+    let block = bytes("1008 7061 7373 776f 7264 0673 6563 7265 74");
+    let (decoded, never_indexed) = decoder.decode_flagged(&block).unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [("password", "secret")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(never_indexed, vec![true]);
+    assert_eq!(decoder.table_size(), 0);
+
+    let mut encoder = Encoder::default();
+    encoder.set_huffman(false);
+    assert_eq!(
+        encoder.encode_flagged(&decoded, &never_indexed).unwrap(),
+        block
+    );
+    assert_eq!(encoder.table_size(), 0);
+}
+
+#[test]
+fn indexed_field() {
+    let mut decoder = Decoder::default();
+    let decoded = decoder.decode(&[0x82]).unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [(":method", "GET")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(decoder.table_size(), 0);
+}
+
+const REQUEST_1: &[(&str, &str)] = &[
+    (":method", "GET"),
+    (":scheme", "http"),
+    (":path", "/"),
+    (":authority", "www.example.com"),
+];
+const REQUEST_2: &[(&str, &str)] = &[
+    (":method", "GET"),
+    (":scheme", "http"),
+    (":path", "/"),
+    (":authority", "www.example.com"),
+    ("cache-control", "no-cache"),
+];
+const REQUEST_3: &[(&str, &str)] = &[
+    (":method", "GET"),
+    (":scheme", "https"),
+    (":path", "/index.html"),
+    (":authority", "www.example.com"),
+    ("custom-key", "custom-value"),
+];
+const REQUEST_TABLE_1: &[(&str, &str)] = &[(":authority", "www.example.com")];
+const REQUEST_TABLE_2: &[(&str, &str)] = &[
+    ("cache-control", "no-cache"),
+    (":authority", "www.example.com"),
+];
+const REQUEST_TABLE_3: &[(&str, &str)] = &[
+    ("custom-key", "custom-value"),
+    ("cache-control", "no-cache"),
+    (":authority", "www.example.com"),
+];
+
+#[test]
+fn requests_without_huffman() {
+    check_sequence(
+        &[
+            Step {
+                block: "8286 8441 0f77 7777 2e65 7861 6d70 6c65 2e63 6f6d",
+                headers: REQUEST_1,
+                table: REQUEST_TABLE_1,
+                table_size: 57,
+            },
+            Step {
+                block: "8286 84be 5808 6e6f 2d63 6163 6865",
+                headers: REQUEST_2,
+                table: REQUEST_TABLE_2,
+                table_size: 110,
+            },
+            Step {
+                block: "8287 85bf 400a 6375 7374 6f6d 2d6b 6579 0c63 7573 746f 6d2d 7661 6c75 65",
+                headers: REQUEST_3,
+                table: REQUEST_TABLE_3,
+                table_size: 164,
+            },
+        ],
+        4096,
+        false,
+    );
+}
+
+#[test]
+fn requests_with_huffman() {
+    check_sequence(
+        &[
+            Step {
+                block: "8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff",
+                headers: REQUEST_1,
+                table: REQUEST_TABLE_1,
+                table_size: 57,
+            },
+            Step {
+                block: "8286 84be 5886 a8eb 1064 9cbf",
+                headers: REQUEST_2,
+                table: REQUEST_TABLE_2,
+                table_size: 110,
+            },
+            Step {
+                block: "8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf",
+                headers: REQUEST_3,
+                table: REQUEST_TABLE_3,
+                table_size: 164,
+            },
+        ],
+        4096,
+        true,
+    );
+}
+
+const RESPONSE_1: &[(&str, &str)] = &[
+    (":status", "302"),
+    ("cache-control", "private"),
+    ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+    ("location", "https://www.example.com"),
+];
+const RESPONSE_2: &[(&str, &str)] = &[
+    (":status", "307"),
+    ("cache-control", "private"),
+    ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+    ("location", "https://www.example.com"),
+];
+const RESPONSE_3: &[(&str, &str)] = &[
+    (":status", "200"),
+    ("cache-control", "private"),
+    ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+    ("location", "https://www.example.com"),
+    ("content-encoding", "gzip"),
+    (
+        "set-cookie",
+        "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1",
+    ),
+];
+const RESPONSE_TABLE_1: &[(&str, &str)] = &[
+    ("location", "https://www.example.com"),
+    ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+    ("cache-control", "private"),
+    (":status", "302"),
+];
+const RESPONSE_TABLE_2: &[(&str, &str)] = &[
+    (":status", "307"),
+    ("location", "https://www.example.com"),
+    ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+    ("cache-control", "private"),
+];
+const RESPONSE_TABLE_3: &[(&str, &str)] = &[
+    (
+        "set-cookie",
+        "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1",
+    ),
+    ("content-encoding", "gzip"),
+    ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+];
+
+#[test]
+fn responses_without_huffman() {
+    check_sequence(
+        &[
+            Step {
+                block: "4803 3330 3258 0770 7269 7661 7465 611d
+                        4d6f 6e2c 2032 3120 4f63 7420 3230 3133
+                        2032 303a 3133 3a32 3120 474d 546e 1768
+                        7474 7073 3a2f 2f77 7777 2e65 7861 6d70
+                        6c65 2e63 6f6d",
+                headers: RESPONSE_1,
+                table: RESPONSE_TABLE_1,
+                table_size: 222,
+            },
+            Step {
+                block: "4803 3330 37c1 c0bf",
+                headers: RESPONSE_2,
+                table: RESPONSE_TABLE_2,
+                table_size: 222,
+            },
+            Step {
+                block: "88c1 611d 4d6f 6e2c 2032 3120 4f63 7420
+                        3230 3133 2032 303a 3133 3a32 3220 474d
+                        54c0 5a04 677a 6970 7738 666f 6f3d 4153
+                        444a 4b48 514b 425a 584f 5157 454f 5049
+                        5541 5851 5745 4f49 553b 206d 6178 2d61
+                        6765 3d33 3630 303b 2076 6572 7369 6f6e
+                        3d31",
+                headers: RESPONSE_3,
+                table: RESPONSE_TABLE_3,
+                table_size: 215,
+            },
+        ],
+        256,
+        false,
+    );
+}
+
+#[test]
+fn responses_with_huffman() {
+    check_sequence(
+        &[
+            Step {
+                block: "4882 6402 5885 aec3 771a 4b61 96d0 7abe
+                        9410 54d4 44a8 2005 9504 0b81 66e0 82a6
+                        2d1b ff6e 919d 29ad 1718 63c7 8f0b 97c8
+                        e9ae 82ae 43d3",
+                headers: RESPONSE_1,
+                table: RESPONSE_TABLE_1,
+                table_size: 222,
+            },
+            Step {
+                block: "4883 640e ffc1 c0bf",
+                headers: RESPONSE_2,
+                table: RESPONSE_TABLE_2,
+                table_size: 222,
+            },
+            Step {
+                block: "88c1 6196 d07a be94 1054 d444 a820 0595
+                        040b 8166 e084 a62d 1bff c05a 839b d9ab
+                        77ad 94e7 821d d7f2 e6c7 b335 dfdf cd5b
+                        3960 d5af 2708 7f36 72c1 ab27 0fb5 291f
+                        9587 3160 65c0 03ed 4ee5 b106 3d50 07",
+                headers: RESPONSE_3,
+                table: RESPONSE_TABLE_3,
+                table_size: 215,
+            },
+        ],
+        256,
+        true,
+    );
+}
+
+#[test]
+fn round_trip_keeps_duplicates_and_order() {
+    let list = [
+        (":status", "200"),
+        ("set-cookie", "a=1"),
+        ("vary", "accept"),
+        ("set-cookie", "b=2"),
+        ("x-non-ascii", "caf\u{e9}"),
+        ("set-cookie", "a=1"),
+    ]
+    .into_iter()
+    .collect::<MultiDict>();
+    let mut encoder = Encoder::new(64);
+    let mut decoder = Decoder::new(64);
+    for _ in 0..3 {
+        let decoded = decoder.decode(&encoder.encode(&list)).unwrap();
+        assert_eq!(decoded.to_string(), list.to_string());
+        assert_eq!(decoder.table_size(), encoder.table_size());
+    }
+}
+
+#[test]
+fn table_size_updates() {
+    let mut encoder = Encoder::new(4096);
+    let mut decoder = Decoder::new(4096);
+    let list = REQUEST_1.iter().copied().collect::<MultiDict>();
+    decoder.decode(&encoder.encode(&list)).unwrap();
+    assert_eq!(decoder.table_size(), 57);
+
+    encoder.set_max_table_size(0);
+    let block = encoder.encode(&list);
+    assert_eq!(block[0], 0x20);
+    decoder.decode(&block).unwrap();
+    assert_eq!(decoder.table_size(), 0);
+
+    let mut limited = Decoder::new(100);
+    assert_eq!(
+        limited.decode(&[0x3f, 0x61]).unwrap_err(),
+        "Dynamic table size update exceeds the limit"
+    );
+}
+
+#[test]
+fn malformed_blocks() {
+    let cases: &[(&[u8], &str)] = &[
+        (&[0x82, 0x84, 0x41], "Truncated integer"),
+        (&[0x41, 0x05, b'a'], "Truncated string literal"),
+        (&[0x41, 0x81, 0x00], "Invalid Huffman padding"),
+        (
+            &[0x41, 0x84, 0xff, 0xff, 0xff, 0xff],
+            "EOS symbol in Huffman string",
+        ),
+        (&[0x41, 0x81, 0xff], "Invalid Huffman padding"),
+        (
+            &[
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+            ],
+            "Integer overflow",
+        ),
+        (&[0x41, 0x01, 0xc3], "Header field is not valid UTF-8"),
+    ];
+    for (block, error) in cases {
+        assert_eq!(Decoder::default().decode(block).unwrap_err(), *error);
+    }
+}
+
+/// One case of a story: the dynamic table size announced before it, its wire bytes when
+/// the story comes from an encoder and the header list
+struct Case {
+    table_size: Option<usize>,
+    wire: Option<Vec<u8>>,
+    headers: MultiDict,
+}
+
+fn story(text: &str) -> Vec<Case> {
+    let story = Json::parse(text);
+    story
+        .get("cases")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|case| Case {
+            table_size: case.get("header_table_size").and_then(Json::as_usize),
+            wire: case.get("wire").map(|wire| bytes(wire.as_str().unwrap())),
+            headers: case
+                .get("headers")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|header| match header {
+                    Json::Object(pairs) => match pairs.as_slice() {
+                        [(key, Json::String(value))] => (key.clone(), value.clone()),
+                        _ => panic!("header is not a single name and value"),
+                    },
+                    _ => panic!("header is not an object"),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Story files of every implementation directory of the corpus, empty when it is not
+/// checked out
+fn story_paths() -> Vec<PathBuf> {
+    let Ok(dirs) = fs::read_dir(STORIES) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = dirs
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .flat_map(|dir| fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn hpack_test_case_stories() {
+    let paths = story_paths();
+    if paths.is_empty() {
+        eprintln!("hpack-test-case corpus not found in {STORIES}, see its README.md");
+        return;
+    }
+    for path in paths {
+        let name = path.display();
+        let cases = story(&fs::read_to_string(&path).unwrap());
+        // every wire of an encoder story decodes to the header list of its case
+        let mut decoder = Decoder::default();
+        for (seqno, case) in cases.iter().enumerate() {
+            let Some(wire) = &case.wire else { continue };
+            let decoded = decoder
+                .decode(wire)
+                .unwrap_or_else(|error| panic!("{name} {seqno}: {error}"));
+            assert_eq!(
+                decoded.to_string(),
+                case.headers.to_string(),
+                "{name} {seqno}"
+            );
+        }
+        // and the encoder output decodes to the same lists, with and without Huffman coding
+        for huffman in [true, false] {
+            let mut encoder = Encoder::default();
+            encoder.set_huffman(huffman);
+            let mut decoder = Decoder::default();
+            for (seqno, case) in cases.iter().enumerate() {
+                if let Some(table_size) = case.table_size {
+                    encoder.set_max_table_size(table_size);
+                }
+                let decoded = decoder.decode(&encoder.encode(&case.headers)).unwrap();
+                assert_eq!(
+                    decoded.to_string(),
+                    case.headers.to_string(),
+                    "{name} {seqno}"
+                );
+                assert_eq!(decoder.table_size(), encoder.table_size(), "{name} {seqno}");
+            }
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! INI, configparser and systemd unit files read, resolved and written back.
+use multidict::ini::{IniDialect, IniFile, IniSection};
+use multidict::metadata::Span;
+use multidict::MultiDict;
+
+const UNIT: &str = "# /etc/systemd/system/app.service
+[Unit]
+Description=Example application
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=notify
+; create the runtime directory first
+ExecStartPre=/bin/mkdir -p /run/app
+ExecStartPre=/bin/chown app:app /run/app
+ExecStart=/usr/bin/app --config /etc/app.conf
+Environment=LANG=C
+Environment=
+Environment=LANG=C.UTF-8 TZ=UTC
+
+[Install]
+WantedBy=multi-user.target
+";
+
+const DROP_IN: &str = "[Service]
+ExecStartPre=
+ExecStartPre=/usr/bin/app-migrate
+ExecStart=
+ExecStart=/usr/bin/app \\
+    --config /etc/app.conf \\
+# ignored inside a continuation
+    --verbose
+";
+
+/// Example of "Supported INI File Structure" in the Python `configparser` documentation,
+/// without the section needing `allow_no_value`, followed by its `empty_lines_in_values`
+/// example
+const PYTHON_EXAMPLE: &str = "[Simple Values]
+key=value
+spaces in keys=allowed
+spaces in values=allowed as well
+spaces around the delimiter = obviously
+you can also use : to delimit keys from values
+
+[All Values Are Strings]
+values like this: 1000000
+or this: 3.14159265359
+are they treated as numbers? : no
+integers, floats and booleans are held as: strings
+can use the API to get converted values directly: true
+
+[Multiline Values]
+chorus: I'm a lumberjack, and I'm okay
+    I sleep all night and I work all day
+
+[No Values]
+empty string value here =
+
+[You can use comments]
+# like this
+; or this
+
+# By default only in an empty line.
+# Inline comments can be harmful because they prevent users
+# from using the delimiting characters as parts of values.
+# That being said, this can be customized.
+
+    [Sections Can Be Indented]
+        can_values_be_as_well = True
+        does_that_mean_anything_special = False
+        purpose = formatting for readability
+        multiline_values = are
+            handled just fine as
+            long as they are indented
+            deeper than the first line
+            of a value
+        # Did I mention we can indent comments, too?
+
+[Section]
+key = multiline
+  value with a gotcha
+
+ this = is still a part of the multiline value of 'key'
+";
+
+#[test]
+fn systemd_unit() {
+    let file = IniFile::parse(UNIT, IniDialect::Systemd).unwrap();
+    assert_eq!(file.section_names(), vec!["Unit", "Service", "Install"]);
+    let service = file.section("Service").unwrap();
+    assert_eq!(
+        service.to_string(),
+        [
+            ("Type", "notify"),
+            ("ExecStartPre", "/bin/mkdir -p /run/app"),
+            ("ExecStartPre", "/bin/chown app:app /run/app"),
+            ("ExecStart", "/usr/bin/app --config /etc/app.conf"),
+            ("Environment", "LANG=C.UTF-8 TZ=UTC"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    assert_eq!(
+        file.sections[1].entries.getall("Environment").unwrap().len(),
+        3
+    );
+    assert_eq!(file.write().unwrap(), UNIT);
+    assert!(file.section("Missing").is_none());
+}
+
+#[test]
+fn systemd_drop_in_resets() {
+    let mut combined = UNIT.to_string();
+    combined.push_str(DROP_IN);
+    let file = IniFile::parse(&combined, IniDialect::Systemd).unwrap();
+    let service = file.section("Service").unwrap();
+    assert_eq!(
+        service.getall("ExecStartPre").unwrap().len(),
+        1,
+        "{service}"
+    );
+    assert_eq!(
+        service.get("ExecStart").unwrap().value,
+        "/usr/bin/app      --config /etc/app.conf      --verbose"
+    );
+    assert_eq!(file.section_names(), vec!["Unit", "Service", "Install"]);
+    assert_eq!(file.to_multidicts().len(), 3);
+}
+
+#[test]
+fn configparser_semantics() {
+    let config = "[DEFAULT]
+ServerAliveInterval = 45
+Compression = yes
+
+[forge.example]
+User = hg
+
+[topsecret.server.example]
+Port: 50022
+ForwardX11 = no
+port = 50023
+description = first line
+    second line
+";
+    let file = IniFile::parse(config, IniDialect::ConfigParser).unwrap();
+    assert_eq!(
+        file.section("topsecret.server.example").unwrap().to_string(),
+        [
+            ("port", "50023"),
+            ("forwardx11", "no"),
+            ("description", "first line\nsecond line"),
+            ("serveraliveinterval", "45"),
+            ("compression", "yes"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    assert_eq!(
+        file.section("forge.example").unwrap().to_string(),
+        [
+            ("user", "hg"),
+            ("serveraliveinterval", "45"),
+            ("compression", "yes"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    let written = file.write().unwrap();
+    assert!(written.contains("Port = 50022\n"));
+    assert!(written.contains("description = first line\n    second line\n"));
+    let reparsed = IniFile::parse(&written, IniDialect::ConfigParser).unwrap();
+    for (reparsed, (name, section)) in reparsed.to_multidicts().iter().zip(file.to_multidicts()) {
+        assert_eq!(reparsed.0, name);
+        assert_eq!(reparsed.1.to_string(), section.to_string());
+    }
+}
+
+#[test]
+fn configparser_python_example() {
+    // expected values as read by Python 3 `configparser.ConfigParser`
+    let file = IniFile::parse(PYTHON_EXAMPLE, IniDialect::ConfigParser).unwrap();
+    let sections: Vec<(&str, &[(&str, &str)])> = vec![
+        (
+            "Simple Values",
+            &[
+                ("key", "value"),
+                ("spaces in keys", "allowed"),
+                ("spaces in values", "allowed as well"),
+                ("spaces around the delimiter", "obviously"),
+                ("you can also use", "to delimit keys from values"),
+            ],
+        ),
+        (
+            "All Values Are Strings",
+            &[
+                ("values like this", "1000000"),
+                ("or this", "3.14159265359"),
+                ("are they treated as numbers?", "no"),
+                ("integers, floats and booleans are held as", "strings"),
+                ("can use the api to get converted values directly", "true"),
+            ],
+        ),
+        (
+            "Multiline Values",
+            &[(
+                "chorus",
+                "I'm a lumberjack, and I'm okay\nI sleep all night and I work all day",
+            )],
+        ),
+        ("No Values", &[("empty string value here", "")]),
+        ("You can use comments", &[]),
+        (
+            "Sections Can Be Indented",
+            &[
+                ("can_values_be_as_well", "True"),
+                ("does_that_mean_anything_special", "False"),
+                ("purpose", "formatting for readability"),
+                (
+                    "multiline_values",
+                    "are\nhandled just fine as\nlong as they are indented\n\
+                     deeper than the first line\nof a value",
+                ),
+            ],
+        ),
+        (
+            "Section",
+            &[(
+                "key",
+                "multiline\nvalue with a gotcha\n\n\
+                 this = is still a part of the multiline value of 'key'",
+            )],
+        ),
+    ];
+    assert_eq!(
+        file.section_names(),
+        sections.iter().map(|(name, _)| *name).collect::<Vec<_>>()
+    );
+    for (name, entries) in &sections {
+        assert_eq!(
+            file.section(name).unwrap().to_string(),
+            entries.iter().copied().collect::<MultiDict>().to_string(),
+            "{name}"
+        );
+    }
+    let written = file.write().unwrap();
+    assert!(written.contains("key = multiline\n    value with a gotcha\n\n    this = is"));
+    let reparsed = IniFile::parse(&written, IniDialect::ConfigParser).unwrap();
+    for (name, _) in &sections {
+        assert_eq!(
+            reparsed.section(name).unwrap().to_string(),
+            file.section(name).unwrap().to_string()
+        );
+    }
+}
+
+#[test]
+fn plain_ini_keeps_everything() {
+    let input =
+        "; global keys\nname=demo\n\n[paths]\ninclude=/a\ninclude=/b\nINCLUDE=/c\n\n; end\n";
+    let file = IniFile::parse(input, IniDialect::Plain).unwrap();
+    assert_eq!(file.section_names(), vec!["", "paths"]);
+    assert_eq!(
+        file.section("").unwrap().to_string(),
+        [("name", "demo")].into_iter().collect::<MultiDict>().to_string()
+    );
+    assert_eq!(file.section("paths").unwrap().len(), 3);
+    assert_eq!(file.trailing, vec!["", "; end"]);
+    assert_eq!(file.write().unwrap(), input);
+}
+
+#[test]
+fn build_and_write() {
+    let mut file = IniFile::new(IniDialect::Systemd);
+    let mut service = IniSection::new("Service");
+    service.add("ExecStart", "/usr/bin/true");
+    service.add("Restart", "on-failure");
+    file.sections.push(service);
+    assert_eq!(
+        file.write().unwrap(),
+        "[Service]\nExecStart=/usr/bin/true\nRestart=on-failure\n"
+    );
+
+    file.sections[0].add("Description", "two\nlines");
+    assert_eq!(
+        file.write().unwrap_err(),
+        "Multi-line INI value needs the ConfigParser dialect"
+    );
+    file.dialect = IniDialect::ConfigParser;
+    assert!(file.write().is_ok());
+}
+
+#[test]
+fn malformed_files() {
+    for (input, dialect, error) in [
+        (
+            "[open\n",
+            IniDialect::Plain,
+            "INI section header is not closed",
+        ),
+        ("[]\n", IniDialect::Plain, "INI section name is empty"),
+        (
+            "[a]\njust text\n",
+            IniDialect::Plain,
+            "INI line has no assignment",
+        ),
+        ("[a]\n=value\n", IniDialect::Plain, "INI key is empty"),
+        (
+            "key=value\n",
+            IniDialect::Systemd,
+            "INI key outside a section",
+        ),
+        (
+            "key=value\n",
+            IniDialect::ConfigParser,
+            "INI key outside a section",
+        ),
+    ] {
+        assert_eq!(IniFile::parse(input, dialect).unwrap_err(), error);
+    }
+}
+
+#[test]
+fn spans_of_assignments() {
+    let config = "[server]\r\nport = eighty\r\nhosts = a\r\n\r\n    b\r\nuser = root\r\n";
+    let file = IniFile::parse(config, IniDialect::ConfigParser).unwrap();
+    let entries = &file.sections[0].entries;
+    let invalid = entries
+        .iter()
+        .find(|item| item.key == "port" && item.value.parse::<u16>().is_err())
+        .unwrap();
+    let span = invalid.meta.as_ref().unwrap().span;
+    assert_eq!(span, Some(Span { start: 10, end: 23 }));
+    assert_eq!(span.unwrap().slice(config), "port = eighty");
+    // continuation lines extend the span of their value
+    let spans: Vec<&str> = entries
+        .iter()
+        .map(|item| item.meta.as_ref().unwrap().span.unwrap().slice(config))
+        .collect();
+    assert_eq!(spans[1], "hosts = a\r\n\r\n    b");
+    assert_eq!(spans[2], "user = root");
+
+    let unit = "[Service]\nExecStart=/usr/bin/app \\\n  --port x\nUser=app\n";
+    let file = IniFile::parse(unit, IniDialect::Systemd).unwrap();
+    let start = file.sections[0].entries.get("ExecStart").unwrap();
+    assert_eq!(
+        start.meta.as_ref().unwrap().span.unwrap().slice(unit),
+        "ExecStart=/usr/bin/app \\\n  --port x"
+    );
+}
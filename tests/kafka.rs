@@ -0,0 +1,111 @@
+//! Kafka record headers against byte fixtures, no broker involved.
+mod common;
+
+use common::bytes;
+use multidict::kafka::{decode_varint, encode_varint, RecordHeaders};
+use multidict::MultiDict;
+
+#[test]
+fn zigzag_varints() {
+    for (value, encoded) in [
+        (0, "00"),
+        (-1, "01"),
+        (1, "02"),
+        (-64, "7f"),
+        (64, "8001"),
+        (i32::MAX, "feffffff0f"),
+        (i32::MIN, "ffffffff0f"),
+    ] {
+        let mut out = Vec::new();
+        encode_varint(value, &mut out);
+        assert_eq!(out, bytes(encoded), "{value}");
+        let mut position = 0;
+        assert_eq!(decode_varint(&out, &mut position), Ok(value));
+        assert_eq!(position, out.len());
+    }
+    let mut position = 0;
+    assert_eq!(
+        decode_varint(&bytes("ffffffff1f"), &mut position),
+        Err("Varint is too long")
+    );
+    let mut position = 0;
+    assert_eq!(
+        decode_varint(&bytes("80"), &mut position),
+        Err("Truncated varint")
+    );
+}
+
+#[test]
+fn record_headers_fixture() {
+    let map: RecordHeaders = [
+        ("content-type", Some(b"application/avro".to_vec())),
+        ("trace-id", Some(vec![0x4b, 0xf9, 0x2f, 0x35])),
+        ("retry", Some(b"1".to_vec())),
+        ("retry", Some(b"2".to_vec())),
+        ("deleted", None),
+        ("empty", Some(Vec::new())),
+    ]
+    .into_iter()
+    .collect();
+    let encoded = bytes(
+        "0c
+         18 636f6e74656e742d74797065 20 6170706c69636174696f6e2f6176726f
+         10 74726163652d6964 08 4bf92f35
+         0a 7265747279 02 31
+         0a 7265747279 02 32
+         0e 64656c65746564 01
+         0a 656d707479 00",
+    );
+    assert_eq!(map.to_kafka_headers().unwrap(), encoded);
+
+    // trailing record bytes are not part of the headers
+    let mut record = encoded.clone();
+    record.extend_from_slice(&bytes("deadbeef"));
+    let (decoded, length) = RecordHeaders::from_kafka_headers(&record).unwrap();
+    assert_eq!(length, encoded.len());
+    assert_eq!(decoded.to_string(), map.to_string());
+    assert_eq!(
+        decoded.to_string(),
+        "MultiDict < \"content-type\":b\"application/avro\", \"trace-id\":b\"K\\xf9/5\", \
+         \"retry\":b\"1\", \"retry\":b\"2\", \"deleted\":null, \"empty\":b\"\" >"
+    );
+    assert_eq!(decoded.getall("retry").unwrap().len(), 2);
+    assert_eq!(decoded.get("deleted").unwrap().value, None);
+
+    assert_eq!(
+        RecordHeaders::<()>::default().to_kafka_headers().unwrap(),
+        bytes("00")
+    );
+}
+
+#[test]
+fn string_headers_round_trip() {
+    let strings = MultiDict::from_query_string("a=1&a=2&b=x").unwrap();
+    let binary: RecordHeaders = strings.clone().map_values(|value| Some(value.into_bytes()));
+    let (decoded, _) =
+        RecordHeaders::from_kafka_headers(&binary.to_kafka_headers().unwrap()).unwrap();
+    let restored: MultiDict = decoded
+        .try_map_values(|value| String::from_utf8(value.unwrap_or_default()))
+        .unwrap();
+    assert_eq!(restored.to_string(), strings.to_string());
+}
+
+#[test]
+fn malformed_headers() {
+    let cases = [
+        ("01", "Negative record header count"),
+        ("02 01", "Record header key is null"),
+        ("02 04 6162", "Truncated varint"),
+        ("02 04 61", "Truncated record header"),
+        ("02 02 ff 00", "Record header key is not valid UTF-8"),
+        ("02 02 61 03", "Invalid record header value length"),
+        ("02 02 61 06 0102", "Truncated record header"),
+    ];
+    for (input, error) in cases {
+        assert_eq!(
+            RecordHeaders::from_kafka_headers(&bytes(input)).unwrap_err(),
+            error,
+            "{input}"
+        );
+    }
+}
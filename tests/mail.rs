@@ -0,0 +1,169 @@
+//! Email header sections with folding, encoded words and repeated `Received` fields.
+use multidict::mail::{decode_words, encode_words, MailHeaders, LINE_LENGTH};
+use multidict::metadata::Span;
+use multidict::MultiDict;
+
+const MESSAGE: &str = "Return-Path: <alice@example.com>\r\n\
+Received: from mx2.example.net (mx2.example.net [192.0.2.20])\r\n\
+\tby inbound.example.org with ESMTPS id 4Xk2;\r\n\
+\tMon, 14 Oct 2024 09:15:03 +0000\r\n\
+Received: from relay.example.com (relay.example.com [198.51.100.7])\r\n\
+\tby mx2.example.net with ESMTP id 77aa; Mon, 14 Oct 2024 09:15:01 +0000\r\n\
+received: from [203.0.113.5] by relay.example.com; Mon, 14 Oct 2024 09:15:00 +0000\r\n\
+From: =?utf-8?q?Ren=C3=A9e?= <renee@example.com>\r\n\
+To: bob@example.org\r\n\
+Subject: =?utf-8?b?UmU6IHLDqXVuaW9u?=\r\n =?utf-8?b?IGRlIGTDqWNlbWJyZQ==?=\r\n\
+\r\n\
+Body line\r\n";
+
+#[test]
+fn received_order_is_kept() {
+    let (parsed, body) = MailHeaders::from_mail(MESSAGE).unwrap();
+    assert_eq!(body, "Body line\r\n");
+    assert_eq!(
+        parsed.getall("Received"),
+        vec![
+            "from mx2.example.net (mx2.example.net [192.0.2.20])\tby inbound.example.org \
+             with ESMTPS id 4Xk2;\tMon, 14 Oct 2024 09:15:03 +0000",
+            "from relay.example.com (relay.example.com [198.51.100.7])\tby mx2.example.net \
+             with ESMTP id 77aa; Mon, 14 Oct 2024 09:15:01 +0000",
+            "from [203.0.113.5] by relay.example.com; Mon, 14 Oct 2024 09:15:00 +0000",
+        ]
+    );
+    let names: Vec<&str> = parsed.headers().iter().map(|item| item.key.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "Return-Path",
+            "Received",
+            "Received",
+            "received",
+            "From",
+            "To",
+            "Subject"
+        ]
+    );
+}
+
+#[test]
+fn encoded_words() {
+    let (parsed, _) = MailHeaders::from_mail(MESSAGE).unwrap();
+    assert_eq!(
+        parsed.get_text("from").unwrap().as_deref(),
+        Some("Renée <renee@example.com>")
+    );
+    assert_eq!(
+        parsed.get_text("Subject").unwrap().as_deref(),
+        Some("Re: réunion de décembre")
+    );
+    assert_eq!(parsed.get_text("Missing").unwrap(), None);
+
+    assert_eq!(decode_words("a =?utf-8?q?b?= c").unwrap(), "a b c");
+    assert_eq!(
+        decode_words("=?utf-8?q?a?=\r\n =?iso-8859-1?q?=E9?=").unwrap(),
+        "aé"
+    );
+    assert_eq!(
+        decode_words("=?utf-8?b?4oI=?= =?utf-8?b?rA==?=").unwrap(),
+        "€"
+    );
+    assert_eq!(decode_words("=?utf-8*en?q?hi?=").unwrap(), "hi");
+    assert_eq!(decode_words("x=?y and =?bad").unwrap(), "x=?y and =?bad");
+    assert_eq!(
+        decode_words("=?koi8-r?q?=F0?=").unwrap_err(),
+        "Unsupported encoded word charset"
+    );
+    assert_eq!(
+        decode_words("=?utf-8?q?=FF?=").unwrap_err(),
+        "Encoded word is not valid in its charset"
+    );
+}
+
+#[test]
+fn long_text_is_split_into_words() {
+    let text = "Ünïcödé ".repeat(12);
+    let encoded = encode_words(&text);
+    assert!(encoded.split(' ').all(|word| word.len() <= 75));
+    assert_eq!(decode_words(&encoded).unwrap(), text);
+    assert_eq!(
+        encode_words("literal =?x?= text"),
+        "=?utf-8?b?bGl0ZXJhbCA9P3g/PSB0ZXh0?="
+    );
+
+    let mut mail = MailHeaders::new();
+    mail.add_text("Subject", &text).unwrap();
+    let serialized = mail.to_mail();
+    assert!(serialized.split("\r\n").all(|line| line.len() <= LINE_LENGTH));
+    let (parsed, _) = MailHeaders::from_mail(&serialized).unwrap();
+    assert_eq!(parsed.get_text("subject").unwrap(), Some(text));
+}
+
+#[test]
+fn refold_round_trip() {
+    let (parsed, _) = MailHeaders::from_mail(MESSAGE).unwrap();
+    let serialized = parsed.to_mail();
+    for line in serialized.split("\r\n") {
+        assert!(line.len() <= LINE_LENGTH, "{line}");
+    }
+    let (reparsed, body) = MailHeaders::from_mail(&serialized).unwrap();
+    assert_eq!(reparsed.to_string(), parsed.to_string());
+    assert_eq!(body, "");
+
+    let long_word = "x".repeat(100);
+    let mut mail = MailHeaders::new();
+    mail.add("X-Token", &long_word).unwrap();
+    assert_eq!(mail.to_mail(), format!("X-Token: {long_word}\r\n"));
+}
+
+#[test]
+fn lf_lines_and_errors() {
+    let (parsed, body) = MailHeaders::from_mail("A: 1\nb: 2\n  two\n\nbody").unwrap();
+    assert_eq!(
+        parsed.headers().to_string(),
+        [("A", "1"), ("b", "2  two")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(body, "body");
+    assert!(parsed.get("B").is_some());
+
+    let mut parsed = parsed;
+    assert!(parsed.remove("a"));
+    assert_eq!(parsed.len(), 1);
+
+    for (input, error) in [
+        (" folded: x\r\n", "Mail header continues nothing"),
+        ("no colon\r\n", "Mail header has no colon"),
+        ("Bad Name: x\r\n", "Invalid mail header name"),
+    ] {
+        assert_eq!(MailHeaders::from_mail(input).unwrap_err(), error);
+    }
+    assert_eq!(
+        MailHeaders::new().add("Subject", "a\r\nBcc: x").unwrap_err(),
+        "Mail header value contains a line break"
+    );
+}
+
+#[test]
+fn spans_of_header_fields() {
+    let message = "From: alice@example.com\r\n\
+                   Subject: =?utf-8?b?Q2Fm?=\r\n =?utf-8?b?w6k*?=\r\n\
+                   \r\nBody\r\n";
+    let (headers, body) = MailHeaders::<Span>::from_mail_spanned(message).unwrap();
+    assert_eq!(body, "Body\r\n");
+    let invalid = headers
+        .headers()
+        .iter()
+        .find(|item| decode_words(&item.value).is_err())
+        .unwrap();
+    assert_eq!(invalid.meta, Some(Span { start: 25, end: 69 }));
+    assert_eq!(
+        invalid.meta.unwrap().slice(message),
+        "Subject: =?utf-8?b?Q2Fm?=\r\n =?utf-8?b?w6k*?="
+    );
+    assert_eq!(
+        headers.headers().get("From").unwrap().meta,
+        Some(Span { start: 0, end: 23 })
+    );
+}
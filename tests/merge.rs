@@ -0,0 +1,94 @@
+//! Every merge strategy on duplicate-heavy input.
+use multidict::merge::MergeStrategy;
+use multidict::metadata::Span;
+use multidict::MultiDict;
+
+const EXISTING: &str = "a=1&b=1&a=2&c=1&a=3&b=2";
+const INCOMING: &str = "b=9&a=8&d=7&a=6&d=5&b=4&b=3";
+
+fn merged(strategy: MergeStrategy) -> String {
+    let existing = MultiDict::from_query_string(EXISTING).unwrap();
+    let incoming = MultiDict::from_query_string(INCOMING).unwrap();
+    let results = existing.merged(&incoming, strategy);
+    // inputs stay unchanged
+    assert_eq!(existing.to_query_string(), EXISTING);
+    assert_eq!(incoming.to_query_string(), INCOMING);
+    results.to_query_string()
+}
+
+#[test]
+fn append() {
+    assert_eq!(
+        merged(MergeStrategy::Append),
+        format!("{EXISTING}&{INCOMING}")
+    );
+}
+
+#[test]
+fn replace_all() {
+    assert_eq!(
+        merged(MergeStrategy::ReplaceAll),
+        "a=8&a=6&b=9&b=4&b=3&c=1&d=7&d=5"
+    );
+}
+
+#[test]
+fn keep_first() {
+    // d is only found in the incoming map, both of its elements are kept
+    assert_eq!(merged(MergeStrategy::KeepFirst), "a=1&b=1&c=1&d=7&d=5");
+}
+
+#[test]
+fn keep_last() {
+    assert_eq!(merged(MergeStrategy::KeepLast), "a=6&b=3&c=1&d=7&d=5");
+}
+
+#[test]
+fn keys_of_one_map_keep_duplicates() {
+    let existing = MultiDict::from_query_string("x=1&x=2&k=1").unwrap();
+    let incoming = MultiDict::from_query_string("k=2&k=3&y=1&y=2").unwrap();
+    for (strategy, expected) in [
+        (MergeStrategy::KeepFirst, "x=1&x=2&k=1&y=1&y=2"),
+        (MergeStrategy::KeepLast, "x=1&x=2&k=3&y=1&y=2"),
+    ] {
+        assert_eq!(
+            existing.merged(&incoming, strategy).to_query_string(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn replace_per_key() {
+    // a: 1,2,3 -> 8,6 and one removed; b: 1,2 -> 9,4 and 3 appended
+    assert_eq!(
+        merged(MergeStrategy::ReplacePerKey),
+        "a=8&b=9&a=6&c=1&b=4&d=7&d=5&b=3"
+    );
+}
+
+#[test]
+fn per_key_closure() {
+    let strategy = MergeStrategy::per_key(|key, mut existing, incoming| match key {
+        "a" => existing,
+        "b" => {
+            existing.extend(incoming);
+            existing.dedup_by(|left, right| left.value == right.value);
+            existing
+        }
+        _ => Vec::new(),
+    });
+    assert_eq!(merged(strategy), "a=1&a=2&a=3&b=1&b=2&b=9&b=4&b=3&c=1");
+}
+
+#[test]
+fn strategies_keep_metadata() {
+    let mut existing: MultiDict<Span> = MultiDict::from_query_string_spanned("a=1&a=2").unwrap();
+    let incoming: MultiDict<Span> = MultiDict::from_query_string_spanned("?b=3&a=4").unwrap();
+    existing.merge(&incoming, MergeStrategy::ReplacePerKey);
+    let spans: Vec<(usize, usize)> = existing
+        .iter()
+        .map(|item| (item.meta.unwrap().start, item.meta.unwrap().end))
+        .collect();
+    assert_eq!(spans, vec![(5, 8), (1, 4)]);
+}
@@ -0,0 +1,380 @@
+//! Test vectors of RFC 9421 section 2 and Appendix B.2.
+//!
+//! The `hmac-sha256` case (B.2.5) is signed and verified end to end. Asymmetric
+//! algorithms are not built in: for B.2.4 and B.2.6 the published signatures, checked
+//! with the Appendix B.1 keys outside this crate, go through [`PublishedSignature`] in
+//! place of an application key.
+mod common;
+
+use common::base64;
+use multidict::message_signatures::{
+    sign, signature_base, verify, HmacSha256Key, HttpMessage, SignatureParams, SigningKey,
+    VerifyingKey,
+};
+use multidict::MultiDict;
+
+const TARGET_URI: &str = "https://example.com/foo?param=Value&Pet=dog";
+const CONTENT_DIGEST: &str = "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYl\
+                              lu7BNNyealdVLvRwEmTHWXvJwew==:";
+const SHARED_SECRET: &str = "uzvJfB4u3N0Jy4T7NZ75MDVcr8zSTInedJtkgcu46YW4XByzNJjxBdtjUkdJPBtbmH\
+                             hIDi6pcl8jsasjlTMtDQ==";
+
+/// Key of an algorithm the crate does not implement, it knows the one signature the
+/// RFC published for `base`
+struct PublishedSignature {
+    base: String,
+    signature: Vec<u8>,
+}
+
+impl SigningKey for PublishedSignature {
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, &'static str> {
+        if data == self.base.as_bytes() {
+            Ok(self.signature.clone())
+        } else {
+            Err("Unexpected signature base")
+        }
+    }
+}
+
+impl VerifyingKey for PublishedSignature {
+    fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        data == self.base.as_bytes() && signature == self.signature
+    }
+}
+
+/// Sign `message` with the published signature of `expected_base`, then verify the
+/// headers carrying it
+fn check_published(
+    message: HttpMessage,
+    label: &str,
+    params: &str,
+    expected_base: &str,
+    signature: &str,
+) {
+    let params = SignatureParams::parse(params).unwrap();
+    assert_eq!(signature_base(&message, &params).unwrap(), expected_base);
+    let key = PublishedSignature {
+        base: expected_base.to_string(),
+        signature: base64(signature),
+    };
+    let signed = sign(&message, label, &params, &key).unwrap();
+    assert_eq!(
+        signed.get("Signature").unwrap().value,
+        format!("{label}=:{signature}:")
+    );
+    let mut headers = message.headers.clone();
+    headers.elements.extend(signed.elements);
+    let message = HttpMessage {
+        headers: &headers,
+        ..message
+    };
+    assert_eq!(verify(&message, label, &key).unwrap(), params);
+}
+
+fn request_headers() -> MultiDict {
+    [
+        ("Host", "example.com"),
+        ("Date", "Tue, 20 Apr 2021 02:07:55 GMT"),
+        ("Content-Type", "application/json"),
+        ("Content-Digest", CONTENT_DIGEST),
+        ("Content-Length", "18"),
+    ]
+    .into_iter()
+    .collect::<MultiDict>()
+}
+
+fn base(headers: &MultiDict, params: &str) -> String {
+    let message = HttpMessage::request("POST", TARGET_URI, headers);
+    signature_base(&message, &SignatureParams::parse(params).unwrap()).unwrap()
+}
+
+#[test]
+fn field_components() {
+    let headers = [
+        ("X-OWS-Header", "   Leading and trailing whitespace.   "),
+        ("X-Obs-Fold-Header", "Obsolete\n    line folding."),
+        ("Cache-Control", "max-age=60"),
+        ("Cache-Control", "   must-revalidate"),
+        ("Example-Dict", " a=1,    b=2;x=1;y=2,   c=(a   b   c)"),
+        ("X-Empty-Header", ""),
+    ]
+    .into_iter()
+    .collect::<MultiDict>();
+    assert_eq!(
+        base(
+            &headers,
+            r#"("cache-control" "x-empty-header" "x-obs-fold-header" "x-ows-header" "example-dict" "example-dict";sf)"#
+        ),
+        "\"cache-control\": max-age=60, must-revalidate\n\
+         \"x-empty-header\": \n\
+         \"x-obs-fold-header\": Obsolete line folding.\n\
+         \"x-ows-header\": Leading and trailing whitespace.\n\
+         \"example-dict\": a=1,    b=2;x=1;y=2,   c=(a   b   c)\n\
+         \"example-dict\";sf: a=1, b=2;x=1;y=2, c=(a b c)\n\
+         \"@signature-params\": (\"cache-control\" \"x-empty-header\" \"x-obs-fold-header\" \
+         \"x-ows-header\" \"example-dict\" \"example-dict\";sf)"
+    );
+}
+
+#[test]
+fn dictionary_member_components() {
+    let headers = [("Example-Dict", "  a=1, b=2;x=1;y=2, c=(a   b    c), d")]
+        .into_iter()
+        .collect::<MultiDict>();
+    assert_eq!(
+        base(
+            &headers,
+            r#"("example-dict";key="a" "example-dict";key="d" "example-dict";key="b" "example-dict";key="c")"#
+        ),
+        "\"example-dict\";key=\"a\": 1\n\
+         \"example-dict\";key=\"d\": ?1\n\
+         \"example-dict\";key=\"b\": 2;x=1;y=2\n\
+         \"example-dict\";key=\"c\": (a b c)\n\
+         \"@signature-params\": (\"example-dict\";key=\"a\" \"example-dict\";key=\"d\" \
+         \"example-dict\";key=\"b\" \"example-dict\";key=\"c\")"
+    );
+}
+
+#[test]
+fn binary_wrapped_components() {
+    let headers = [
+        ("Example-Header", "value, with, lots"),
+        ("Example-Header", "of, commas"),
+    ]
+    .into_iter()
+    .collect::<MultiDict>();
+    assert_eq!(
+        base(&headers, r#"("example-header";bs)"#),
+        "\"example-header\";bs: :dmFsdWUsIHdpdGgsIGxvdHM=:, :b2YsIGNvbW1hcw==:\n\
+         \"@signature-params\": (\"example-header\";bs)"
+    );
+}
+
+#[test]
+fn derived_components() {
+    let headers = MultiDict::new();
+    let message = HttpMessage::request(
+        "GET",
+        "https://www.example.com/path?param=value&foo=bar&baz=batman&qux=",
+        &headers,
+    );
+    let params = SignatureParams::parse(
+        r#"("@target-uri" "@authority" "@scheme" "@request-target" "@path" "@query" "@query-param";name="baz")"#,
+    )
+    .unwrap();
+    assert_eq!(
+        signature_base(&message, &params).unwrap(),
+        "\"@target-uri\": https://www.example.com/path?param=value&foo=bar&baz=batman&qux=\n\
+         \"@authority\": www.example.com\n\
+         \"@scheme\": https\n\
+         \"@request-target\": /path?param=value&foo=bar&baz=batman&qux=\n\
+         \"@path\": /path\n\
+         \"@query\": ?param=value&foo=bar&baz=batman&qux=\n\
+         \"@query-param\";name=\"baz\": batman\n\
+         \"@signature-params\": (\"@target-uri\" \"@authority\" \"@scheme\" \"@request-target\" \
+         \"@path\" \"@query\" \"@query-param\";name=\"baz\")"
+    );
+
+    let message = HttpMessage::request(
+        "GET",
+        "https://example.com/parameters?var=this%20is%20a%20big%0Avalue&bar=with+plus+whitespace\
+         &fa%C3%A7ade%22%3A%20=something",
+        &headers,
+    );
+    let params = SignatureParams::parse(
+        r#"("@query-param";name="var" "@query-param";name="bar" "@query-param";name="fa%C3%A7ade%22%3A%20")"#,
+    )
+    .unwrap();
+    let base = signature_base(&message, &params).unwrap();
+    let lines: Vec<&str> = base.lines().collect();
+    assert_eq!(
+        lines[0],
+        "\"@query-param\";name=\"var\": this%20is%20a%20big%0Avalue"
+    );
+    assert_eq!(
+        lines[1],
+        "\"@query-param\";name=\"bar\": with%20plus%20whitespace"
+    );
+    assert_eq!(
+        lines[2],
+        "\"@query-param\";name=\"fa%C3%A7ade%22%3A%20\": something"
+    );
+
+    let message = HttpMessage::response(200, &headers);
+    let params = SignatureParams::parse(r#"("@status")"#).unwrap();
+    assert!(signature_base(&message, &params)
+        .unwrap()
+        .starts_with("\"@status\": 200\n"));
+}
+
+#[test]
+fn minimal_signature() {
+    // B.2.1
+    assert_eq!(
+        base(
+            &request_headers(),
+            r#"();created=1618884473;keyid="test-key-rsa-pss";nonce="b3k2pp5k7z-50gnwp.yemd""#
+        ),
+        "\"@signature-params\": ();created=1618884473;keyid=\"test-key-rsa-pss\";\
+         nonce=\"b3k2pp5k7z-50gnwp.yemd\""
+    );
+}
+
+#[test]
+fn selective_covered_components() {
+    // B.2.2
+    assert_eq!(
+        base(
+            &request_headers(),
+            r#"("@authority" "content-digest" "@query-param";name="Pet");created=1618884473;keyid="test-key-rsa-pss";tag="header-example""#
+        ),
+        format!(
+            "\"@authority\": example.com\n\
+             \"content-digest\": {CONTENT_DIGEST}\n\
+             \"@query-param\";name=\"Pet\": dog\n\
+             \"@signature-params\": (\"@authority\" \"content-digest\" \"@query-param\";name=\"Pet\")\
+             ;created=1618884473;keyid=\"test-key-rsa-pss\";tag=\"header-example\""
+        )
+    );
+}
+
+#[test]
+fn full_coverage() {
+    // B.2.3
+    assert_eq!(
+        base(
+            &request_headers(),
+            r#"("date" "@method" "@path" "@query" "@authority" "content-type" "content-digest" "content-length");created=1618884473;keyid="test-key-rsa-pss""#
+        ),
+        format!(
+            "\"date\": Tue, 20 Apr 2021 02:07:55 GMT\n\
+             \"@method\": POST\n\
+             \"@path\": /foo\n\
+             \"@query\": ?param=Value&Pet=dog\n\
+             \"@authority\": example.com\n\
+             \"content-type\": application/json\n\
+             \"content-digest\": {CONTENT_DIGEST}\n\
+             \"content-length\": 18\n\
+             \"@signature-params\": (\"date\" \"@method\" \"@path\" \"@query\" \"@authority\" \
+             \"content-type\" \"content-digest\" \"content-length\");created=1618884473;\
+             keyid=\"test-key-rsa-pss\""
+        )
+    );
+}
+
+#[test]
+fn hmac_sha256_signature() {
+    // B.2.5
+    let key = HmacSha256Key::from_base64(SHARED_SECRET).unwrap();
+    let headers = request_headers();
+    let message = HttpMessage::request("POST", TARGET_URI, &headers);
+    let params = SignatureParams::parse(
+        r#"("date" "@authority" "content-type");created=1618884473;keyid="test-shared-secret""#,
+    )
+    .unwrap();
+    assert_eq!(
+        signature_base(&message, &params).unwrap(),
+        "\"date\": Tue, 20 Apr 2021 02:07:55 GMT\n\
+         \"@authority\": example.com\n\
+         \"content-type\": application/json\n\
+         \"@signature-params\": (\"date\" \"@authority\" \"content-type\");created=1618884473;\
+         keyid=\"test-shared-secret\""
+    );
+
+    let signature = sign(&message, "sig-b25", &params, &key).unwrap();
+    assert_eq!(
+        signature.get("Signature").unwrap().value,
+        "sig-b25=:pxcQw6G3AjtMBQjwo8XzkZf/bws5LelbaMk5rGIGtE8=:"
+    );
+
+    let mut signed = request_headers();
+    signed.elements.extend(signature.elements);
+    let message = HttpMessage::request("POST", TARGET_URI, &signed);
+    assert_eq!(verify(&message, "sig-b25", &key).unwrap(), params);
+    assert_eq!(
+        verify(&message, "sig-b25", &HmacSha256Key::new(b"other")).unwrap_err(),
+        "Signature verification failed"
+    );
+    assert_eq!(
+        verify(&message, "sig1", &key).unwrap_err(),
+        "Signature label not found"
+    );
+}
+
+#[test]
+fn ecdsa_p256_response() {
+    // B.2.4
+    let headers = [
+        ("Date", "Tue, 20 Apr 2021 02:07:56 GMT"),
+        ("Content-Type", "application/json"),
+        (
+            "Content-Digest",
+            "sha-512=:mEWXIS7MaLRuGgxOBdODa3xqM1XdEvxoYhvlCFJ41QJgJc4GTsPp29l5oGX69wWdXym\
+             yU0rjJuahq4l5aGgfLQ==:",
+        ),
+        ("Content-Length", "23"),
+    ]
+    .into_iter()
+    .collect::<MultiDict>();
+    check_published(
+        HttpMessage::response(200, &headers),
+        "sig-b24",
+        r#"("@status" "content-type" "content-digest" "content-length");created=1618884473;keyid="test-key-ecc-p256""#,
+        "\"@status\": 200\n\
+         \"content-type\": application/json\n\
+         \"content-digest\": sha-512=:mEWXIS7MaLRuGgxOBdODa3xqM1XdEvxoYhvlCFJ41QJgJc4GTsPp29l5oGX\
+         69wWdXymyU0rjJuahq4l5aGgfLQ==:\n\
+         \"content-length\": 23\n\
+         \"@signature-params\": (\"@status\" \"content-type\" \"content-digest\" \
+         \"content-length\");created=1618884473;keyid=\"test-key-ecc-p256\"",
+        "wNmSUAhwb5LxtOtOpNa6W5xj067m5hFrj0XQ4fvpaCLx0NKocgPquLgyahnzDnDAUy5eCdlYUEkLIj+32oiasw==",
+    );
+}
+
+#[test]
+fn ed25519_signature() {
+    // B.2.6
+    let headers = request_headers();
+    check_published(
+        HttpMessage::request("POST", TARGET_URI, &headers),
+        "sig-b26",
+        r#"("date" "@method" "@path" "@authority" "content-type" "content-length");created=1618884473;keyid="test-key-ed25519""#,
+        "\"date\": Tue, 20 Apr 2021 02:07:55 GMT\n\
+         \"@method\": POST\n\
+         \"@path\": /foo\n\
+         \"@authority\": example.com\n\
+         \"content-type\": application/json\n\
+         \"content-length\": 18\n\
+         \"@signature-params\": (\"date\" \"@method\" \"@path\" \"@authority\" \
+         \"content-type\" \"content-length\");created=1618884473;keyid=\"test-key-ed25519\"",
+        "wqcAqbmYJ2ji2glfAMaRy4gruYYnx2nEFN2HN6jrnDnQCK1u02Gb04v9EDgwUPiu4A0w6vuQv5lIp5WPpBKRCw==",
+    );
+}
+
+#[test]
+fn invalid_components() {
+    let headers = request_headers();
+    for (params, error) in [
+        (r#"("x-missing")"#, "Covered field not found"),
+        (r#"("date" "date")"#, "Component is covered more than once"),
+        (r#"("Date")"#, "Invalid component name"),
+        (r#"("@signature-params")"#, "Invalid component name"),
+        (r#"("@status")"#, "Missing response status"),
+        (
+            r#"("date";req)"#,
+            "req and tr component parameters are not supported",
+        ),
+        (
+            r#"("date";bs;sf)"#,
+            "bs parameter can not be combined with sf or key",
+        ),
+        (
+            r#"("@query-param")"#,
+            "@query-param requires name parameter",
+        ),
+    ] {
+        let message = HttpMessage::request("POST", TARGET_URI, &headers);
+        let params = SignatureParams::parse(params).unwrap();
+        assert_eq!(signature_base(&message, &params), Err(error));
+    }
+}
@@ -0,0 +1,91 @@
+//! Metadata is carried through the core `MultiDict` operations and filled in by parsers.
+use multidict::metadata::{Metadata, Span};
+use multidict::{MultiDict, MultiElement};
+
+fn origin(key: &str, value: &str, layer: &str) -> MultiElement<Metadata> {
+    MultiElement {
+        key: key.to_string(),
+        value: value.to_string(),
+        meta: Some(Metadata {
+            origin: Some(layer.to_string()),
+            ..Default::default()
+        }),
+    }
+}
+
+fn layer(item: &MultiElement<Metadata>) -> &str {
+    item.meta.as_ref().unwrap().origin.as_deref().unwrap()
+}
+
+#[test]
+fn metadata_kept_by_operations() {
+    let mut map = MultiDict::default();
+    map.add(origin("a", "1", "defaults"));
+    map.add(origin("b", "2", "defaults"));
+    map.add(origin("a", "3", "user"));
+
+    let layers: Vec<&str> = map.iter().map(layer).collect();
+    assert_eq!(layers, vec!["defaults", "defaults", "user"]);
+
+    let all = map.getall("a").unwrap();
+    assert_eq!(
+        all.iter().map(layer).collect::<Vec<_>>(),
+        vec!["defaults", "user"]
+    );
+
+    map.update(origin("b", "4", "override"));
+    assert_eq!(layer(map.get("b").unwrap()), "override");
+    map.update(MultiElement {
+        key: "b".to_string(),
+        value: "5".to_string(),
+        meta: None,
+    });
+    assert_eq!(map.get("b").unwrap().value, "5");
+    assert_eq!(layer(map.get("b").unwrap()), "override");
+
+    let popped = map.popone("a").unwrap();
+    assert_eq!(layer(&popped), "defaults");
+    assert_eq!(layer(map.get("a").unwrap()), "user");
+}
+
+#[test]
+fn sensitive_metadata_is_redacted() {
+    let mut map = MultiDict::default();
+    map.add(MultiElement {
+        key: "password".to_string(),
+        value: "hunter2".to_string(),
+        meta: Some(Metadata {
+            sensitive: true,
+            ..Default::default()
+        }),
+    });
+    map.add(origin("user", "alice", "form"));
+    assert_eq!(
+        map.to_string(),
+        r#"MultiDict < "password":"***", "user":"alice" >"#
+    );
+    assert!(!format!("{map:?}").contains("hunter2"));
+    assert_eq!(map.redacted().get("password").unwrap().value, "***");
+    assert_eq!(map.get("password").unwrap().value, "hunter2");
+}
+
+#[test]
+fn query_string_spans() {
+    let query = "?a=1&&b=x+y&a=%C3%A9&flag";
+    let map: MultiDict<Span> = MultiDict::from_query_string_spanned(query).unwrap();
+    let spans: Vec<&str> = map.iter().map(|item| item.meta.unwrap().slice(query)).collect();
+    assert_eq!(spans, vec!["a=1", "b=x+y", "a=%C3%A9", "flag"]);
+
+    let map: MultiDict<Metadata> = MultiDict::from_query_string_spanned("x=1").unwrap();
+    assert_eq!(
+        map.get("x").unwrap().meta,
+        Some(Metadata {
+            span: Some(Span { start: 0, end: 3 }),
+            ..Default::default()
+        })
+    );
+    assert_eq!(
+        map.without_meta().to_string(),
+        MultiDict::from_query_string("x=1").unwrap().to_string()
+    );
+}
@@ -0,0 +1,131 @@
+//! MQTT 5 variable byte integers and user property sections as byte fixtures.
+mod common;
+
+use common::bytes;
+use multidict::mqtt::{
+    decode_variable_byte_integer, encode_variable_byte_integer, packet_size, MAXIMUM_PACKET_SIZE,
+};
+use multidict::MultiDict;
+
+#[test]
+fn variable_byte_integers() {
+    // boundaries of MQTT 5 section 1.5.5
+    for (value, encoded) in [
+        (0, "00"),
+        (127, "7f"),
+        (128, "8001"),
+        (16_383, "ff7f"),
+        (16_384, "808001"),
+        (2_097_151, "ffff7f"),
+        (2_097_152, "80808001"),
+        (268_435_455, "ffffff7f"),
+    ] {
+        let mut out = Vec::new();
+        encode_variable_byte_integer(value, &mut out).unwrap();
+        assert_eq!(out, bytes(encoded));
+        let mut position = 0;
+        assert_eq!(decode_variable_byte_integer(&out, &mut position), Ok(value));
+        assert_eq!(position, out.len());
+    }
+    let mut position = 0;
+    assert_eq!(
+        decode_variable_byte_integer(&bytes("ffffffff01"), &mut position),
+        Err("Malformed variable byte integer")
+    );
+    let mut position = 0;
+    assert_eq!(
+        decode_variable_byte_integer(&bytes("ff"), &mut position),
+        Err("Truncated variable byte integer")
+    );
+}
+
+#[test]
+fn user_property_section() {
+    let map = [("region", "eu"), ("sensor", "t1"), ("sensor", "t2")]
+        .into_iter()
+        .collect::<MultiDict>();
+    let section = bytes(
+        "27
+         26 0006 726567696f6e 0002 6575
+         26 0006 73656e736f72 0002 7431
+         26 0006 73656e736f72 0002 7432",
+    );
+    assert_eq!(map.to_mqtt_properties().unwrap(), section);
+    let (decoded, length) = MultiDict::from_mqtt_properties(&section).unwrap();
+    assert_eq!(decoded.to_string(), map.to_string());
+    assert_eq!(length, section.len());
+
+    assert_eq!(MultiDict::new().to_mqtt_properties().unwrap(), bytes("00"));
+    let (decoded, length) = MultiDict::from_mqtt_properties(&bytes("00 30")).unwrap();
+    assert!(decoded.is_empty());
+    assert_eq!(length, 1);
+}
+
+#[test]
+fn long_section_uses_multi_byte_length() {
+    let value = "x".repeat(200);
+    let map = [("k", value.as_str()), ("k", "")]
+        .into_iter()
+        .collect::<MultiDict>();
+    let section = map.to_mqtt_properties().unwrap();
+    // 206 + 6 bytes of properties
+    assert_eq!(section[..2], bytes("d401"));
+    let (decoded, _) = MultiDict::from_mqtt_properties(&section).unwrap();
+    assert_eq!(decoded.values(), vec![&value, ""]);
+}
+
+#[test]
+fn maximum_packet_size() {
+    let map = [("a", "1")].into_iter().collect::<MultiDict>();
+    // 8 byte section, 118 other bytes: remaining length 126, packet 128
+    assert!(map.to_mqtt_properties_limited(118, 128).is_ok());
+    // remaining length 128 needs a two byte length: packet 131
+    assert_eq!(
+        map.to_mqtt_properties_limited(120, 130).unwrap_err(),
+        "MQTT packet exceeds maximum packet size"
+    );
+    assert!(map.to_mqtt_properties_limited(120, 131).is_ok());
+    assert_eq!(
+        packet_size(&bytes("30ffffff7f"), MAXIMUM_PACKET_SIZE),
+        Ok(MAXIMUM_PACKET_SIZE as usize)
+    );
+    assert_eq!(
+        packet_size(&bytes("30"), MAXIMUM_PACKET_SIZE),
+        Err("Truncated variable byte integer")
+    );
+}
+
+#[test]
+fn malformed_sections() {
+    let cases = [
+        ("05 26 0001 61", "Truncated MQTT property section"),
+        ("03 26 0001", "Truncated MQTT property"),
+        ("07 26 0001 61 0001 00", "Invalid MQTT UTF-8 string"),
+        ("07 26 0001 61 0001 ff", "Invalid MQTT UTF-8 string"),
+        ("02 7f 00", "Unknown MQTT property identifier"),
+        ("02 02 00", "Truncated MQTT property"),
+    ];
+    for (input, error) in cases {
+        assert_eq!(
+            MultiDict::from_mqtt_properties(&bytes(input)).unwrap_err(),
+            error,
+            "{input}"
+        );
+    }
+    assert_eq!(
+        [("a", "\u{0}")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_mqtt_properties()
+            .unwrap_err(),
+        "MQTT string contains U+0000"
+    );
+    assert_eq!(
+        [("a", &"x".repeat(65_536))]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_mqtt_properties()
+            .unwrap_err(),
+        "MQTT string is too long"
+    );
+}
@@ -0,0 +1,147 @@
+//! NATS header blocks of captured `HMSG` frames, no server involved.
+use multidict::metadata::Span;
+use multidict::nats::NatsStatus;
+use multidict::MultiDict;
+
+/// Return header block and payload of an `HMSG` frame, checking its lengths
+fn split_hmsg(frame: &[u8]) -> (&[u8], &[u8]) {
+    let end = frame.windows(2).position(|pair| pair == b"\r\n").unwrap();
+    let line = std::str::from_utf8(&frame[..end]).unwrap();
+    let fields: Vec<&str> = line.split(' ').collect();
+    assert_eq!(fields[0], "HMSG");
+    let header_length: usize = fields[fields.len() - 2].parse().unwrap();
+    let total_length: usize = fields[fields.len() - 1].parse().unwrap();
+    let body = &frame[end + 2..];
+    assert_eq!(body.len(), total_length + 2);
+    assert!(body.ends_with(b"\r\n"));
+    (&body[..header_length], &body[header_length..total_length])
+}
+
+#[test]
+fn message_with_headers() {
+    let frame =
+        b"HMSG FOO.BAR 9 BAZ.69 34 45\r\nNATS/1.0\r\nFoodGroup: vegetable\r\n\r\nHello World\r\n";
+    let (block, payload) = split_hmsg(frame);
+    let (decoded, status) = MultiDict::from_nats_headers(block).unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [("FoodGroup", "vegetable")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(status, None);
+    assert_eq!(payload, b"Hello World");
+    assert_eq!(decoded.to_nats_headers(None).unwrap(), block);
+}
+
+#[test]
+fn repeated_headers() {
+    let frame = b"HMSG SUBJECT 1 REPLY 47 47\r\nNATS/1.0\r\nBREAKFAST: donut\r\nBREAKFAST: eggs\r\n\r\n\r\n";
+    let (block, payload) = split_hmsg(frame);
+    let (decoded, _) = MultiDict::from_nats_headers(block).unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [("BREAKFAST", "donut"), ("BREAKFAST", "eggs")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert!(payload.is_empty());
+    assert_eq!(decoded.to_nats_headers(None).unwrap(), block);
+}
+
+#[test]
+fn status_lines() {
+    let no_responders = b"HMSG _INBOX.x 1 16 16\r\nNATS/1.0 503\r\n\r\n\r\n";
+    let (block, _) = split_hmsg(no_responders);
+    let (decoded, status) = MultiDict::from_nats_headers(block).unwrap();
+    assert!(decoded.is_empty());
+    assert_eq!(status, Some(NatsStatus::new(503)));
+    assert_eq!(decoded.to_nats_headers(status.as_ref()).unwrap(), block);
+
+    let heartbeat = b"HMSG hb 2 75 75\r\nNATS/1.0 100 Idle Heartbeat\r\n\
+Nats-Last-Consumer: 0\r\nNats-Last-Stream: 0\r\n\r\n\r\n";
+    let (block, _) = split_hmsg(heartbeat);
+    let (decoded, status) = MultiDict::from_nats_headers(block).unwrap();
+    let status = status.unwrap();
+    assert_eq!(status.to_string(), "100 Idle Heartbeat");
+    assert_eq!(decoded.get("Nats-Last-Stream").unwrap().value, "0");
+    assert_eq!(decoded.to_nats_headers(Some(&status)).unwrap(), block);
+}
+
+#[test]
+fn case_and_whitespace() {
+    let (decoded, _) =
+        MultiDict::from_nats_headers(b"NATS/1.0\r\nkey:  spaced value \r\nKey:x\r\n\r\n").unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [("key", "spaced value"), ("Key", "x")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+}
+
+#[test]
+fn malformed_blocks() {
+    for (block, error) in [
+        (
+            &b"NATS/1.0\r\nA: b\r\n"[..],
+            "NATS header block does not end with an empty line",
+        ),
+        (b"NATS/2.0\r\n\r\n", "Unsupported NATS header version"),
+        (b"NATS/1.0503\r\n\r\n", "Unsupported NATS header version"),
+        (b"NATS/1.0 50\r\n\r\n", "Invalid NATS status code"),
+        (b"NATS/1.0\r\nno colon\r\n\r\n", "NATS header has no colon"),
+        (
+            b"NATS/1.0\r\nbad name: x\r\n\r\n",
+            "Invalid NATS header name",
+        ),
+        (
+            b"NATS/1.0\r\nA: \xff\r\n\r\n",
+            "NATS headers are not valid UTF-8",
+        ),
+    ] {
+        assert_eq!(MultiDict::from_nats_headers(block).unwrap_err(), error);
+    }
+    assert_eq!(
+        [("A", "line\nbreak")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_nats_headers(None)
+            .unwrap_err(),
+        "Invalid NATS header value"
+    );
+    assert_eq!(
+        [("A:B", "x")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_nats_headers(None)
+            .unwrap_err(),
+        "Invalid NATS header name"
+    );
+    assert_eq!(
+        MultiDict::new()
+            .to_nats_headers(Some(&NatsStatus::new(42)))
+            .unwrap_err(),
+        "Invalid NATS status code"
+    );
+}
+
+#[test]
+fn spans_of_header_lines() {
+    let block = b"NATS/1.0 503\r\nNats-Msg-Id: 7\r\nNats-Expected-Last-Sequence: 4x\r\n\r\n";
+    let (headers, status) = MultiDict::<Span>::from_nats_headers_spanned(block).unwrap();
+    assert_eq!(status, Some(NatsStatus::new(503)));
+    let invalid = headers
+        .iter()
+        .find(|item| item.value.parse::<u64>().is_err())
+        .unwrap();
+    assert_eq!(invalid.meta, Some(Span { start: 30, end: 61 }));
+    assert_eq!(&block[30..61], b"Nats-Expected-Last-Sequence: 4x");
+    assert_eq!(
+        headers.get("Nats-Msg-Id").unwrap().meta,
+        Some(Span { start: 14, end: 28 })
+    );
+}
@@ -0,0 +1,136 @@
+//! OpenAPI 3 style table: every style and explode combination for `color`.
+use multidict::openapi::ParameterStyle;
+use multidict::MultiDict;
+
+use ParameterStyle::{Form, Label, Matrix, PipeDelimited, SpaceDelimited};
+
+/// style, explode, empty, string, array
+const ARRAYS: &[(ParameterStyle, bool, &str, &str, &str)] = &[
+    (Matrix, false, ";color", ";color=blue", ";color=blue,black"),
+    (
+        Matrix,
+        true,
+        ";color",
+        ";color=blue",
+        ";color=blue;color=black",
+    ),
+    (Label, false, ".", ".blue", ".blue,black"),
+    (Label, true, ".", ".blue", ".blue.black"),
+    (Form, false, "color=", "color=blue", "color=blue,black"),
+    (Form, true, "color=", "color=blue", "color=blue&color=black"),
+    (
+        SpaceDelimited,
+        false,
+        "color=",
+        "color=blue",
+        "color=blue%20black",
+    ),
+    (
+        SpaceDelimited,
+        true,
+        "color=",
+        "color=blue",
+        "color=blue&color=black",
+    ),
+    (
+        PipeDelimited,
+        false,
+        "color=",
+        "color=blue",
+        "color=blue|black",
+    ),
+    (
+        PipeDelimited,
+        true,
+        "color=",
+        "color=blue",
+        "color=blue&color=black",
+    ),
+];
+
+/// style, explode, object
+const OBJECTS: &[(ParameterStyle, bool, &str)] = &[
+    (Matrix, false, ";color=R,100,G,200,B,150"),
+    (Matrix, true, ";R=100;G=200;B=150"),
+    (Label, false, ".R,100,G,200,B,150"),
+    (Label, true, ".R=100.G=200.B=150"),
+    (Form, false, "color=R,100,G,200,B,150"),
+    (Form, true, "R=100&G=200&B=150"),
+    (SpaceDelimited, false, "color=R%20100%20G%20200%20B%20150"),
+    (PipeDelimited, false, "color=R|100|G|200|B|150"),
+];
+
+fn color(values: &[&str]) -> MultiDict {
+    let pairs: Vec<String> = values.iter().map(|value| format!("color={value}")).collect();
+    MultiDict::from_query_string(&pairs.join("&")).unwrap()
+}
+
+#[test]
+fn array_parameters() {
+    for &(style, explode, empty, string, array) in ARRAYS {
+        for (values, expected) in [
+            (vec![""], empty),
+            (vec!["blue"], string),
+            (vec!["blue", "black"], array),
+        ] {
+            let map = color(&values);
+            let written = map.to_openapi_parameter("color", style, explode).unwrap();
+            assert_eq!(written, expected, "{style:?} explode={explode}");
+            let parsed =
+                MultiDict::from_openapi_parameter("color", &written, style, explode).unwrap();
+            assert_eq!(parsed.values(), values, "{style:?} explode={explode}");
+        }
+    }
+}
+
+#[test]
+fn object_parameters() {
+    let map = MultiDict::from_query_string("R=100&G=200&B=150").unwrap();
+    for &(style, explode, expected) in OBJECTS {
+        let written = map.to_openapi_object("color", style, explode).unwrap();
+        assert_eq!(written, expected, "{style:?} explode={explode}");
+        let parsed = MultiDict::from_openapi_object("color", &written, style, explode).unwrap();
+        assert_eq!(
+            parsed.to_string(),
+            map.to_string(),
+            "{style:?} explode={explode}"
+        );
+    }
+    for style in [SpaceDelimited, PipeDelimited] {
+        assert_eq!(
+            map.to_openapi_object("color", style, true),
+            Err("Style does not support exploded objects")
+        );
+    }
+}
+
+#[test]
+fn label_escapes_dots() {
+    let map = color(&["1.5", "2"]);
+    for (explode, expected) in [(true, ".1%2E5.2"), (false, ".1%2E5,2")] {
+        let written = map.to_openapi_parameter("color", Label, explode).unwrap();
+        assert_eq!(written, expected);
+        assert_eq!(
+            MultiDict::from_openapi_parameter("color", &written, Label, explode)
+                .unwrap()
+                .values(),
+            vec!["1.5", "2"]
+        );
+    }
+    let object = MultiDict::from_query_string("v1.0=1.5&x=2").unwrap();
+    for (explode, expected) in [(true, ".v1%2E0=1%2E5.x=2"), (false, ".v1%2E0,1%2E5,x,2")] {
+        let written = object.to_openapi_object("version", Label, explode).unwrap();
+        assert_eq!(written, expected);
+        assert_eq!(
+            MultiDict::from_openapi_object("version", &written, Label, explode)
+                .unwrap()
+                .to_string(),
+            object.to_string()
+        );
+    }
+    // other styles keep dots as they are
+    assert_eq!(
+        map.to_openapi_parameter("color", Matrix, false).unwrap(),
+        ";color=1.5,2"
+    );
+}
@@ -0,0 +1,256 @@
+//! Examples of RFC 9204 Appendix B and in memory encoder/decoder loopback tests.
+//!
+//! The encoder never blocks streams, so its output differs from the appendix from
+//! B.2 on; those examples are checked on the decoder side only.
+mod common;
+
+use common::bytes;
+use multidict::qpack::{Decoder, Encoder};
+use multidict::MultiDict;
+
+#[test]
+fn literal_field_line_with_name_reference() {
+    let section = bytes("0000 510b 2f69 6e64 6578 2e68 746d 6c");
+    let list = [(":path", "/index.html")].into_iter().collect::<MultiDict>();
+
+    let mut decoder = Decoder::default();
+    let decoded = decoder.decode(0, &section).unwrap().unwrap();
+    assert_eq!(decoded.to_string(), list.to_string());
+    assert!(decoder.decoder_stream().is_empty());
+
+    let mut encoder = Encoder::default();
+    encoder.set_huffman(false);
+    assert_eq!(encoder.encode(0, &list), section);
+    assert!(encoder.encoder_stream().is_empty());
+}
+
+#[test]
+fn dynamic_table_walkthrough() {
+    let mut decoder = Decoder::new(220, 1);
+
+    // B.2 Dynamic Table
+    let unblocked = decoder
+        .receive_encoder_stream(&bytes(
+            "3fbd01 c00f 7777 772e 6578 616d 706c 652e 636f 6d
+             c10c 2f73 616d 706c 652f 7061 7468",
+        ))
+        .unwrap();
+    assert!(unblocked.is_empty());
+    let decoded = decoder.decode(4, &bytes("0381 10 11")).unwrap().unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [(":authority", "www.example.com"), (":path", "/sample/path")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(decoder.decoder_stream(), bytes("84"));
+    assert_eq!(decoder.table_size(), 106);
+
+    // B.3 Speculative Insert
+    decoder
+        .receive_encoder_stream(&bytes(
+            "4a63 7573 746f 6d2d 6b65 790c 6375 7374 6f6d 2d76 616c 7565",
+        ))
+        .unwrap();
+    assert_eq!(decoder.decoder_stream(), bytes("01"));
+    assert_eq!(decoder.table_size(), 160);
+
+    // B.4 Duplicate Instruction, Stream Cancellation
+    decoder.receive_encoder_stream(&bytes("02")).unwrap();
+    let decoded = decoder.decode(8, &bytes("0500 80 c1 81")).unwrap().unwrap();
+    assert_eq!(
+        decoded.to_string(),
+        [
+            (":authority", "www.example.com"),
+            (":path", "/"),
+            ("custom-key", "custom-value"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    assert_eq!(decoder.decoder_stream(), bytes("88"));
+    assert_eq!(decoder.table_size(), 217);
+
+    // B.5 Dynamic Table Insert, Eviction
+    decoder
+        .receive_encoder_stream(&bytes("810d 6375 7374 6f6d 2d76 616c 7565 32"))
+        .unwrap();
+    assert_eq!(decoder.table_size(), 215);
+    assert_eq!(
+        decoder.dynamic_table().to_string(),
+        [
+            (":path", "/sample/path"),
+            ("custom-key", "custom-value"),
+            (":authority", "www.example.com"),
+            ("custom-key", "custom-value2"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+}
+
+#[test]
+fn blocked_streams() {
+    let encoder_stream = bytes(
+        "3fbd01 c00f 7777 772e 6578 616d 706c 652e 636f 6d
+         c10c 2f73 616d 706c 652f 7061 7468",
+    );
+    let section = bytes("0381 10 11");
+
+    let mut decoder = Decoder::new(220, 0);
+    assert_eq!(
+        decoder.decode(4, &section).unwrap_err(),
+        "Too many blocked streams"
+    );
+
+    let mut decoder = Decoder::new(220, 1);
+    assert!(decoder.decode(4, &section).unwrap().is_none());
+    decoder.cancel_stream(4);
+    assert_eq!(decoder.decoder_stream(), bytes("44"));
+    assert!(decoder
+        .receive_encoder_stream(&encoder_stream)
+        .unwrap()
+        .is_empty());
+
+    let mut decoder = Decoder::new(220, 1);
+    assert!(decoder.decode(4, &section).unwrap().is_none());
+    // encoder stream split at arbitrary points
+    let mut unblocked = Vec::new();
+    for chunk in encoder_stream.chunks(3) {
+        unblocked.extend(decoder.receive_encoder_stream(chunk).unwrap());
+    }
+    assert_eq!(unblocked.len(), 1);
+    assert_eq!(unblocked[0].stream_id, 4);
+    assert_eq!(unblocked[0].never_indexed, vec![false, false]);
+    assert_eq!(decoder.decoder_stream(), bytes("84"));
+}
+
+const REQUEST: &[(&str, &str)] = &[
+    (":method", "GET"),
+    (":scheme", "https"),
+    (":authority", "www.example.com"),
+    (":path", "/index.html"),
+    ("user-agent", "multidict"),
+    ("x-request-id", "42"),
+    ("cookie", "a=1"),
+    ("cookie", "b=2"),
+];
+
+#[test]
+fn loopback_uses_acknowledged_entries() {
+    let list = REQUEST.iter().copied().collect::<MultiDict>();
+    let mut encoder = Encoder::new(4096);
+    let mut decoder = Decoder::new(4096, 0);
+
+    let first = encoder.encode(0, &list);
+    decoder
+        .receive_encoder_stream(&encoder.encoder_stream())
+        .unwrap();
+    let decoded = decoder.decode(0, &first).unwrap().unwrap();
+    assert_eq!(decoded.to_string(), list.to_string());
+    encoder
+        .receive_decoder_stream(&decoder.decoder_stream())
+        .unwrap();
+
+    let second = encoder.encode(4, &list);
+    assert!(second.len() < first.len());
+    assert!(encoder.encoder_stream().is_empty());
+    let decoded = decoder.decode(4, &second).unwrap().unwrap();
+    assert_eq!(decoded.to_string(), list.to_string());
+    encoder
+        .receive_decoder_stream(&decoder.decoder_stream())
+        .unwrap();
+    assert_eq!(
+        encoder.dynamic_table().to_string(),
+        decoder.dynamic_table().to_string()
+    );
+    assert_eq!(encoder.table_size(), decoder.table_size());
+}
+
+#[test]
+fn loopback_never_indexed() {
+    let list = [("authorization", "secret"), ("x-token", "secret")]
+        .into_iter()
+        .collect::<MultiDict>();
+    let mut encoder = Encoder::new(4096);
+    let mut decoder = Decoder::new(4096, 0);
+    let section = encoder.encode_flagged(0, &list, &[true, true]).unwrap();
+    decoder
+        .receive_encoder_stream(&encoder.encoder_stream())
+        .unwrap();
+    let decoded = decoder.decode_flagged(0, &section).unwrap().unwrap();
+    assert_eq!(decoded.headers.to_string(), list.to_string());
+    assert_eq!(decoded.never_indexed, vec![true, true]);
+    assert_eq!(decoder.table_size(), 0);
+}
+
+#[test]
+fn loopback_eviction_with_late_acknowledgments() {
+    let mut encoder = Encoder::new(200);
+    let mut decoder = Decoder::new(200, 0);
+    let mut pending = Vec::new();
+    for round in 0..40u64 {
+        let list = [
+            (":method", "POST"),
+            ("x-round", &(round % 7).to_string()),
+            ("x-trace", &format!("trace-{}", round % 3)),
+            ("content-type", "application/json"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>();
+        let section = encoder.encode(round * 4, &list);
+        pending.push((round * 4, section, list));
+        decoder
+            .receive_encoder_stream(&encoder.encoder_stream())
+            .unwrap();
+        // decoder stream reaches the encoder only every third section
+        if round % 3 == 2 {
+            for (stream_id, section, list) in pending.drain(..) {
+                let decoded = decoder.decode(stream_id, &section).unwrap().unwrap();
+                assert_eq!(decoded.to_string(), list.to_string());
+            }
+            encoder
+                .receive_decoder_stream(&decoder.decoder_stream())
+                .unwrap();
+            assert_eq!(
+                encoder.dynamic_table().to_string(),
+                decoder.dynamic_table().to_string()
+            );
+        }
+        assert!(encoder.table_size() <= 200);
+    }
+}
+
+#[test]
+fn malformed_input() {
+    let mut decoder = Decoder::new(4096, 0);
+    assert_eq!(
+        decoder.decode(0, &bytes("0000 ff40")).unwrap_err(),
+        "Static table index out of range"
+    );
+    assert_eq!(
+        decoder.decode(0, &bytes("0000 80")).unwrap_err(),
+        "Dynamic table reference out of range"
+    );
+    assert_eq!(
+        decoder.decode(0, &bytes("ff02 00")).unwrap_err(),
+        "Invalid required insert count"
+    );
+    assert_eq!(
+        decoder.receive_encoder_stream(&bytes("00")).unwrap_err(),
+        "Dynamic table index out of range"
+    );
+    assert_eq!(
+        decoder.receive_encoder_stream(&bytes("c0 00")).unwrap_err(),
+        "Dynamic table entry exceeds capacity"
+    );
+
+    let mut encoder = Encoder::new(4096);
+    assert_eq!(
+        encoder.receive_decoder_stream(&bytes("84")).unwrap_err(),
+        "Section acknowledgment for unknown stream"
+    );
+}
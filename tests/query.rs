@@ -0,0 +1,149 @@
+//! Nested query string round trips for every nesting style.
+use multidict::query::{NestedStyle, NestedValue};
+
+fn leaf(values: &[&str]) -> NestedValue {
+    NestedValue::Leaf(values.iter().map(|value| value.to_string()).collect())
+}
+
+fn list(values: &[&str]) -> NestedValue {
+    NestedValue::List(values.iter().map(|value| value.to_string()).collect())
+}
+
+fn node(children: Vec<(&str, NestedValue)>) -> NestedValue {
+    NestedValue::Node(
+        children
+            .into_iter()
+            .map(|(name, child)| (name.to_string(), child))
+            .collect(),
+    )
+}
+
+fn round_trip(query: &str, style: NestedStyle) -> NestedValue {
+    let tree = NestedValue::from_query_string(query, style).unwrap();
+    let written = tree.to_query_string(style);
+    assert_eq!(
+        NestedValue::from_query_string(&written, style).unwrap(),
+        tree,
+        "{query} was written as {written}"
+    );
+    tree
+}
+
+#[test]
+fn brackets() {
+    let tree = round_trip(
+        "a[]=1&b=2&c[]=3&c[]=4&e[]=&user[name]=x&user[tags][]=t&user[address][city]=y",
+        NestedStyle::Brackets,
+    );
+    assert_eq!(
+        tree,
+        node(vec![
+            ("a", list(&["1"])),
+            ("b", leaf(&["2"])),
+            ("c", list(&["3", "4"])),
+            ("e", list(&[""])),
+            (
+                "user",
+                node(vec![
+                    ("name", leaf(&["x"])),
+                    ("tags", list(&["t"])),
+                    ("address", node(vec![("city", leaf(&["y"]))])),
+                ])
+            ),
+        ])
+    );
+    assert_eq!(
+        tree.to_multidict(NestedStyle::Brackets).keys(),
+        vec![
+            "a[]",
+            "b",
+            "c[]",
+            "c[]",
+            "e[]",
+            "user[name]",
+            "user[tags][]",
+            "user[address][city]"
+        ]
+    );
+    // repeated scalar keys stay without brackets
+    assert_eq!(
+        round_trip("a=1&a=2", NestedStyle::Brackets).to_query_string(NestedStyle::Brackets),
+        "a=1&a=2"
+    );
+    assert_eq!(
+        NestedValue::from_query_string("a[]=1&a=2", NestedStyle::Brackets),
+        Err("Conflicting nested key")
+    );
+}
+
+#[test]
+fn deep_object() {
+    let tree = round_trip(
+        "id=1&filter[name]=x&filter[tag]=a&filter[tag]=b&filter[range][min]=1",
+        NestedStyle::DeepObject,
+    );
+    assert_eq!(
+        tree,
+        node(vec![
+            ("id", leaf(&["1"])),
+            (
+                "filter",
+                node(vec![
+                    ("name", leaf(&["x"])),
+                    ("tag", leaf(&["a", "b"])),
+                    ("range", node(vec![("min", leaf(&["1"]))])),
+                ])
+            ),
+        ])
+    );
+    // lists are repeated keys, a single value reads back as a leaf
+    let tree = node(vec![("ids", list(&["1"]))]);
+    assert_eq!(tree.to_query_string(NestedStyle::DeepObject), "ids=1");
+}
+
+#[test]
+fn dotted() {
+    let tree = round_trip(
+        "user.name=x&user.tag=a&user.tag=b&user.address.city=y&page=",
+        NestedStyle::Dotted,
+    );
+    assert_eq!(
+        tree,
+        node(vec![
+            (
+                "user",
+                node(vec![
+                    ("name", leaf(&["x"])),
+                    ("tag", leaf(&["a", "b"])),
+                    ("address", node(vec![("city", leaf(&["y"]))])),
+                ])
+            ),
+            ("page", leaf(&[""])),
+        ])
+    );
+    let tree = node(vec![("ids", list(&["1", "2"]))]);
+    assert_eq!(tree.to_query_string(NestedStyle::Dotted), "ids=1&ids=2");
+}
+
+#[test]
+fn empty_lists_are_not_written() {
+    let tree = node(vec![
+        ("a", list(&[])),
+        ("b", node(vec![("c", list(&[])), ("d", list(&["1"]))])),
+    ]);
+    for style in [
+        NestedStyle::Brackets,
+        NestedStyle::DeepObject,
+        NestedStyle::Dotted,
+    ] {
+        assert!(!tree.to_query_string(style).contains("a"));
+    }
+    assert_eq!(
+        tree.to_query_string(NestedStyle::Brackets),
+        "b%5Bd%5D%5B%5D=1"
+    );
+    assert_eq!(
+        NestedValue::from_query_string("", NestedStyle::Brackets).unwrap(),
+        node(vec![])
+    );
+}
@@ -0,0 +1,149 @@
+//! Redis stream commands and replies replayed from an in-memory RESP transcript.
+use multidict::resp::{RespValue, StreamEntry, MAX_DEPTH};
+use multidict::MultiDict;
+
+/// Decode every value of `input`, checking that nothing is left over
+fn decode_all(input: &[u8]) -> Vec<RespValue> {
+    let mut position = 0;
+    let mut values = Vec::new();
+    while position < input.len() {
+        let (value, length) = RespValue::decode(&input[position..]).unwrap();
+        values.push(value);
+        position += length;
+    }
+    values
+}
+
+const CLIENT: &[u8] = b"*7\r\n$4\r\nXADD\r\n$6\r\nsensor\r\n$3\r\n1-1\r\n\
+$4\r\ntemp\r\n$2\r\n20\r\n$4\r\ntemp\r\n$2\r\n21\r\n\
+*5\r\n$4\r\nXADD\r\n$6\r\nsensor\r\n$3\r\n1-2\r\n$4\r\nnote\r\n$0\r\n\r\n";
+
+const SERVER: &[u8] = b"$3\r\n1-1\r\n$3\r\n1-2\r\n\
+*2\r\n\
+*2\r\n$3\r\n1-1\r\n*4\r\n$4\r\ntemp\r\n$2\r\n20\r\n$4\r\ntemp\r\n$2\r\n21\r\n\
+*2\r\n$3\r\n1-2\r\n*2\r\n$4\r\nnote\r\n$0\r\n\r\n\
+*-1\r\n";
+
+#[test]
+fn transcript() {
+    let first = [("temp", "20"), ("temp", "21")]
+        .into_iter()
+        .collect::<MultiDict>();
+    let second = [("note", "")].into_iter().collect::<MultiDict>();
+    let mut client = Vec::new();
+    first.to_xadd("sensor", "1-1").unwrap().encode(&mut client);
+    second.to_xadd("sensor", "1-2").unwrap().encode(&mut client);
+    assert_eq!(client, CLIENT);
+
+    let replies = decode_all(SERVER);
+    assert_eq!(replies.len(), 4);
+    assert_eq!(replies[0].as_str(), Ok("1-1"));
+    assert_eq!(replies[1].as_str(), Ok("1-2"));
+    let entries = StreamEntry::from_xrange(&replies[2]).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].id, "1-1");
+    assert_eq!(entries[0].fields.to_string(), first.to_string());
+    assert_eq!(entries[1].fields.to_string(), second.to_string());
+    assert!(StreamEntry::from_xrange(&replies[3]).unwrap().is_empty());
+
+    let mut server = Vec::new();
+    for reply in &replies {
+        reply.encode(&mut server);
+    }
+    assert_eq!(server, SERVER);
+}
+
+#[test]
+fn resp3_map_reply() {
+    let reply = b"%3\r\n+server\r\n+redis\r\n+version\r\n$5\r\n7.2.4\r\n+mode\r\n+standalone\r\n";
+    let (value, length) = RespValue::decode(reply).unwrap();
+    assert_eq!(length, reply.len());
+    assert_eq!(
+        MultiDict::from_resp(&value).unwrap().to_string(),
+        [
+            ("server", "redis"),
+            ("version", "7.2.4"),
+            ("mode", "standalone")
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    assert_eq!(value.to_bytes(), reply);
+
+    let (value, _) = RespValue::decode(b"%1\r\n+proto\r\n:3\r\n").unwrap();
+    assert_eq!(
+        MultiDict::from_resp(&value).unwrap_err(),
+        "RESP value is not a string"
+    );
+}
+
+#[test]
+fn resp3_null_and_binary() {
+    assert_eq!(
+        decode_all(b"_\r\n$-1\r\n"),
+        vec![RespValue::Null, RespValue::BulkString(None)]
+    );
+    assert!(StreamEntry::from_xrange(&RespValue::Null).unwrap().is_empty());
+    let (value, _) = RespValue::decode(b"$4\r\na\r\nb\r\n").unwrap();
+    assert_eq!(value.as_str(), Ok("a\r\nb"));
+    let (value, _) = RespValue::decode(b"*2\r\n$1\r\nk\r\n$1\r\n\xff\r\n").unwrap();
+    assert_eq!(
+        MultiDict::from_resp(&value).unwrap_err(),
+        "RESP string is not valid UTF-8"
+    );
+}
+
+#[test]
+fn nesting_limit() {
+    let nested = |depth: usize| {
+        let mut input = b"*1\r\n".repeat(depth);
+        input.extend_from_slice(b":1\r\n");
+        input
+    };
+    let (value, length) = RespValue::decode(&nested(MAX_DEPTH)).unwrap();
+    assert_eq!(length, MAX_DEPTH * 4 + 4);
+    assert_eq!(value.to_bytes(), nested(MAX_DEPTH));
+    assert_eq!(
+        RespValue::decode(&nested(MAX_DEPTH + 1)).unwrap_err(),
+        "RESP nesting too deep"
+    );
+    // far deeper than the stack could take without the limit
+    assert_eq!(
+        RespValue::decode(&nested(1_000_000)).unwrap_err(),
+        "RESP nesting too deep"
+    );
+    assert_eq!(
+        RespValue::decode(&b"%1\r\n".repeat(200)).unwrap_err(),
+        "RESP nesting too deep"
+    );
+}
+
+#[test]
+fn malformed_values() {
+    for (input, error) in [
+        (&b"*2\r\n$1\r\na\r\n"[..], "Truncated RESP value"),
+        (
+            b"$3\r\nabcd\r\n",
+            "RESP bulk string is not followed by CRLF",
+        ),
+        (b"$x\r\n", "Invalid RESP length"),
+        (b"$18446744073709551615\r\nab\r\n", "Invalid RESP length"),
+        (b"$18446744073709551610\r\nab\r\n", "Invalid RESP length"),
+        (b"$1000\r\nab\r\n", "Truncated RESP value"),
+        (b":1.5\r\n", "Invalid RESP integer"),
+        (b"!3\r\nerr\r\n", "Unsupported RESP type"),
+        (b"+OK", "Truncated RESP value"),
+    ] {
+        assert_eq!(RespValue::decode(input).unwrap_err(), error);
+    }
+    let (odd, _) = RespValue::decode(b"*1\r\n$1\r\na\r\n").unwrap();
+    assert_eq!(
+        MultiDict::from_resp(&odd).unwrap_err(),
+        "RESP field-value array has odd length"
+    );
+    assert_eq!(
+        MultiDict::new().to_xadd("s", "*").unwrap_err(),
+        "Stream entry needs at least one field"
+    );
+}
@@ -0,0 +1,218 @@
+//! SigV4 header signing cases of the AWS signing test suite in
+//! `tests/aws-sig-v4-test-suite` and the OAuth 1.0 example of RFC 5849 section 1.2.
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::json::Json;
+use multidict::signing::{oauth1_base_string, oauth1_hmac_sha1, SigV4};
+use multidict::{MultiDict, MultiElement};
+
+const SUITE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/aws-sig-v4-test-suite");
+const DATETIME: &str = "20150830T123600Z";
+
+fn add(map: &mut MultiDict, key: &str, value: &str) {
+    map.add(MultiElement::new([key.to_string(), value.to_string()]));
+}
+
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            decoded.push(u8::from_str_radix(&path[idx + 1..idx + 3], 16).unwrap());
+            idx += 3;
+        } else {
+            decoded.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(decoded).unwrap()
+}
+
+/// Remove dot segments and empty segments, keeping a trailing slash
+fn normalize(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let trailing = path.ends_with('/') && !segments.is_empty();
+    format!("/{}{}", segments.join("/"), if trailing { "/" } else { "" })
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: MultiDict,
+    headers: MultiDict,
+    body: String,
+}
+
+fn parse_request(text: &str) -> Request {
+    let (head, body) = text.split_once("\n\n").unwrap_or((text, ""));
+    let mut lines = head.lines();
+    let request_line = lines.next().unwrap();
+    let (method, rest) = request_line.split_once(' ').unwrap();
+    let target = rest.strip_suffix(" HTTP/1.1").unwrap();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut headers = MultiDict::new();
+    for line in lines {
+        if line.starts_with([' ', '\t']) {
+            let last = headers.elements.last_mut().unwrap();
+            last.value.push(' ');
+            last.value.push_str(line.trim());
+        } else {
+            let (name, value) = line.split_once(':').unwrap();
+            add(&mut headers, name, value);
+        }
+    }
+    Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: MultiDict::from_query_string(query).unwrap(),
+        headers,
+        body: body.to_string(),
+    }
+}
+
+fn read(case: &Path, name: &str) -> String {
+    fs::read_to_string(case.join(name)).unwrap()
+}
+
+#[test]
+fn aws_sigv4_test_suite() {
+    let mut cases: Vec<_> = fs::read_dir(SUITE)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    cases.sort();
+    assert_eq!(cases.len(), 38);
+    for case in cases {
+        let name = case.file_name().unwrap().to_string_lossy().to_string();
+        let context = Json::parse(&read(&case, "context.json"));
+        let credentials = context.get("credentials").unwrap();
+        let signer = SigV4::new(
+            credentials.get("access_key_id").unwrap().as_str().unwrap(),
+            credentials.get("secret_access_key").unwrap().as_str().unwrap(),
+            context.get("region").unwrap().as_str().unwrap(),
+            context.get("service").unwrap().as_str().unwrap(),
+        );
+        let mut request = parse_request(&read(&case, "request.txt"));
+        add(&mut request.headers, "X-Amz-Date", DATETIME);
+        let omit_token = context.get("omit_session_token") == Some(&Json::Bool(true));
+        if let Some(token) = credentials.get("token") {
+            if !omit_token {
+                add(
+                    &mut request.headers,
+                    "X-Amz-Security-Token",
+                    token.as_str().unwrap(),
+                );
+            }
+        }
+        if context.get("sign_body") == Some(&Json::Bool(true)) {
+            // the payload hash is the last line of a canonical request
+            let empty = MultiDict::new();
+            let hash = SigV4::canonical_request("", "/", &empty, &empty, request.body.as_bytes());
+            let hash = hash.rsplit('\n').next().unwrap().to_string();
+            add(&mut request.headers, "X-Amz-Content-Sha256", &hash);
+        }
+        let mut path = percent_decode(&request.path);
+        if context.get("normalize") == Some(&Json::Bool(true)) {
+            path = normalize(&path);
+        }
+
+        let canonical = SigV4::canonical_request(
+            &request.method,
+            &path,
+            &request.query,
+            &request.headers,
+            request.body.as_bytes(),
+        );
+        assert_eq!(
+            canonical,
+            read(&case, "header-canonical-request.txt"),
+            "{name}"
+        );
+        assert_eq!(
+            signer.string_to_sign(DATETIME, &canonical).unwrap(),
+            read(&case, "header-string-to-sign.txt"),
+            "{name}"
+        );
+        assert_eq!(
+            signer
+                .signature(
+                    &request.method,
+                    &path,
+                    &request.query,
+                    &request.headers,
+                    request.body.as_bytes()
+                )
+                .unwrap(),
+            read(&case, "header-signature.txt"),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn invalid_request_time() {
+    let signer = SigV4::new("AKIDEXAMPLE", "secret", "us-east-1", "service");
+    for datetime in [
+        "20150830T1236Z",
+        "2015-08-30T12:36",
+        "2015083€12360Z",
+        "20150830X123600Z",
+    ] {
+        let mut headers = MultiDict::new();
+        add(&mut headers, "Host", "example.amazonaws.com");
+        add(&mut headers, "X-Amz-Date", datetime);
+        assert_eq!(
+            signer.signature("GET", "/", &MultiDict::new(), &headers, b""),
+            Err("Invalid x-amz-date header"),
+            "{datetime}"
+        );
+        assert_eq!(
+            signer.authorization("GET", "/", &MultiDict::new(), &headers, b""),
+            Err("Invalid x-amz-date header")
+        );
+        assert_eq!(
+            signer.string_to_sign(datetime, ""),
+            Err("Invalid x-amz-date header")
+        );
+    }
+}
+
+#[test]
+fn oauth1_rfc5849_example() {
+    let mut parameters = MultiDict::from_query_string("file=vacation.jpg&size=original").unwrap();
+    for (key, value) in [
+        ("oauth_consumer_key", "dpf43f3p2l4k3l03"),
+        ("oauth_token", "nnch734d00sl2jdk"),
+        ("oauth_signature_method", "HMAC-SHA1"),
+        ("oauth_timestamp", "137131202"),
+        ("oauth_nonce", "chapoH"),
+    ] {
+        add(&mut parameters, key, value);
+    }
+    let base_string = oauth1_base_string("GET", "http://photos.example.net/photos", &parameters);
+    assert_eq!(
+        base_string,
+        "GET&http%3A%2F%2Fphotos.example.net%2Fphotos&file%3Dvacation.jpg\
+         %26oauth_consumer_key%3Ddpf43f3p2l4k3l03%26oauth_nonce%3DchapoH\
+         %26oauth_signature_method%3DHMAC-SHA1%26oauth_timestamp%3D137131202\
+         %26oauth_token%3Dnnch734d00sl2jdk%26size%3Doriginal"
+    );
+    assert_eq!(
+        oauth1_hmac_sha1(&base_string, "kd94hf93k423kf44", "pfkkdhi9sl3r4s00"),
+        "MdpQcU8iPSUjWoN/UDMsK2sui9I="
+    );
+}
@@ -0,0 +1,184 @@
+//! SIP headers of RFC 3261 messages in long and compact form.
+use multidict::metadata::Span;
+use multidict::sip::{canonical_name, compact_name, is_list_header, SipForm, SipHeaders};
+use multidict::MultiDict;
+
+const INVITE: &str = "Via: SIP/2.0/UDP server10.biloxi.com;branch=z9hG4bK4b43c2ff8.1, \
+SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+Via: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+Max-Forwards: 68\r\n\
+To: Bob <sip:bob@biloxi.com>\r\n\
+From: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+Call-ID: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+Contact: <sip:alice@pc33.atlanta.com>\r\n\
+Content-Type: application/sdp\r\n\
+Content-Length: 142\r\n\
+\r\n";
+
+const COMPACT_INVITE: &str = "v: SIP/2.0/UDP server10.biloxi.com;branch=z9hG4bK4b43c2ff8.1\r\n\
+v: SIP/2.0/UDP bigbox3.site3.atlanta.com;branch=z9hG4bK77ef4c2312983.1\r\n\
+v: SIP/2.0/UDP pc33.atlanta.com;branch=z9hG4bK776asdhds\r\n\
+Max-Forwards: 68\r\n\
+t: Bob <sip:bob@biloxi.com>\r\n\
+f: Alice <sip:alice@atlanta.com>;tag=1928301774\r\n\
+i: a84b4c76e66710\r\n\
+CSeq: 314159 INVITE\r\n\
+m: <sip:alice@pc33.atlanta.com>\r\n\
+c: application/sdp\r\n\
+l: 142\r\n";
+
+#[test]
+fn names() {
+    for (compact, long) in [
+        ("v", "Via"),
+        ("f", "From"),
+        ("t", "To"),
+        ("m", "Contact"),
+        ("i", "Call-ID"),
+        ("l", "Content-Length"),
+        ("k", "Supported"),
+    ] {
+        assert_eq!(canonical_name(compact), long);
+        assert_eq!(canonical_name(&compact.to_uppercase()), long);
+        assert_eq!(canonical_name(&long.to_lowercase()), long);
+        assert_eq!(compact_name(long), Some(compact));
+    }
+    assert_eq!(canonical_name("www-authenticate"), "WWW-Authenticate");
+    assert_eq!(canonical_name("x-unknown"), "X-Unknown");
+    assert_eq!(canonical_name("P-ASSERTED-identity"), "P-Asserted-Identity");
+    assert_eq!(compact_name("Max-Forwards"), None);
+    assert!(is_list_header("v"));
+    assert!(!is_list_header("From"));
+}
+
+#[test]
+fn parse_and_serialize() {
+    let parsed = SipHeaders::from_sip(INVITE).unwrap();
+    assert_eq!(parsed.getall("v").len(), 3);
+    assert_eq!(parsed.get("VIA"), parsed.getall("Via").first().copied());
+    assert_eq!(
+        parsed.get("f"),
+        Some("Alice <sip:alice@atlanta.com>;tag=1928301774")
+    );
+    assert_eq!(parsed.get("l"), Some("142"));
+    assert_eq!(parsed.to_sip(SipForm::Compact), COMPACT_INVITE);
+
+    let compact = SipHeaders::from_sip(COMPACT_INVITE).unwrap();
+    assert_eq!(compact.to_string(), parsed.to_string());
+    assert_eq!(
+        SipHeaders::from_sip(&compact.to_sip(SipForm::Long))
+            .unwrap()
+            .to_string(),
+        parsed.to_string()
+    );
+}
+
+#[test]
+fn extension_headers_ignore_case() {
+    let parsed =
+        SipHeaders::from_sip("X-Custom: 1\r\nx-CUSTOM: 2\r\nP-Charging-Vector: icid-value=1\r\n")
+            .unwrap();
+    assert_eq!(parsed.get("x-custom"), Some("1"));
+    assert_eq!(parsed.getall("X-CUSTOM"), vec!["1", "2"]);
+    assert_eq!(parsed.get("p-charging-vector"), Some("icid-value=1"));
+    assert!(parsed.contains("x-Custom"));
+    assert_eq!(
+        parsed.to_sip(SipForm::Long),
+        "X-Custom: 1\r\nX-Custom: 2\r\nP-Charging-Vector: icid-value=1\r\n"
+    );
+}
+
+#[test]
+fn list_splitting() {
+    let mut parsed = SipHeaders::new();
+    parsed.add(
+        "Contact",
+        r#""Watson, Thomas" <sip:watson@worcester.example.com>;q=0.7, <sip:a@b,c.example.com>,
+        "Quote \" , inside" <sip:q@example.com>"#,
+    );
+    parsed.add("k", "100rel,timer , path");
+    parsed.add("Subject", "Lunch, maybe?");
+    parsed.add(
+        "Authorization",
+        r#"Digest username="bob", realm="biloxi.com""#,
+    );
+    assert_eq!(
+        parsed.headers().to_string(),
+        [
+            (
+                "Contact",
+                r#""Watson, Thomas" <sip:watson@worcester.example.com>;q=0.7"#
+            ),
+            ("Contact", "<sip:a@b,c.example.com>"),
+            ("Contact", r#""Quote \" , inside" <sip:q@example.com>"#),
+            ("Supported", "100rel"),
+            ("Supported", "timer"),
+            ("Supported", "path"),
+            ("Subject", "Lunch, maybe?"),
+            (
+                "Authorization",
+                r#"Digest username="bob", realm="biloxi.com""#
+            ),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    assert!(parsed.remove("s"));
+    assert!(!parsed.contains("Subject"));
+}
+
+#[test]
+fn folding_and_errors() {
+    let parsed =
+        SipHeaders::from_sip("Subject: I know you're there,\n\tpick up the phone\n").unwrap();
+    assert_eq!(
+        parsed.get("s"),
+        Some("I know you're there, pick up the phone")
+    );
+    assert_eq!(
+        SipHeaders::from_sip(" folded: value\r\n").unwrap_err(),
+        "SIP header continues nothing"
+    );
+    assert_eq!(
+        SipHeaders::from_sip("Via SIP/2.0/UDP host\r\n").unwrap_err(),
+        "SIP header has no colon"
+    );
+    assert_eq!(
+        SipHeaders::from_sip("Bad Name: value\r\n").unwrap_err(),
+        "Invalid SIP header name"
+    );
+}
+
+#[test]
+fn spans_of_header_lines() {
+    let message = "Via: SIP/2.0/UDP a.example.com, SIP/2.0/TCP b.example.com\r\n\
+                   Max-Forwards: seventy\r\n\
+                   Subject: lunch\r\n \
+                   today\r\n\r\nbody";
+    let headers: SipHeaders<Span> = SipHeaders::from_sip_spanned(message).unwrap();
+    let invalid = headers
+        .headers()
+        .iter()
+        .find(|item| item.key == "Max-Forwards")
+        .unwrap();
+    assert!(invalid.value.parse::<u8>().is_err());
+    assert_eq!(invalid.meta, Some(Span { start: 59, end: 80 }));
+    assert_eq!(
+        invalid.meta.unwrap().slice(message),
+        "Max-Forwards: seventy"
+    );
+    // split values share the span of their line, folded lines extend it
+    let spans: Vec<&str> = headers
+        .headers()
+        .iter()
+        .map(|item| item.meta.unwrap().slice(message))
+        .collect();
+    assert_eq!(spans[0], spans[1]);
+    assert_eq!(spans[3], "Subject: lunch\r\n today");
+    assert_eq!(
+        SipHeaders::from_sip(message).unwrap().to_string(),
+        headers.to_string()
+    );
+}
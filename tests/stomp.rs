@@ -0,0 +1,147 @@
+//! STOMP 1.2 frames against byte fixtures.
+use multidict::metadata::Span;
+use multidict::stomp::StompFrame;
+use multidict::MultiDict;
+
+#[test]
+fn repeated_headers_first_wins() {
+    let input = b"MESSAGE\r\nfoo:World\r\nfoo:Hello\r\n\r\n\0";
+    let (frame, length) = StompFrame::from_bytes(input).unwrap();
+    assert_eq!(length, input.len());
+    assert_eq!(frame.get("foo"), Some("World"));
+    assert_eq!(
+        frame.headers.to_string(),
+        [("foo", "World"), ("foo", "Hello")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(
+        frame.to_bytes().unwrap(),
+        b"MESSAGE\nfoo:World\nfoo:Hello\n\n\0"
+    );
+}
+
+#[test]
+fn escapes() {
+    let input = b"MESSAGE\nkey\\cname:a\\\\b\\nc\\rd:e\n\n\0";
+    let (frame, _) = StompFrame::from_bytes(input).unwrap();
+    assert_eq!(
+        frame.headers.to_string(),
+        [("key:name", "a\\b\nc\rd:e")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_string()
+    );
+    assert_eq!(
+        frame.to_bytes().unwrap(),
+        b"MESSAGE\nkey\\cname:a\\\\b\\nc\\rd\\ce\n\n\0"
+    );
+    assert_eq!(
+        StompFrame::from_bytes(b"MESSAGE\nkey:a\\tb\n\n\0").unwrap_err(),
+        "Undefined STOMP header escape"
+    );
+}
+
+#[test]
+fn connect_frames_are_not_escaped() {
+    let input = b"CONNECT\naccept-version:1.2\nhost:stomp.example.com\npasscode:a\\c:b\n\n\0";
+    let (frame, _) = StompFrame::from_bytes(input).unwrap();
+    assert_eq!(frame.get("passcode"), Some("a\\c:b"));
+    assert_eq!(frame.to_bytes().unwrap(), input);
+
+    let mut connected = StompFrame::new("CONNECTED");
+    connected.add_header("session", "line\nbreak");
+    assert_eq!(
+        connected.to_bytes().unwrap_err(),
+        "Invalid header in unescaped STOMP frame"
+    );
+}
+
+#[test]
+fn content_length_bodies() {
+    let mut frame = StompFrame::new("SEND");
+    frame.add_header("destination", "/queue/binary");
+    frame.body = vec![1, 0, 2, 0];
+    assert_eq!(
+        frame.to_bytes().unwrap_err(),
+        "STOMP body with NUL needs content-length"
+    );
+    frame.add_header("content-length", "3");
+    assert_eq!(
+        frame.to_bytes().unwrap_err(),
+        "STOMP content-length does not match body"
+    );
+    frame.headers.elements.pop();
+    frame.add_header("content-length", "4");
+    frame.add_header("content-length", "99");
+
+    let encoded = frame.to_bytes().unwrap();
+    let (decoded, length) = StompFrame::from_bytes(&encoded).unwrap();
+    assert_eq!(length, encoded.len());
+    assert_eq!(decoded.body, frame.body);
+    assert_eq!(decoded.headers.to_string(), frame.headers.to_string());
+    assert_eq!(decoded.to_bytes().unwrap(), encoded);
+}
+
+#[test]
+fn frame_stream() {
+    let input = b"\n\r\nMESSAGE\nid:1\n\nfirst\0\nMESSAGE\nid:2\ncontent-length:6\n\nsecond\0\n";
+    let mut position = 0;
+    let mut bodies = Vec::new();
+    while input[position..]
+        .iter()
+        .any(|byte| *byte != b'\n' && *byte != b'\r')
+    {
+        let (frame, length) = StompFrame::from_bytes(&input[position..]).unwrap();
+        bodies.push((frame.get("id").unwrap().to_string(), frame.body));
+        position += length;
+    }
+    assert_eq!(
+        bodies,
+        vec![
+            ("1".to_string(), b"first".to_vec()),
+            ("2".to_string(), b"second".to_vec())
+        ]
+    );
+}
+
+#[test]
+fn malformed_frames() {
+    for (input, error) in [
+        (&b"MESSAGE\nid:1\n"[..], "Truncated STOMP frame"),
+        (b"MESSAGE\nid\n\n\0", "STOMP frame header has no colon"),
+        (b"MESSAGE\n\nbody", "Truncated STOMP frame"),
+        (
+            b"MESSAGE\ncontent-length:2\n\nabc\0",
+            "STOMP frame body is not followed by NUL",
+        ),
+        (
+            b"MESSAGE\ncontent-length:x\n\n\0",
+            "Invalid STOMP content-length",
+        ),
+    ] {
+        assert_eq!(StompFrame::from_bytes(input).unwrap_err(), error);
+    }
+}
+
+#[test]
+fn spans_of_header_lines() {
+    let input = b"\nSUBSCRIBE\r\nid:0\r\ndestination:/queue/a\\cb\r\nack:sometimes\r\n\r\n\0";
+    let (frame, _): (StompFrame<Span>, _) = StompFrame::from_bytes_spanned(input).unwrap();
+    let invalid = frame
+        .headers
+        .iter()
+        .find(|item| {
+            item.key == "ack"
+                && !["auto", "client", "client-individual"].contains(&item.value.as_str())
+        })
+        .unwrap();
+    assert_eq!(invalid.meta, Some(Span { start: 43, end: 56 }));
+    assert_eq!(&input[43..56], b"ack:sometimes");
+    // spans cover the escaped header as written
+    let destination = frame.headers.get("destination").unwrap();
+    let span = destination.meta.unwrap();
+    assert_eq!(&input[span.start..span.end], b"destination:/queue/a\\cb");
+    assert_eq!(destination.value, "/queue/a:b");
+}
@@ -0,0 +1,115 @@
+//! Git commit trailers of sample commit messages.
+use multidict::metadata::Span;
+use multidict::trailers::split_trailers;
+use multidict::MultiDict;
+
+const COMMIT: &str = "net: fix use-after-free in socket teardown
+
+The socket could be released while a timer still held a reference:
+see https://example.com/bug for details.
+
+Fixes: 0123456789ab (\"net: add socket timers\")
+Reported-by: Dana <dana@example.com>
+Signed-off-by: Alice <alice@example.com>
+Link: https://lore.example.com/r/20240101.1234
+ -abcdef@example.com
+Signed-off-by: Bob <bob@example.com>
+";
+
+#[test]
+fn commit_trailers() {
+    let (body, trailers) = split_trailers(COMMIT);
+    assert!(body.ends_with("for details."));
+    assert_eq!(
+        trailers.to_string(),
+        [
+            ("Fixes", "0123456789ab (\"net: add socket timers\")"),
+            ("Reported-by", "Dana <dana@example.com>"),
+            ("Signed-off-by", "Alice <alice@example.com>"),
+            (
+                "Link",
+                "https://lore.example.com/r/20240101.1234 -abcdef@example.com"
+            ),
+            ("Signed-off-by", "Bob <bob@example.com>"),
+        ]
+        .into_iter()
+        .collect::<MultiDict>()
+        .to_string()
+    );
+    assert_eq!(
+        MultiDict::from_git_trailers(COMMIT).to_string(),
+        trailers.to_string()
+    );
+}
+
+#[test]
+fn paragraphs_without_trailers() {
+    for message in [
+        "Signed-off-by: only a title\n",
+        "Title\n\nSome prose: with a colon\nSigned-off-by: Alice\n",
+        "Title\n\n continuation first\n",
+        "",
+    ] {
+        assert!(
+            MultiDict::from_git_trailers(message).is_empty(),
+            "{message}"
+        );
+    }
+    let (body, _) = split_trailers("Title\r\n\r\nBody\r\n\r\nAcked-by: A\r\n");
+    assert_eq!(body, "Title\r\n\r\nBody");
+}
+
+#[test]
+fn append_trailers() {
+    let added = [("Signed-off-by", "Carol <carol@example.com>")]
+        .into_iter()
+        .collect::<MultiDict>();
+    let message = added.append_git_trailers(COMMIT).unwrap();
+    assert_eq!(
+        message,
+        format!("{COMMIT}Signed-off-by: Carol <carol@example.com>\n")
+    );
+    assert_eq!(MultiDict::from_git_trailers(&message).len(), 6);
+    assert_eq!(
+        added.append_git_trailers("Title only").unwrap(),
+        "Title only\n\nSigned-off-by: Carol <carol@example.com>\n"
+    );
+    assert_eq!(
+        [("Bad token", "x")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_git_trailers()
+            .unwrap_err(),
+        "Invalid trailer token"
+    );
+    assert_eq!(
+        [("Note", "a\nb")]
+            .into_iter()
+            .collect::<MultiDict>()
+            .to_git_trailers()
+            .unwrap_err(),
+        "Trailer value contains a line break"
+    );
+}
+
+#[test]
+fn spans_of_trailers() {
+    let message = "Fix parser\r\n\r\nBody.\r\n\r\nFixes: not-a-hash\r\nCc: Alice\r\n  <alice@example.com>\r\n";
+    let trailers: MultiDict<Span> = MultiDict::from_git_trailers_spanned(message);
+    let invalid = trailers
+        .iter()
+        .find(|item| {
+            item.key == "Fixes" && !item.value.bytes().all(|byte| byte.is_ascii_hexdigit())
+        })
+        .unwrap();
+    assert_eq!(invalid.meta, Some(Span { start: 23, end: 40 }));
+    assert_eq!(invalid.meta.unwrap().slice(message), "Fixes: not-a-hash");
+    assert_eq!(
+        trailers.get("Cc").unwrap().meta.unwrap().slice(message),
+        "Cc: Alice\r\n  <alice@example.com>"
+    );
+    assert_eq!(
+        trailers.to_string(),
+        MultiDict::from_git_trailers(message).to_string()
+    );
+}
@@ -0,0 +1,193 @@
+//! The uritemplate-test suite in `tests/uritemplate-test`. Associative array variables
+//! are given as `name[field]` keys, a `false` result means the template must fail.
+mod common;
+
+use common::json::Json;
+use multidict::uri_template::UriTemplate;
+use multidict::MultiDict;
+
+const SUITE: &[(&str, &str)] = &[
+    (
+        "spec-examples.json",
+        include_str!("uritemplate-test/spec-examples.json"),
+    ),
+    (
+        "spec-examples-by-section.json",
+        include_str!("uritemplate-test/spec-examples-by-section.json"),
+    ),
+    (
+        "extended-tests.json",
+        include_str!("uritemplate-test/extended-tests.json"),
+    ),
+    (
+        "negative-tests.json",
+        include_str!("uritemplate-test/negative-tests.json"),
+    ),
+];
+
+struct Case {
+    group: String,
+    template: String,
+    variables: MultiDict,
+    /// Accepted expansions, empty if the template must fail
+    expected: Vec<String>,
+    /// True if a variable used by the template is an associative array
+    associative: bool,
+}
+
+fn variables(group: &Json) -> MultiDict {
+    let Some(Json::Object(members)) = group.get("variables") else {
+        panic!("group without variables");
+    };
+    members
+        .iter()
+        .flat_map(|(name, value)| -> Vec<(String, String)> {
+            match value {
+                Json::Null => Vec::new(),
+                Json::Array(items) => {
+                    items.iter().map(|item| (name.clone(), item.scalar())).collect()
+                }
+                Json::Object(pairs) => pairs
+                    .iter()
+                    .map(|(field, item)| (format!("{name}[{field}]"), item.scalar()))
+                    .collect(),
+                other => vec![(name.clone(), other.scalar())],
+            }
+        })
+        .collect()
+}
+
+fn cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+    for (file, text) in SUITE {
+        let Json::Object(groups) = Json::parse(text) else {
+            panic!("{file} is not an object");
+        };
+        for (name, group) in groups {
+            let variables = variables(&group);
+            let associative: Vec<String> = match group.get("variables") {
+                Some(Json::Object(members)) => members
+                    .iter()
+                    .filter(|(_, value)| matches!(value, Json::Object(_)))
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for case in group.get("testcases").unwrap().as_array().unwrap() {
+                let [template, expected] = case.as_array().unwrap() else {
+                    panic!("{file} {name}: malformed test case");
+                };
+                let template = template.as_str().unwrap().to_string();
+                let expected = match expected {
+                    Json::String(value) => vec![value.clone()],
+                    Json::Array(values) => values
+                        .iter()
+                        .map(|value| value.as_str().unwrap().to_string())
+                        .collect(),
+                    Json::Bool(false) => Vec::new(),
+                    other => panic!("{file} {name}: unexpected result {other:?}"),
+                };
+                cases.push(Case {
+                    group: format!("{file} {name}"),
+                    associative: associative
+                        .iter()
+                        .any(|variable| template.contains(variable.as_str())),
+                    template,
+                    variables: variables.clone(),
+                    expected,
+                });
+            }
+        }
+    }
+    cases
+}
+
+#[test]
+fn suite_expand() {
+    let cases = cases();
+    assert_eq!(cases.len(), 234);
+    for case in cases {
+        let expanded = UriTemplate::new(&case.template).and_then(|t| t.expand(&case.variables));
+        match expanded {
+            Ok(uri) => assert!(
+                case.expected.contains(&uri),
+                "{}: {} expanded to {uri}, expected one of {:?}",
+                case.group,
+                case.template,
+                case.expected
+            ),
+            Err(error) => assert!(
+                case.expected.is_empty(),
+                "{}: {} failed with {error}",
+                case.group,
+                case.template
+            ),
+        }
+    }
+}
+
+#[test]
+fn suite_match() {
+    let mut matched_cases = 0;
+    for case in cases() {
+        // Associative arrays come back as plain keys, templates using one variable twice
+        // can not be inverted
+        let names: Vec<&str> = case
+            .template
+            .split(['{', '}', ',', '*', ':', '+', '#', '.', '/', ';', '?', '&'])
+            .filter(|name| case.variables.contains(name))
+            .collect();
+        let repeated = names
+            .iter()
+            .enumerate()
+            .any(|(idx, name)| names[..idx].contains(name));
+        if case.expected.is_empty() || case.associative || repeated {
+            continue;
+        }
+        let template = UriTemplate::new(&case.template).unwrap();
+        let uri = &case.expected[0];
+        let matched = template
+            .match_uri(uri)
+            .unwrap_or_else(|| panic!("{}: {uri} does not match {}", case.group, case.template));
+        // Matching is not always exact (e.g. `{x,hello,y}` splits ambiguously),
+        // but expanding matched variables must reproduce the URI
+        assert_eq!(
+            template.expand(&matched).as_ref(),
+            Ok(uri),
+            "{}: {}",
+            case.group,
+            case.template
+        );
+        matched_cases += 1;
+    }
+    assert_eq!(matched_cases, 165);
+}
+
+#[test]
+fn adjacent_variables_match_in_polynomial_time() {
+    let template: String = ('a'..='t').map(|name| format!("{{{name}}}")).collect();
+    let template = UriTemplate::new(&format!("{template}/end")).unwrap();
+    assert!(template.match_uri(&"x".repeat(100)).is_none());
+    let matched = template.match_uri(&format!("{}/end", "x".repeat(40))).unwrap();
+    assert_eq!(
+        matched.values().into_iter().cloned().collect::<String>(),
+        "x".repeat(40)
+    );
+}
+
+#[test]
+fn invalid_templates() {
+    for template in [
+        "{",
+        "}",
+        "{var",
+        "{=var}",
+        "{var:0}",
+        "{var:10000}",
+        "{a..b}",
+        "{a b}",
+        "{}",
+    ] {
+        assert!(UriTemplate::new(template).is_err(), "template {template}");
+    }
+}
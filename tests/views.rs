@@ -0,0 +1,71 @@
+//! Set views and multiset operations, e.g. query parameters shared by two requests.
+use multidict::MultiDict;
+
+fn query(input: &str) -> MultiDict {
+    MultiDict::from_query_string(input).unwrap()
+}
+
+#[test]
+fn shared_query_parameters() {
+    let first = query("q=rust&page=2&tag=web&tag=cli&utm_source=mail");
+    let second = query("q=rust&page=3&tag=cli&tag=web&tag=cli&lang=en");
+
+    let keys = &first.keys_view() & &second.keys_view();
+    assert_eq!(
+        keys.into_iter().collect::<Vec<_>>(),
+        vec!["q", "page", "tag"]
+    );
+    let items = &first.items_view() & &second.items_view();
+    assert_eq!(
+        items.into_iter().collect::<Vec<_>>(),
+        vec![("q", "rust"), ("tag", "web"), ("tag", "cli")]
+    );
+    assert_eq!(
+        (&first.keys_view() ^ &second.keys_view())
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["utm_source", "lang"]
+    );
+    assert!(first.keys_view().contains(&"utm_source"));
+    assert!((&first.keys_view() - &second.keys_view()).is_subset(&first.keys_view()));
+    assert!(query("a=1").keys_view().is_disjoint(&first.keys_view()));
+}
+
+#[test]
+fn view_algebra() {
+    let first = query("a=1&b=2&a=1&c=3");
+    let second = query("b=2&d=4&a=9");
+    let (left, right) = (first.items_view(), second.items_view());
+    let union = &left | &right;
+    assert_eq!(union.len(), 5);
+    assert_eq!(
+        (&union - &(&left & &right)),
+        &(&left - &right) | &(&right - &left)
+    );
+    assert_eq!(&left ^ &right, &(&left - &right) | &(&right - &left));
+    assert!((&left & &right).is_subset(&left));
+    assert_eq!((&left & &left), left);
+}
+
+#[test]
+fn multiset_operations_respect_counts() {
+    let first = query("tag=a&tag=a&tag=a&tag=b&q=1");
+    let second = query("tag=b&tag=a&tag=b&q=1&q=1");
+
+    assert_eq!((&first & &second).to_query_string(), "tag=a&tag=b&q=1");
+    assert_eq!(
+        (&first | &second).to_query_string(),
+        "tag=a&tag=a&tag=a&tag=b&q=1&tag=b&q=1"
+    );
+    assert_eq!((&first - &second).to_query_string(), "tag=a&tag=a");
+    assert_eq!((&second - &first).to_query_string(), "tag=b&q=1");
+    assert_eq!(
+        (&first ^ &second).to_query_string(),
+        "tag=a&tag=a&tag=b&q=1"
+    );
+    // |A| + |B| = |A & B| + |A | B|
+    assert_eq!(
+        first.len() + second.len(),
+        (&first & &second).len() + (&first | &second).len()
+    );
+}